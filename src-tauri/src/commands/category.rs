@@ -1,48 +1,98 @@
-use crate::models::Category;
-use crate::db::get_db_connection;
+use crate::models::{Category, CategoryDeleteMode, CategoryDeleteSummary, EditAction, FolderSyncSummary, WatchedFolder};
+use crate::commands::file::next_sort_order;
+use crate::db::DbState;
+use crate::file_watcher::FileWatcher;
+use crate::folder_category::{row_to_folder, sync_category, FolderRule};
+use crate::icon_cache::{intern_icon, prune_unused_icons};
+use crate::journal::Recorder;
+use crate::thumbnail::prune_stale_thumbnails;
+use crate::utils::now_millis;
+use rusqlite::{Connection, OptionalExtension, Row, Transaction};
+use tauri::{AppHandle, Manager, State};
+
+// 文件夹分类的包含和排除规则以 JSON 数组保存，没有规则时保存为空
+fn patterns_json(patterns: &[String]) -> Option<String> {
+    (!patterns.is_empty())
+        .then(|| serde_json::to_string(patterns).ok())
+        .flatten()
+}
+
+// categories 表查询时使用的列顺序，与 row_to_category 保持一致
+const CATEGORY_COLUMNS: &str = "id, parent_id, name, icon, sort_order, folder_dir, folder_include, folder_exclude, folder_depth, deleted_at";
+
+// 将查询结果行转换为 Category
+pub fn row_to_category(row: &Row) -> rusqlite::Result<Category> {
+    Ok(Category {
+        id: row.get(0)?,
+        parent_id: row.get(1)?,
+        name: row.get(2)?,
+        icon: row.get(3)?,
+        sort_order: row.get(4)?,
+        folder: row_to_folder(row.get(5)?, row.get(6)?, row.get(7)?, row.get(8)?),
+        deleted_at: row.get(9)?,
+    })
+}
+
+// 回收站中的分类，最近删除的在前
+pub fn query_trashed_categories(conn: &Connection) -> Result<Vec<Category>, String> {
+    let mut stmt = conn
+        .prepare_cached(&format!("SELECT {} FROM categories WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, sort_order", CATEGORY_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_category).map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
 
 // 保存分类列表到SQLite数据库
 #[tauri::command]
-pub fn save_categories_to_db(app: tauri::AppHandle, categories: Vec<Category>) -> Result<(), String> {
-    let mut conn = get_db_connection(&app)?;
+pub fn save_categories_to_db(db: State<'_, DbState>, categories: Vec<Category>) -> Result<(), String> {
+    let mut conn = db.conn()?;
     let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let mut recorder = Recorder::new(EditAction::SaveCategories);
+    recorder.categories_where(&tx, "SELECT id FROM categories WHERE deleted_at IS NULL", [])?;
+    recorder.categories(&tx, categories.iter().map(|category| &category.id))?;
     
-    tx.execute("DELETE FROM categories", []).map_err(|e| e.to_string())?;
+    // 回收站中的分类不在前端的列表中，保持不变
+    tx.execute("DELETE FROM categories WHERE deleted_at IS NULL", []).map_err(|e| e.to_string())?;
     
-    let mut stmt = tx.prepare(
-        "INSERT OR REPLACE INTO categories (id, parent_id, name, icon, sort_order) VALUES (?, ?, ?, ?, ?)"
+    let mut stmt = tx.prepare_cached(
+        "INSERT OR REPLACE INTO categories (id, parent_id, name, icon, sort_order, folder_dir, folder_include, folder_exclude, folder_depth) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
     ).map_err(|e| e.to_string())?;
 
     for category in categories {
+        // 内联的 base64 图标转存到图标缓存
+        let icon = match &category.icon {
+            Some(icon) => Some(intern_icon(&tx, icon).map_err(|e| e.to_string())?),
+            None => None,
+        };
+        let folder = category.folder.as_ref();
         stmt.execute(rusqlite::params![
             &category.id, 
             &category.parent_id, 
             &category.name, 
-            &category.icon, 
-            category.sort_order
+            &icon, 
+            category.sort_order,
+            folder.map(|f| &f.dir),
+            folder.and_then(|f| patterns_json(&f.include)),
+            folder.and_then(|f| patterns_json(&f.exclude)),
+            folder.map_or(1, |f| f.depth)
         ]).map_err(|e| e.to_string())?;
     }
     
     drop(stmt);
+    recorder.finish(&tx)?;
+    prune_unused_icons(&tx).map_err(|e| e.to_string())?;
     tx.commit().map_err(|e| e.to_string())?;
     Ok(())
 }
 
 // 从SQLite数据库读取分类列表
 #[tauri::command]
-pub fn load_categories_from_db(app: tauri::AppHandle) -> Result<Vec<Category>, String> {
-    let conn = get_db_connection(&app)?;
-    let mut stmt = conn.prepare("SELECT id, parent_id, name, icon, sort_order FROM categories ORDER BY sort_order ASC").map_err(|e| e.to_string())?;
+pub fn load_categories_from_db(db: State<'_, DbState>) -> Result<Vec<Category>, String> {
+    let conn = db.conn()?;
+    let mut stmt = conn.prepare_cached(&format!("SELECT {} FROM categories WHERE deleted_at IS NULL ORDER BY sort_order ASC", CATEGORY_COLUMNS)).map_err(|e| e.to_string())?;
     
-    let categories_iter = stmt.query_map([], |row| {
-        Ok(Category {
-            id: row.get(0)?,
-            parent_id: row.get(1)?,
-            name: row.get(2)?,
-            icon: row.get(3)?,
-            sort_order: row.get(4)?,
-        })
-    }).map_err(|e| e.to_string())?;
+    let categories_iter = stmt.query_map([], row_to_category).map_err(|e| e.to_string())?;
     
     let mut categories = Vec::new();
     for category in categories_iter {
@@ -54,28 +104,394 @@ pub fn load_categories_from_db(app: tauri::AppHandle) -> Result<Vec<Category>, S
 
 // 更新分类名称
 #[tauri::command]
-pub fn rename_category_in_db(app: tauri::AppHandle, id: String, new_name: String) -> Result<(), String> {
-    let conn = get_db_connection(&app)?;
-    conn.execute(
+pub fn rename_category_in_db(db: State<'_, DbState>, id: String, new_name: String) -> Result<(), String> {
+    let mut conn = db.conn()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut recorder = Recorder::new(EditAction::RenameCategory);
+    recorder.categories(&tx, [&id])?;
+    tx.execute(
         "UPDATE categories SET name = ? WHERE id = ?",
         [&new_name, &id]
     ).map_err(|e| e.to_string())?;
+    recorder.finish(&tx)?;
+    tx.commit().map_err(|e| e.to_string())?;
     Ok(())
 }
 
-// 删除分类及其关联的文件
+// 分类及其所有子分类，UNION 去重，parent_id 出现环时也能结束
+fn category_subtree(conn: &Connection, id: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT ?
+                UNION
+                SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id
+            )
+            SELECT id FROM subtree",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+// 删除分类，mode 决定分类中的项目和子分类如何处理
+// 永久删除时回收站中属于这些分类的项目也一起删除，不会留下指向不存在分类的项目
+fn delete_category(tx: &Transaction, id: &str, mode: CategoryDeleteMode) -> Result<CategoryDeleteSummary, String> {
+    // 主分类是没有分类的项目的归宿，不能删除
+    if id == "main" {
+        return Err("Cannot delete the main category".to_string());
+    }
+
+    let parent_id: Option<String> = tx
+        .prepare_cached("SELECT parent_id FROM categories WHERE id = ? AND deleted_at IS NULL")
+        .map_err(|e| e.to_string())?
+        .query_row([id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Category not found: {}", id))?;
+
+    // 各种方式都只修改分类子树中的分类和项目
+    let subtree = category_subtree(tx, id)?;
+    let mut recorder = Recorder::new(EditAction::DeleteCategory);
+    recorder.categories(tx, &subtree)?;
+    for category in &subtree {
+        recorder.files_where(tx, "SELECT id FROM files WHERE category = ?", [category])?;
+    }
+
+    let mut summary = CategoryDeleteSummary::default();
+    match mode {
+        CategoryDeleteMode::MoveToParent | CategoryDeleteMode::MoveToMain => {
+            let target = match (mode, &parent_id) {
+                (CategoryDeleteMode::MoveToParent, Some(parent)) => parent.clone(),
+                _ => "main".to_string(),
+            };
+            if target == id {
+                return Err(format!("Cannot move items of category {} into itself", id));
+            }
+
+            // 按原来的顺序追加到目标分类末尾，回收站中的项目留在回收站，恢复时放到主分类
+            let items: Vec<String> = {
+                let mut stmt = tx
+                    .prepare_cached("SELECT id FROM files WHERE category = ? AND deleted_at IS NULL ORDER BY sort_order")
+                    .map_err(|e| e.to_string())?;
+                let rows = stmt
+                    .query_map([id], |row| row.get(0))
+                    .map_err(|e| e.to_string())?;
+                rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+            };
+            let start = next_sort_order(tx, &target)?;
+            for (offset, item) in items.iter().enumerate() {
+                summary.moved_items += tx
+                    .prepare_cached("UPDATE files SET category = ?, sort_order = ? WHERE id = ?")
+                    .and_then(|mut stmt| stmt.execute(rusqlite::params![&target, start + offset as i64, item]))
+                    .map_err(|e| e.to_string())?;
+            }
+
+            summary.reparented_categories = tx
+                .execute(
+                    "UPDATE categories SET parent_id = ? WHERE parent_id = ?",
+                    rusqlite::params![&parent_id, id],
+                )
+                .map_err(|e| e.to_string())?;
+            tx.execute("DELETE FROM categories WHERE id = ?", [id])
+                .map_err(|e| e.to_string())?;
+            summary.deleted_categories.push(id.to_string());
+            summary.target_category = Some(target);
+        }
+        CategoryDeleteMode::Trash => {
+            let now = now_millis();
+            for category in &subtree {
+                summary.trashed_items += tx
+                    .execute(
+                        "UPDATE files SET deleted_at = ? WHERE category = ? AND deleted_at IS NULL",
+                        rusqlite::params![now, category],
+                    )
+                    .map_err(|e| e.to_string())?;
+                // 之前已经移到回收站的子分类保留原来的删除时间
+                tx.execute(
+                    "UPDATE categories SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL",
+                    rusqlite::params![now, category],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            summary.deleted_categories = subtree;
+        }
+        CategoryDeleteMode::Cascade => {
+            for category in &subtree {
+                summary.deleted_items += tx
+                    .execute("DELETE FROM files WHERE category = ?", [category])
+                    .map_err(|e| e.to_string())?;
+                tx.execute("DELETE FROM categories WHERE id = ?", [category])
+                    .map_err(|e| e.to_string())?;
+            }
+            summary.deleted_categories = subtree;
+        }
+    }
+    recorder.finish(tx)?;
+
+    prune_unused_icons(tx).map_err(|e| e.to_string())?;
+    prune_stale_thumbnails(tx).map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+// 删除分类，整个操作在一个事务中完成，未指定 mode 时项目移到上级分类
 #[tauri::command]
-pub fn delete_category_from_db(app: tauri::AppHandle, id: String) -> Result<(), String> {
-    let mut conn = get_db_connection(&app)?;
+pub fn delete_category_from_db(
+    db: State<'_, DbState>,
+    watcher: State<'_, FileWatcher>,
+    id: String,
+    mode: Option<CategoryDeleteMode>,
+) -> Result<CategoryDeleteSummary, String> {
+    let mut conn = db.conn()?;
     let tx = conn.transaction().map_err(|e| e.to_string())?;
-    
-    // 删除分类
-    tx.execute("DELETE FROM categories WHERE id = ?", [&id]).map_err(|e| e.to_string())?;
-    
-    // 删除该分类下的所有文件
-    tx.execute("DELETE FROM files WHERE category = ?", [&id]).map_err(|e| e.to_string())?;
-    
+    let summary = delete_category(&tx, &id, mode.unwrap_or_default())?;
     tx.commit().map_err(|e| e.to_string())?;
-    println!("Category {} and its files deleted from DB.", id);
-    Ok(())
+    watcher.sync(&conn);
+
+    println!(
+        "Category {} deleted: {} categories, {} items moved, {} deleted, {} trashed",
+        id,
+        summary.deleted_categories.len(),
+        summary.moved_items,
+        summary.deleted_items,
+        summary.trashed_items
+    );
+    Ok(summary)
+}
+
+// 绑定或解除分类的文件夹，绑定后立即同步一次
+// 解除绑定时分类中已有的项目保留，之后不再同步
+#[tauri::command]
+pub async fn set_category_folder(
+    app: AppHandle,
+    id: String,
+    folder: Option<WatchedFolder>,
+) -> Result<FolderSyncSummary, String> {
+    if let Some(folder) = &folder {
+        let rule = FolderRule::new(folder)?;
+        if !rule.dir.is_dir() {
+            return Err(format!("Folder does not exist: {}", rule.dir.display()));
+        }
+    }
+
+    // 只记录绑定的修改，同步添加和移除的项目与文件监视的修改一样不记录
+    {
+        let db = app.state::<DbState>();
+        let mut conn = db.conn()?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let mut recorder = Recorder::new(EditAction::SetCategoryFolder);
+        recorder.categories(&tx, [&id])?;
+        let folder = folder.as_ref();
+        let updated = tx
+            .execute(
+                "UPDATE categories SET folder_dir = ?, folder_include = ?, folder_exclude = ?, folder_depth = ? WHERE id = ?",
+                rusqlite::params![
+                    folder.map(|f| f.dir.trim()),
+                    folder.and_then(|f| patterns_json(&f.include)),
+                    folder.and_then(|f| patterns_json(&f.exclude)),
+                    folder.map_or(1, |f| f.depth),
+                    &id
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        if updated == 0 {
+            return Err(format!("Category not found: {}", id));
+        }
+        recorder.finish(&tx)?;
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+
+    sync_folder_category(app, id).await
+}
+
+// 按文件夹的当前内容同步分类，枚举文件夹较慢，在后台线程中执行
+#[tauri::command]
+pub async fn sync_folder_category(app: AppHandle, id: String) -> Result<FolderSyncSummary, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = app.state::<DbState>();
+        let (summary, _) = sync_category(&db, &id)?;
+        app.state::<FileWatcher>().sync(&*db.conn()?);
+        Ok(summary)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    // work 下有子分类 dev，dev 下有之前移到回收站的子分类 old
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::init_database(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO categories (id, parent_id, name, sort_order, deleted_at) VALUES
+                ('main', NULL, 'Main', 0, NULL),
+                ('work', NULL, 'Work', 1, NULL),
+                ('dev', 'work', 'Dev', 0, NULL),
+                ('old', 'dev', 'Old', 0, 5);",
+        )
+        .unwrap();
+        for (id, category, sort_order, deleted_at) in [
+            ("m1", "main", 0, None),
+            ("m2", "main", 4, None),
+            ("w1", "work", 1, None),
+            ("w2", "work", 0, None),
+            ("wt", "work", 2, Some(7)),
+            ("d1", "dev", 0, None),
+            ("o1", "old", 0, Some(5)),
+        ] {
+            conn.execute(
+                "INSERT INTO files (id, name, display_name, path, category, sort_order, deleted_at)
+                 VALUES (?1, ?1, ?1, ?1, ?2, ?3, ?4)",
+                params![id, category, sort_order, deleted_at],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn delete(conn: &mut Connection, id: &str, mode: CategoryDeleteMode) -> Result<CategoryDeleteSummary, String> {
+        let tx = conn.transaction().unwrap();
+        let summary = delete_category(&tx, id, mode)?;
+        tx.commit().unwrap();
+        Ok(summary)
+    }
+
+    // (category, sort_order, deleted_at)
+    fn file(conn: &Connection, id: &str) -> Option<(String, i64, Option<i64>)> {
+        conn.query_row(
+            "SELECT category, sort_order, deleted_at FROM files WHERE id = ?",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .unwrap()
+    }
+
+    // (parent_id, deleted_at)
+    fn category(conn: &Connection, id: &str) -> Option<(Option<String>, Option<i64>)> {
+        conn.query_row(
+            "SELECT parent_id, deleted_at FROM categories WHERE id = ?",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .unwrap()
+    }
+
+    #[test]
+    fn move_to_parent_appends_items_and_reparents_children() {
+        let mut conn = test_db();
+        let summary = delete(&mut conn, "dev", CategoryDeleteMode::MoveToParent).unwrap();
+        assert_eq!(summary.target_category.as_deref(), Some("work"));
+        assert_eq!(summary.deleted_categories, ["dev"]);
+        assert_eq!((summary.moved_items, summary.reparented_categories), (1, 1));
+
+        // 追加到 work 当前最大的 sort_order 之后，回收站中的项目也占用位置
+        assert_eq!(file(&conn, "d1"), Some(("work".to_string(), 3, None)));
+        assert_eq!(category(&conn, "dev"), None);
+        assert_eq!(category(&conn, "old"), Some((Some("work".to_string()), Some(5))));
+    }
+
+    #[test]
+    fn move_to_parent_of_top_level_category_moves_to_main() {
+        let mut conn = test_db();
+        let summary = delete(&mut conn, "work", CategoryDeleteMode::MoveToParent).unwrap();
+        assert_eq!(summary.target_category.as_deref(), Some("main"));
+        assert_eq!(summary.moved_items, 2);
+
+        // 按原来的顺序追加到 main 末尾
+        assert_eq!(file(&conn, "w2"), Some(("main".to_string(), 5, None)));
+        assert_eq!(file(&conn, "w1"), Some(("main".to_string(), 6, None)));
+        // 回收站中的项目留在回收站
+        assert_eq!(file(&conn, "wt"), Some(("work".to_string(), 2, Some(7))));
+        assert_eq!(category(&conn, "dev"), Some((None, None)));
+        assert_eq!(file(&conn, "d1"), Some(("dev".to_string(), 0, None)));
+    }
+
+    #[test]
+    fn move_to_main_skips_parent() {
+        let mut conn = test_db();
+        let summary = delete(&mut conn, "dev", CategoryDeleteMode::MoveToMain).unwrap();
+        assert_eq!(summary.target_category.as_deref(), Some("main"));
+        assert_eq!(file(&conn, "d1"), Some(("main".to_string(), 5, None)));
+        // 子分类仍改挂到上级分类
+        assert_eq!(category(&conn, "old"), Some((Some("work".to_string()), Some(5))));
+    }
+
+    #[test]
+    fn trash_moves_subtree_to_trash() {
+        let mut conn = test_db();
+        let summary = delete(&mut conn, "work", CategoryDeleteMode::Trash).unwrap();
+        assert_eq!(summary.trashed_items, 3);
+        assert_eq!(summary.deleted_categories.len(), 3);
+
+        let (_, _, deleted_at) = file(&conn, "w1").unwrap();
+        let now = deleted_at.unwrap();
+        assert_eq!(file(&conn, "d1"), Some(("dev".to_string(), 0, Some(now))));
+        assert_eq!(category(&conn, "work"), Some((None, Some(now))));
+        assert_eq!(category(&conn, "dev"), Some((Some("work".to_string()), Some(now))));
+        // 之前移到回收站的项目和分类保留原来的删除时间
+        assert_eq!(file(&conn, "wt").unwrap().2, Some(7));
+        assert_eq!(file(&conn, "o1").unwrap().2, Some(5));
+        assert_eq!(category(&conn, "old").unwrap().1, Some(5));
+        assert_eq!(file(&conn, "m1"), Some(("main".to_string(), 0, None)));
+    }
+
+    #[test]
+    fn cascade_deletes_subtree_including_trashed_items() {
+        let mut conn = test_db();
+        let summary = delete(&mut conn, "work", CategoryDeleteMode::Cascade).unwrap();
+        assert_eq!(summary.deleted_items, 5);
+        assert_eq!(summary.deleted_categories.len(), 3);
+
+        for id in ["w1", "w2", "wt", "d1", "o1"] {
+            assert_eq!(file(&conn, id), None, "file {}", id);
+        }
+        for id in ["work", "dev", "old"] {
+            assert_eq!(category(&conn, id), None, "category {}", id);
+        }
+        // 没有留下指向不存在分类的项目
+        let orphans: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM files WHERE category NOT IN (SELECT id FROM categories)",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(orphans, 0);
+        assert_eq!(file(&conn, "m2"), Some(("main".to_string(), 4, None)));
+    }
+
+    #[test]
+    fn refuses_main_and_missing_categories() {
+        let mut conn = test_db();
+        for mode in [
+            CategoryDeleteMode::MoveToParent,
+            CategoryDeleteMode::MoveToMain,
+            CategoryDeleteMode::Cascade,
+            CategoryDeleteMode::Trash,
+        ] {
+            assert!(delete(&mut conn, "main", mode).is_err());
+        }
+        assert!(delete(&mut conn, "missing", CategoryDeleteMode::Cascade).is_err());
+        // 已经在回收站中的分类
+        assert!(delete(&mut conn, "old", CategoryDeleteMode::Trash).is_err());
+        assert_eq!(category(&conn, "main"), Some((None, None)));
+        assert_eq!(file(&conn, "o1").unwrap().2, Some(5));
+    }
+
+    #[test]
+    fn refuses_to_move_items_into_the_category_itself() {
+        let mut conn = test_db();
+        conn.execute("UPDATE categories SET parent_id = 'work' WHERE id = 'work'", [])
+            .unwrap();
+        assert!(delete(&mut conn, "work", CategoryDeleteMode::MoveToParent).is_err());
+        assert_eq!(file(&conn, "w1"), Some(("work".to_string(), 1, None)));
+    }
 }
@@ -1,32 +1,155 @@
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use crate::models::FileInfo;
-use crate::db::get_db_connection;
-use crate::utils::{to_abs_path, resolve_shortcut};
-use crate::icon::get_file_icon_base64;
-use rusqlite::params;
+use crate::models::{EditAction, FileInfo, FileSort, ItemKind};
+use crate::frecency::{frecency_score, recent_launches};
+use crate::db::DbState;
+use crate::file_watcher::FileWatcher;
+use crate::utils::{to_abs_path, resolve_shortcut, now_millis};
+use crate::icon::{get_file_icon_base64, get_named_icon_base64, image_file_to_base64, png_to_data_url};
+use crate::thumbnail::{is_image_file, prune_stale_thumbnails, render_thumbnail, DEFAULT_THUMBNAIL_SIZE};
+use crate::desktop_entry::{self, is_desktop_file};
+use crate::shell_link::{expand_env_vars, ShellLink};
+use crate::pinyin_index;
+use crate::mime_type;
+use crate::shell_command::command_name;
+use crate::url_item::{file_url_to_path, is_url, path_to_file_url, read_html_page, resolve_icon_href, url_name, url_scheme, IconSource};
+use crate::text_preview;
+use crate::icon_cache::{intern_icon, prune_unused_icons};
+use crate::journal::Recorder;
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+use tauri::State;
 
-// 保存文件列表到SQLite数据库
+// files 表查询时使用的列顺序，与 row_to_file_info 保持一致
+const FILE_COLUMNS: &str = "id, name, display_name, path, size, type, icon, content, category, open_count, created_at, sort_order, pinyin, mime, launch_args, working_dir, env, run_in_terminal, kind, shell, keep_open, missing, deleted_at";
+
+// 将查询结果行转换为 FileInfo
+fn row_to_file_info(row: &Row) -> rusqlite::Result<FileInfo> {
+    Ok(FileInfo {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        display_name: row.get(2)?,
+        path: row.get(3)?,
+        size: row.get::<_, Option<i64>>(4)?.unwrap_or(0) as u64,
+        r#type: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+        icon: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+        content: row.get(7)?,
+        category: Some(row.get(8)?),
+        open_count: Some(row.get::<_, Option<i64>>(9)?.unwrap_or(0) as u64),
+        created_at: row.get(10)?,
+        sort_order: Some(row.get(11)?),
+        frecency: None,
+        pinyin: row.get(12)?,
+        mime: row.get(13)?,
+        launch_args: row.get(14)?,
+        working_dir: row.get(15)?,
+        env: row
+            .get::<_, Option<String>>(16)?
+            .and_then(|env| serde_json::from_str(&env).ok()),
+        run_in_terminal: Some(row.get(17)?),
+        kind: ItemKind::from_db(&row.get::<_, String>(18)?),
+        shell: row.get(19)?,
+        keep_open: Some(row.get(20)?),
+        missing: Some(row.get(21)?),
+        deleted_at: row.get(22)?,
+    })
+}
+
+// URL 和命令保持原样，文件路径转换为绝对路径
+fn item_path(file: &FileInfo) -> Result<String, String> {
+    match file.kind {
+        ItemKind::Url | ItemKind::Command => Ok(file.path.trim().to_string()),
+        ItemKind::File => to_abs_path(&file.path),
+    }
+}
+
+// 环境变量以 JSON 对象保存，没有设置时保存为空
+fn env_json(file: &FileInfo) -> Option<String> {
+    file.env
+        .as_ref()
+        .filter(|env| !env.is_empty())
+        .and_then(|env| serde_json::to_string(env).ok())
+}
+
+// 按 ID 读取单个文件
+pub fn query_file(conn: &Connection, id: &str) -> Result<Option<FileInfo>, String> {
+    conn.prepare_cached(&format!("SELECT {} FROM files WHERE id = ?", FILE_COLUMNS))
+        .map_err(|e| e.to_string())?
+        .query_row([id], row_to_file_info)
+        .optional()
+        .map_err(|e| e.to_string())
+}
+
+// 回收站中的文件，最近删除的在前
+pub fn query_trashed_files(conn: &Connection) -> Result<Vec<FileInfo>, String> {
+    let mut stmt = conn
+        .prepare_cached(&format!("SELECT {} FROM files WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, sort_order", FILE_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_file_info).map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+// 将文件启动项指向重新读取的目标，更新名称、大小、类型、图标和预览内容，并清除缺失标记
+// 显示名称、分类、打开次数和启动选项保持不变，返回更新的行数
+pub fn update_item_target(conn: &Connection, id: &str, info: &FileInfo) -> Result<usize, String> {
+    let icon = intern_icon(conn, &info.icon).map_err(|e| e.to_string())?;
+    conn.prepare_cached("UPDATE files SET name = ?, path = ?, size = ?, type = ?, icon = ?, content = ?, mime = ?, missing = 0 WHERE id = ? AND kind = 'file'")
+        .map_err(|e| e.to_string())?
+        .execute(params![
+            &info.name,
+            &info.path,
+            info.size as i64,
+            &info.r#type,
+            &icon,
+            &info.content,
+            &info.mime,
+            id
+        ])
+        .map_err(|e| e.to_string())
+}
+
+// 显示名称为空时（老数据）使用文件名生成拼音索引
+fn pinyin_for(file: &FileInfo) -> Option<String> {
+    if file.display_name.is_empty() {
+        pinyin_index::build(&file.name)
+    } else {
+        pinyin_index::build(&file.display_name)
+    }
+}
+
+// 分类中下一个可用的排序位置
+pub fn next_sort_order(conn: &Connection, category: &str) -> Result<i64, String> {
+    conn.prepare_cached("SELECT IFNULL(MAX(sort_order) + 1, 0) FROM files WHERE category = ?")
+        .map_err(|e| e.to_string())?
+        .query_row([category], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+// 批量导入文件列表到SQLite数据库（会覆盖现有的全部文件）
+// 日常的增删改请使用 add_file / update_file / delete_files 等增量命令
 #[tauri::command]
-pub fn save_files_to_db(app: tauri::AppHandle, files: Vec<FileInfo>) -> Result<(), String> {
+pub fn save_files_to_db(db: State<'_, DbState>, watcher: State<'_, FileWatcher>, files: Vec<FileInfo>) -> Result<(), String> {
     println!("Saving {} files to database...", files.len());
-    let mut conn = get_db_connection(&app)?;
+    let mut conn = db.conn()?;
     
     // 开始事务
     let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let mut recorder = Recorder::new(EditAction::ReplaceItems);
+    recorder.files_where(&tx, "SELECT id FROM files WHERE deleted_at IS NULL", [])?;
+    recorder.files(&tx, files.iter().map(|file| &file.id))?;
     
-    // 清空现有数据
-    tx.execute("DELETE FROM files", []).map_err(|e| e.to_string())?;
+    // 清空现有数据，回收站中的文件不在前端的列表中，保持不变
+    tx.execute("DELETE FROM files WHERE deleted_at IS NULL", []).map_err(|e| e.to_string())?;
     
     // 插入新数据
-    let mut stmt = tx.prepare(
-        "INSERT OR REPLACE INTO files (id, name, display_name, path, size, type, icon, content, category, open_count, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    let mut stmt = tx.prepare_cached(
+        "INSERT OR REPLACE INTO files (id, name, display_name, path, size, type, icon, content, category, open_count, created_at, sort_order, pinyin, mime, launch_args, working_dir, env, run_in_terminal, kind, shell, keep_open, missing) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
     ).map_err(|e| e.to_string())?;
 
-    for mut file in files {
+    for (index, mut file) in files.into_iter().enumerate() {
         // 尝试处理每个文件，跳过失败的文件
-        match to_abs_path(&file.path) {
+        match item_path(&file) {
             Ok(abs_path) => {
                 file.path = abs_path;
                 
@@ -37,12 +160,16 @@ pub fn save_files_to_db(app: tauri::AppHandle, files: Vec<FileInfo>) -> Result<(
                 };
                 
                 // 获取当前时间作为创建时间（如果前端没传）
-                let created_at = file.created_at.unwrap_or_else(|| {
-                    std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_millis() as i64
-                });
+                let created_at = file.created_at.unwrap_or_else(now_millis);
+
+                // 内联的 base64 图标转存到图标缓存
+                match intern_icon(&tx, &file.icon) {
+                    Ok(icon) => file.icon = icon,
+                    Err(e) => {
+                        println!("Failed to cache icon for file {}: {}", file.name, e);
+                        file.icon = String::new();
+                    }
+                }
                 
                 // 尝试执行插入
                 if let Err(e) = stmt.execute(
@@ -57,7 +184,18 @@ pub fn save_files_to_db(app: tauri::AppHandle, files: Vec<FileInfo>) -> Result<(
                         &file.content,
                         category_id,
                         file.open_count.unwrap_or(0) as i64,
-                        created_at
+                        created_at,
+                        file.sort_order.unwrap_or(index as i64),
+                        pinyin_for(&file),
+                        &file.mime,
+                        &file.launch_args,
+                        &file.working_dir,
+                        env_json(&file),
+                        file.run_in_terminal.unwrap_or(false),
+                        file.kind.as_str(),
+                        &file.shell,
+                        file.keep_open.unwrap_or(false),
+                        file.missing.unwrap_or(false)
                     ]
                 ) {
                     println!("Failed to save file {} to DB: {}", file.name, e);
@@ -73,40 +211,42 @@ pub fn save_files_to_db(app: tauri::AppHandle, files: Vec<FileInfo>) -> Result<(
     
     // 释放 statement
     drop(stmt);
+    recorder.finish(&tx)?;
+
+    // 清理被覆盖掉的图标和缩略图
+    prune_unused_icons(&tx).map_err(|e| e.to_string())?;
+    prune_stale_thumbnails(&tx).map_err(|e| e.to_string())?;
 
     // 提交事务
     tx.commit().map_err(|e| e.to_string())?;
+    watcher.sync(&conn);
     
     Ok(())
 }
 
 // 从SQLite数据库读取文件列表
+// sort 缺省时按 frecency 排序，每个文件都会带上计算好的 frecency 分数
 #[tauri::command]
-pub fn load_files_from_db(app: tauri::AppHandle) -> Result<Vec<FileInfo>, String> {
+pub fn load_files_from_db(db: State<'_, DbState>, sort: Option<FileSort>) -> Result<Vec<FileInfo>, String> {
     println!("Loading files from database...");
-    let conn = get_db_connection(&app)?;
+    let sort = sort.unwrap_or_default();
+    let conn = db.conn()?;
+
+    let order_by = match sort {
+        FileSort::Frecency | FileSort::OpenCount => "open_count DESC",
+        FileSort::Name => "display_name COLLATE NOCASE ASC, name COLLATE NOCASE ASC",
+        FileSort::CreatedAt => "created_at DESC",
+        FileSort::Size => "size DESC",
+        FileSort::Manual => "category ASC, sort_order ASC",
+    };
     
-    let mut stmt = conn.prepare("SELECT id, name, display_name, path, size, type, icon, content, category, open_count, created_at FROM files ORDER BY open_count DESC")
+    let mut stmt = conn.prepare_cached(&format!("SELECT {} FROM files WHERE deleted_at IS NULL ORDER BY {}", FILE_COLUMNS, order_by))
         .map_err(|e| {
             println!("Failed to prepare select statement: {}", e);
             e.to_string()
         })?;
     
-    let files_iter = stmt.query_map([], |row| {
-        Ok(FileInfo {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            display_name: row.get(2)?,
-            path: row.get(3)?,
-            size: row.get::<_, i64>(4)? as u64,
-            r#type: row.get(5)?,
-            icon: row.get(6)?,
-            content: row.get(7)?,
-            category: Some(row.get(8)?),
-            open_count: Some(row.get::<_, i64>(9)? as u64),
-            created_at: row.get(10)?,
-        })
-    }).map_err(|e| {
+    let files_iter = stmt.query_map([], row_to_file_info).map_err(|e| {
         println!("Failed to query files: {}", e);
         e.to_string()
     })?;
@@ -118,17 +258,282 @@ pub fn load_files_from_db(app: tauri::AppHandle) -> Result<Vec<FileInfo>, String
             Err(e) => println!("Error mapping file row: {}", e),
         }
     }
+
+    // 根据启动历史计算 frecency
+    let launches = recent_launches(&conn)?;
+    let now = now_millis();
+    for file in files.iter_mut() {
+        let recent = launches.get(&file.id).map(Vec::as_slice).unwrap_or(&[]);
+        file.frecency = Some(frecency_score(file.open_count.unwrap_or(0), recent, now));
+    }
+
+    if let FileSort::Frecency = sort {
+        // 稳定排序，分数相同时保持打开次数的顺序
+        files.sort_by(|a, b| {
+            b.frecency
+                .unwrap_or(0.0)
+                .total_cmp(&a.frecency.unwrap_or(0.0))
+        });
+    }
     
     println!("Successfully loaded {} files from database.", files.len());
     Ok(files)
 }
 
+// 新增单个文件，排在所属分类的末尾，同一分类中不能有相同路径的项目
+fn add_item(tx: &Transaction, file: &mut FileInfo) -> Result<(), String> {
+    if file.id.trim().is_empty() {
+        return Err("file id is empty".to_string());
+    }
+
+    file.path = item_path(file)?;
+    let category = file.category.clone().unwrap_or_else(|| "main".to_string());
+
+    let exists: bool = tx
+        .prepare_cached("SELECT EXISTS(SELECT 1 FROM files WHERE category = ? AND path = ? AND deleted_at IS NULL)")
+        .map_err(|e| e.to_string())?
+        .query_row([&category, &file.path], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if exists {
+        return Err(format!("File already exists in category: {}", file.path));
+    }
+
+    let mut recorder = Recorder::new(EditAction::AddItem);
+    recorder.files(tx, [&file.id])?;
+    insert_file(tx, file, &category)?;
+    recorder.finish(tx)
+}
+
 #[tauri::command]
-pub fn get_file_info(path: String) -> Result<FileInfo, String> {
-    let abs_path = to_abs_path(&path)?;
+pub fn add_file(db: State<'_, DbState>, watcher: State<'_, FileWatcher>, mut file: FileInfo) -> Result<FileInfo, String> {
+    let mut conn = db.conn()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    add_item(&tx, &mut file)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    if file.kind == ItemKind::File {
+        watcher.sync(&conn);
+    }
+    Ok(file)
+}
+
+// 将文件插入到分类末尾，补全分类、排序位置、创建时间和拼音索引，图标转存为引用
+pub fn insert_file(conn: &Connection, file: &mut FileInfo, category: &str) -> Result<(), String> {
+    let sort_order = next_sort_order(conn, category)?;
+    let created_at = file.created_at.unwrap_or_else(now_millis);
+    file.pinyin = pinyin_for(file);
+    file.icon = intern_icon(conn, &file.icon).map_err(|e| e.to_string())?;
+
+    conn.prepare_cached(
+        "INSERT INTO files (id, name, display_name, path, size, type, icon, content, category, open_count, created_at, sort_order, pinyin, mime, launch_args, working_dir, env, run_in_terminal, kind, shell, keep_open) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .map_err(|e| e.to_string())?
+    .execute(params![
+        &file.id,
+        &file.name,
+        &file.display_name,
+        &file.path,
+        file.size as i64,
+        &file.r#type,
+        &file.icon,
+        &file.content,
+        category,
+        file.open_count.unwrap_or(0) as i64,
+        created_at,
+        sort_order,
+        &file.pinyin,
+        &file.mime,
+        &file.launch_args,
+        &file.working_dir,
+        env_json(file),
+        file.run_in_terminal.unwrap_or(false),
+        file.kind.as_str(),
+        &file.shell,
+        file.keep_open.unwrap_or(false)
+    ])
+    .map_err(|e| e.to_string())?;
+
+    file.category = Some(category.to_string());
+    file.open_count = Some(file.open_count.unwrap_or(0));
+    file.created_at = Some(created_at);
+    file.sort_order = Some(sort_order);
+    Ok(())
+}
+
+// 更新单个文件的全部字段，排序位置保持不变，返回更新前的文件
+fn update_item(tx: &Transaction, file: &mut FileInfo) -> Result<FileInfo, String> {
+    let existing = query_file(tx, &file.id)?
+        .ok_or_else(|| format!("File not found in database: {}", file.id))?;
+    let mut recorder = Recorder::new(EditAction::UpdateItem);
+    recorder.files(tx, [&file.id])?;
+
+    file.path = item_path(file)?;
+    let category = file.category.clone().or_else(|| existing.category.clone()).unwrap_or_else(|| "main".to_string());
+    let open_count = file.open_count.or(existing.open_count).unwrap_or(0);
+    let created_at = file.created_at.or(existing.created_at);
+    file.pinyin = pinyin_for(file);
+    file.icon = intern_icon(tx, &file.icon).map_err(|e| e.to_string())?;
+
+    tx.prepare_cached(
+        "UPDATE files SET name = ?, display_name = ?, path = ?, size = ?, type = ?, icon = ?, content = ?, category = ?, open_count = ?, created_at = ?, pinyin = ?, mime = ?, launch_args = ?, working_dir = ?, env = ?, run_in_terminal = ?, kind = ?, shell = ?, keep_open = ? WHERE id = ?"
+    )
+    .map_err(|e| e.to_string())?
+    .execute(params![
+        &file.name,
+        &file.display_name,
+        &file.path,
+        file.size as i64,
+        &file.r#type,
+        &file.icon,
+        &file.content,
+        &category,
+        open_count as i64,
+        created_at,
+        &file.pinyin,
+        &file.mime,
+        &file.launch_args,
+        &file.working_dir,
+        env_json(file),
+        file.run_in_terminal.unwrap_or(false),
+        file.kind.as_str(),
+        &file.shell,
+        file.keep_open.unwrap_or(false),
+        &file.id
+    ])
+    .map_err(|e| e.to_string())?;
+
+    recorder.finish(tx)?;
+
+    // 图标被替换时清理旧图标
+    if existing.icon != file.icon {
+        prune_unused_icons(tx).map_err(|e| e.to_string())?;
+    }
+
+    file.category = Some(category);
+    file.open_count = Some(open_count);
+    file.created_at = created_at;
+    file.sort_order = existing.sort_order;
+    Ok(existing)
+}
+
+#[tauri::command]
+pub fn update_file(db: State<'_, DbState>, watcher: State<'_, FileWatcher>, mut file: FileInfo) -> Result<FileInfo, String> {
+    let mut conn = db.conn()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let existing = update_item(&tx, &mut file)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    // 路径或类型改变后监视的目录可能不同
+    if existing.path != file.path || existing.kind != file.kind {
+        watcher.sync(&conn);
+    }
+    Ok(file)
+}
+
+// 将指定的文件移到回收站，返回实际删除的数量
+// 图标和缩略图保留到回收站清空或过期清除时
+#[tauri::command]
+pub fn delete_files(db: State<'_, DbState>, watcher: State<'_, FileWatcher>, ids: Vec<String>) -> Result<usize, String> {
+    let mut conn = db.conn()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let mut recorder = Recorder::new(EditAction::DeleteItems);
+    recorder.files(&tx, &ids)?;
+
+    let now = now_millis();
+    let mut deleted = 0;
+    {
+        let mut stmt = tx
+            .prepare_cached("UPDATE files SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL")
+            .map_err(|e| e.to_string())?;
+        for id in &ids {
+            deleted += stmt.execute(params![now, id]).map_err(|e| e.to_string())?;
+        }
+    }
+    recorder.finish(&tx)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    watcher.sync(&conn);
+    Ok(deleted)
+}
+
+// 将文件移动到另一个分类，按传入顺序追加到目标分类末尾，返回实际移动的数量
+fn move_items(tx: &Transaction, ids: &[String], category: &str) -> Result<usize, String> {
+    let mut recorder = Recorder::new(EditAction::MoveItems);
+    recorder.files(tx, ids)?;
+    let mut sort_order = next_sort_order(tx, category)?;
+    let mut moved = 0;
+    {
+        let mut stmt = tx
+            .prepare_cached("UPDATE files SET category = ?, sort_order = ? WHERE id = ? AND category != ?")
+            .map_err(|e| e.to_string())?;
+        for id in ids {
+            let changed = stmt
+                .execute(params![category, sort_order, id, category])
+                .map_err(|e| e.to_string())?;
+            if changed > 0 {
+                moved += changed;
+                sort_order += 1;
+            }
+        }
+    }
+    recorder.finish(tx)?;
+    Ok(moved)
+}
+
+#[tauri::command]
+pub fn move_files_to_category(db: State<'_, DbState>, ids: Vec<String>, category: String) -> Result<usize, String> {
+    let mut conn = db.conn()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let moved = move_items(&tx, &ids, &category)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(moved)
+}
+
+// 按传入的 ID 顺序重排分类内的文件，不在列表中的文件保持原来的排序位置
+fn reorder_items(tx: &Transaction, category: &str, ids: &[String]) -> Result<(), String> {
+    // 分类中所有文件的排序位置都可能变化
+    let mut recorder = Recorder::new(EditAction::ReorderItems);
+    recorder.files_where(tx, "SELECT id FROM files WHERE category = ?", [category])?;
+    {
+        let mut stmt = tx
+            .prepare_cached("UPDATE files SET sort_order = ? WHERE id = ? AND category = ?")
+            .map_err(|e| e.to_string())?;
+        for (index, id) in ids.iter().enumerate() {
+            stmt.execute(params![index as i64, id, category])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    recorder.finish(tx)
+}
+
+#[tauri::command]
+pub fn reorder_files(db: State<'_, DbState>, category: String, ids: Vec<String>) -> Result<(), String> {
+    let mut conn = db.conn()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    reorder_items(&tx, &category, &ids)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// 读取文件信息，提取到的图标转存到图标缓存，返回的 icon 为图标引用
+// 提取图标较慢，期间不持有数据库连接
+#[tauri::command]
+pub fn get_file_info(db: State<'_, DbState>, path: String) -> Result<FileInfo, String> {
+    if is_url(&path) {
+        return get_url_info(db, path, None, None);
+    }
+
+    let mut info = read_file_info(&path)?;
+    let conn = db.conn()?;
+    info.icon = intern_icon(&conn, &info.icon).map_err(|e| e.to_string())?;
+    Ok(info)
+}
+
+pub fn read_file_info(path: &str) -> Result<FileInfo, String> {
+    let abs_path = to_abs_path(path)?;
     
-    // 解析快捷方式
-    let target_path = resolve_shortcut(&abs_path);
+    // 解析快捷方式，启动项指向快捷方式的目标
+    let (target_path, link) = resolve_shortcut(&abs_path);
     let p = Path::new(&target_path);
     
     if !p.exists() {
@@ -140,13 +545,30 @@ pub fn get_file_info(path: String) -> Result<FileInfo, String> {
         .ok_or("Invalid file name")?
         .to_string_lossy()
         .to_string();
+
+    // Linux 桌面启动器使用条目中的名称、图标和启动命令
+    if is_desktop_file(&target_path) {
+        return desktop_file_info(p, name, metadata.len());
+    }
     
     let extension = p.extension()
         .map(|e| e.to_string_lossy().to_string())
         .unwrap_or_default();
 
-    // 获取文件图标
-    let icon = match get_file_icon_base64(p) {
+    // 获取文件图标，快捷方式指定了图标文件时优先使用，图片文件直接使用缩略图
+    // 图标资源中的序号不处理，使用图标文件本身的图标
+    let link_icon = link
+        .as_ref()
+        .and_then(ShellLink::icon_path)
+        .map(PathBuf::from)
+        .filter(|icon| icon.is_file());
+    let icon = match &link_icon {
+        Some(icon) if is_image_file(icon) => image_file_to_base64(icon),
+        Some(icon) => get_file_icon_base64(icon),
+        None if is_image_file(p) => render_thumbnail(p, DEFAULT_THUMBNAIL_SIZE).map(|png| png_to_data_url(&png)),
+        None => get_file_icon_base64(p),
+    };
+    let icon = match icon {
         Ok(icon) => icon,
         Err(e) => {
             println!("Failed to get icon: {}", e);
@@ -154,20 +576,33 @@ pub fn get_file_info(path: String) -> Result<FileInfo, String> {
         }
     };
 
-    // 读取文件内容 (如果是文本文件且大小小于1MB)
+    // 根据文件内容判断类型，没有扩展名的脚本和配置文件也能识别
+    let detected = match mime_type::detect(p) {
+        Ok(detected) => Some(detected),
+        Err(e) => {
+            println!("Failed to detect file type: {}", e);
+            None
+        }
+    };
+
+    // 读取文本文件的预览，自动识别 GBK、UTF-16 等编码，大文件只保留开头若干行
     let mut content = None;
-    if metadata.is_file() && metadata.len() < 1024 * 1024 {
-        // 常见的文本文件后缀
-        let text_extensions = ["txt", "md", "json", "js", "ts", "html", "css", "py", "rs", "c", "cpp", "h", "hpp", "go", "sql", "yml", "yaml", "toml", "xml"];
-        if text_extensions.contains(&extension.to_lowercase().as_str()) {
-            if let Ok(c) = fs::read_to_string(p) {
-                content = Some(c);
-            }
+    let is_text = detected.as_ref().is_some_and(|d| d.is_text);
+    if metadata.is_file() && is_text {
+        match text_preview::read_preview(p) {
+            Ok(text) => content = Some(text),
+            Err(e) => println!("Failed to read text preview: {}", e),
         }
     }
 
-    // 生成 display_name，去掉常见后缀
-    let display_name = if let Some(last_dot_idx) = name.rfind('.') {
+    // 生成 display_name，快捷方式有说明时使用说明，否则去掉常见后缀
+    let description = link
+        .as_ref()
+        .and_then(|link| link.description.clone())
+        .filter(|description| !description.trim().is_empty());
+    let display_name = if let Some(description) = description {
+        description
+    } else if let Some(last_dot_idx) = name.rfind('.') {
         let ext = &name[last_dot_idx + 1..].to_lowercase();
         let common_extensions = ["exe", "js", "ts", "html", "css", "py", "rs", "c", "cpp", "h", "hpp", "go", "sql", "yml", "yaml", "toml", "xml", "txt", "md", "json"];
         if common_extensions.contains(&ext.as_str()) {
@@ -179,10 +614,19 @@ pub fn get_file_info(path: String) -> Result<FileInfo, String> {
         name.clone()
     };
 
-    let created_at = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as i64;
+    let created_at = now_millis();
+    let pinyin = pinyin_index::build(&display_name);
+
+    // 快捷方式中保存的启动参数和工作目录作为启动选项
+    let launch_args = link
+        .as_ref()
+        .and_then(|link| link.arguments.clone())
+        .filter(|args| !args.trim().is_empty());
+    let working_dir = link
+        .as_ref()
+        .and_then(|link| link.working_dir.as_deref())
+        .map(expand_env_vars)
+        .filter(|dir| !dir.trim().is_empty());
 
     Ok(FileInfo {
         id: "".to_string(),
@@ -196,48 +640,270 @@ pub fn get_file_info(path: String) -> Result<FileInfo, String> {
         category: None,
         open_count: None,
         created_at: Some(created_at),
+        sort_order: None,
+        frecency: None,
+        pinyin,
+        mime: detected.map(|d| d.mime),
+        launch_args,
+        working_dir,
+        env: None,
+        run_in_terminal: None,
+        shell: None,
+        keep_open: None,
+        missing: None,
+        deleted_at: None,
+        kind: ItemKind::File,
     })
 }
 
+// 根据 .desktop 条目生成文件信息，content 中保存展开后的启动命令
+fn desktop_file_info(path: &Path, name: String, size: u64) -> Result<FileInfo, String> {
+    let entry = desktop_entry::load(path)?;
+    entry.check_usable()?;
+
+    // Icon 可以是绝对路径，也可以是图标主题中的名称
+    let icon = entry
+        .icon
+        .as_deref()
+        .and_then(|icon| get_named_icon_base64(icon).ok())
+        .unwrap_or_default();
+
+    let pinyin = pinyin_index::build(&entry.name);
+
+    Ok(FileInfo {
+        id: "".to_string(),
+        name,
+        display_name: entry.name.clone(),
+        path: path.to_string_lossy().to_string(),
+        size,
+        r#type: "desktop".to_string(),
+        icon,
+        content: entry.command_line(path),
+        category: None,
+        open_count: None,
+        created_at: Some(now_millis()),
+        sort_order: None,
+        frecency: None,
+        pinyin,
+        mime: Some("application/x-desktop".to_string()),
+        launch_args: None,
+        working_dir: None,
+        env: None,
+        run_in_terminal: None,
+        shell: None,
+        keep_open: None,
+        missing: None,
+        deleted_at: None,
+        kind: ItemKind::File,
+    })
+}
+
+// 读取 URL 启动项的信息，标题和图标可以手动指定
+// 本地 HTML 文件（路径或 file:// URL）从文件中读取标题和图标，其他 URL 使用主机名作为标题
 #[tauri::command]
-pub fn open_path(path: String) -> Result<(), String> {
+pub fn get_url_info(
+    db: State<'_, DbState>,
+    url: String,
+    title: Option<String>,
+    favicon: Option<String>,
+) -> Result<FileInfo, String> {
+    let mut info = read_url_info(&url, title, favicon)?;
+    let conn = db.conn()?;
+    info.icon = intern_icon(&conn, &info.icon).map_err(|e| e.to_string())?;
+    Ok(info)
+}
+
+fn read_url_info(url: &str, title: Option<String>, favicon: Option<String>) -> Result<FileInfo, String> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Err("url is empty".to_string());
+    }
+
+    // 本地 HTML 文件转换为 file:// URL
+    let url = if is_url(url) {
+        url.to_string()
+    } else {
+        let path = to_abs_path(url)?;
+        if !Path::new(&path).is_file() {
+            return Err(format!("Not a url: {}", url));
+        }
+        path_to_file_url(Path::new(&path))
+    };
+    let scheme = url_scheme(&url).unwrap_or_default().to_lowercase();
+
+    let html_path = file_url_to_path(&url).filter(|path| {
+        path.extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"))
+    });
+    let page = html_path
+        .as_deref()
+        .map(read_html_page)
+        .transpose()
+        .unwrap_or_else(|e| {
+            println!("Failed to read html page {}: {}", url, e);
+            None
+        })
+        .unwrap_or_default();
+
+    let name = url_name(&url);
+    let display_name = title
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty())
+        .or(page.title)
+        .unwrap_or_else(|| name.clone());
+
+    // 手动指定的图标可以是 data URL 或本地图片，其次使用 HTML 中声明的图标
+    let icon_source = match favicon.map(|f| f.trim().to_string()).filter(|f| !f.is_empty()) {
+        Some(favicon) if favicon.starts_with("data:") => Some(IconSource::DataUrl(favicon)),
+        Some(favicon) => Some(IconSource::File(PathBuf::from(favicon))),
+        None => html_path
+            .as_deref()
+            .zip(page.icon_href.as_deref())
+            .and_then(|(html_path, href)| resolve_icon_href(href, html_path)),
+    };
+    let icon = match icon_source {
+        Some(IconSource::DataUrl(icon)) => Ok(icon),
+        Some(IconSource::File(path)) => image_file_to_base64(&path),
+        None => get_named_icon_base64("web-browser"),
+    };
+    let icon = match icon {
+        Ok(icon) => icon,
+        Err(e) => {
+            println!("Failed to get icon: {}", e);
+            "".to_string()
+        }
+    };
+
+    let pinyin = pinyin_index::build(&display_name);
+
+    Ok(FileInfo {
+        id: "".to_string(),
+        name,
+        display_name,
+        path: url,
+        size: 0,
+        r#type: "url".to_string(),
+        kind: ItemKind::Url,
+        icon,
+        content: None,
+        category: None,
+        open_count: None,
+        created_at: Some(now_millis()),
+        sort_order: None,
+        frecency: None,
+        pinyin,
+        // freedesktop 中协议处理程序的类型写法
+        mime: Some(format!("x-scheme-handler/{}", scheme)),
+        launch_args: None,
+        working_dir: None,
+        env: None,
+        run_in_terminal: None,
+        shell: None,
+        keep_open: None,
+        missing: None,
+        deleted_at: None,
+    })
+}
+
+// 生成命令启动项的信息，标题为空时使用命令的第一行
+#[tauri::command]
+pub fn get_command_info(db: State<'_, DbState>, command: String, title: Option<String>) -> Result<FileInfo, String> {
+    let command = command.trim().to_string();
+    if command.is_empty() {
+        return Err("command is empty".to_string());
+    }
+
+    let name = command_name(&command);
+    let display_name = title
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| name.clone());
+
+    let icon = match get_named_icon_base64("utilities-terminal") {
+        Ok(icon) => icon,
+        Err(e) => {
+            println!("Failed to get icon: {}", e);
+            "".to_string()
+        }
+    };
+    let conn = db.conn()?;
+    let icon = intern_icon(&conn, &icon).map_err(|e| e.to_string())?;
+
+    let pinyin = pinyin_index::build(&display_name);
+
+    Ok(FileInfo {
+        id: "".to_string(),
+        name,
+        display_name,
+        path: command,
+        size: 0,
+        r#type: "command".to_string(),
+        kind: ItemKind::Command,
+        icon,
+        content: None,
+        category: None,
+        open_count: None,
+        created_at: Some(now_millis()),
+        sort_order: None,
+        frecency: None,
+        pinyin,
+        mime: Some("application/x-shellscript".to_string()),
+        launch_args: None,
+        working_dir: None,
+        env: None,
+        run_in_terminal: Some(false),
+        shell: None,
+        keep_open: Some(false),
+        missing: None,
+        deleted_at: None,
+    })
+}
+
+// 生成用系统默认程序打开路径的命令
+pub fn open_command(path: &str) -> Result<Command, String> {
     let path = path.trim();
     if path.is_empty() {
         return Err("path is empty".to_string());
     }
 
+    let opener = if cfg!(target_os = "windows") {
+        "explorer.exe"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+
+    // URL 交给系统的协议处理程序
+    if is_url(path) {
+        let mut command = Command::new(opener);
+        command.arg(path);
+        return Ok(command);
+    }
+
     let path = to_abs_path(path)?;
     let p = Path::new(&path);
     if !p.exists() {
         return Err(format!("path does not exist: {}", path));
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        Command::new("explorer.exe")
-            .arg(&path)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-        return Ok(());
+    // .desktop 文件直接按条目启动，否则 xdg-open 会用文本编辑器打开它
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if is_desktop_file(&path) {
+        return desktop_entry::load(p)?.command(p);
     }
 
-    #[cfg(target_os = "macos")]
-    {
-        Command::new("open")
-            .arg(&path)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-        return Ok(());
-    }
+    let mut command = Command::new(opener);
+    command.arg(&path);
+    Ok(command)
+}
 
-    #[cfg(all(unix, not(target_os = "macos")))]
-    {
-        Command::new("xdg-open")
-            .arg(&path)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-        return Ok(());
-    }
+#[tauri::command]
+pub fn open_path(path: String) -> Result<(), String> {
+    open_command(&path)?
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    Ok(())
 }
 
 #[tauri::command]
@@ -246,6 +912,9 @@ pub fn open_file_location(path: String) -> Result<(), String> {
     if path.is_empty() {
         return Err("path is empty".to_string());
     }
+    if is_url(path) {
+        return Err(format!("URL has no file location: {}", path));
+    }
 
     let path = to_abs_path(path)?;
     let p = Path::new(&path);
@@ -290,3 +959,142 @@ pub fn open_file_location(path: String) -> Result<(), String> {
         return Ok(());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // main 中 a、b、c 的排序依次为 0、5、2，work 中有 w（排序 3）和已移到回收站的 t（排序 9）
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::init_database(&mut conn).unwrap();
+        conn.execute_batch("INSERT OR IGNORE INTO categories (id, name) VALUES ('main', 'Main'), ('work', 'Work');")
+            .unwrap();
+        for (id, category, sort_order, deleted_at) in [
+            ("a", "main", 0, None),
+            ("b", "main", 5, None),
+            ("c", "main", 2, None),
+            ("w", "work", 3, None),
+            ("t", "work", 9, Some(7)),
+        ] {
+            conn.execute(
+                "INSERT INTO files (id, name, display_name, path, category, sort_order, kind, deleted_at)
+                 VALUES (?1, ?1, ?1, 'https://example.com/' || ?1, ?2, ?3, 'url', ?4)",
+                params![id, category, sort_order, deleted_at],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn url_item(id: &str, path: &str, category: &str) -> FileInfo {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": id,
+            "display_name": id,
+            "path": path,
+            "size": 0,
+            "type": "",
+            "kind": "url",
+            "icon": "",
+            "category": category,
+        }))
+        .unwrap()
+    }
+
+    fn with_tx<T>(conn: &mut Connection, f: impl FnOnce(&Transaction) -> Result<T, String>) -> Result<T, String> {
+        let tx = conn.transaction().unwrap();
+        let result = f(&tx)?;
+        tx.commit().unwrap();
+        Ok(result)
+    }
+
+    // (category, sort_order)
+    fn position(conn: &Connection, id: &str) -> (String, i64) {
+        conn.query_row("SELECT category, sort_order FROM files WHERE id = ?", [id], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn add_rejects_duplicate_path_in_same_category() {
+        let mut conn = test_db();
+
+        let mut duplicate = url_item("dup", " https://example.com/a ", "main");
+        let err = with_tx(&mut conn, |tx| add_item(tx, &mut duplicate)).unwrap_err();
+        assert!(err.contains("already exists"), "{}", err);
+        assert!(query_file(&conn, "dup").unwrap().is_none());
+
+        // 其他分类中可以有相同的路径
+        let mut other = url_item("other", "https://example.com/a", "work");
+        with_tx(&mut conn, |tx| add_item(tx, &mut other)).unwrap();
+        // 回收站中的项目不算重复，新项目排在包括它在内的所有项目之后
+        let mut restored = url_item("again", "https://example.com/t", "work");
+        with_tx(&mut conn, |tx| add_item(tx, &mut restored)).unwrap();
+
+        assert_eq!(position(&conn, "other"), ("work".to_string(), 10));
+        assert_eq!(position(&conn, "again"), ("work".to_string(), 11));
+        assert_eq!(restored.sort_order, Some(11));
+    }
+
+    #[test]
+    fn add_rejects_empty_id() {
+        let mut conn = test_db();
+        let mut item = url_item("  ", "https://example.com/new", "main");
+        assert!(with_tx(&mut conn, |tx| add_item(tx, &mut item)).is_err());
+    }
+
+    #[test]
+    fn update_unknown_id_is_an_error() {
+        let mut conn = test_db();
+        let mut item = url_item("missing", "https://example.com/missing", "main");
+
+        let err = with_tx(&mut conn, |tx| update_item(tx, &mut item)).unwrap_err();
+        assert!(err.contains("missing"), "{}", err);
+        assert!(query_file(&conn, "missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn update_keeps_sort_order_and_returns_previous_file() {
+        let mut conn = test_db();
+        let mut item = url_item("b", "https://example.com/renamed", "main");
+
+        let existing = with_tx(&mut conn, |tx| update_item(tx, &mut item)).unwrap();
+
+        assert_eq!(existing.path, "https://example.com/b");
+        assert_eq!(item.sort_order, Some(5));
+        assert_eq!(query_file(&conn, "b").unwrap().unwrap().path, "https://example.com/renamed");
+        assert_eq!(position(&conn, "b"), ("main".to_string(), 5));
+    }
+
+    #[test]
+    fn move_appends_after_target_max_sort_order() {
+        let mut conn = test_db();
+        let ids = ["c", "w", "a"].map(String::from);
+
+        // w 已经在 work 中，不计入移动数量，也不占用新的排序位置
+        let moved = with_tx(&mut conn, |tx| move_items(tx, &ids, "work")).unwrap();
+
+        assert_eq!(moved, 2);
+        // 回收站中的 t 也占用排序位置
+        assert_eq!(position(&conn, "c"), ("work".to_string(), 10));
+        assert_eq!(position(&conn, "a"), ("work".to_string(), 11));
+        assert_eq!(position(&conn, "w"), ("work".to_string(), 3));
+        assert_eq!(position(&conn, "b"), ("main".to_string(), 5));
+    }
+
+    #[test]
+    fn reorder_leaves_unlisted_ids_alone() {
+        let mut conn = test_db();
+        // w 属于其他分类，不会被移动到 main 中
+        let ids = ["c", "w", "a"].map(String::from);
+
+        with_tx(&mut conn, |tx| reorder_items(tx, "main", &ids)).unwrap();
+
+        assert_eq!(position(&conn, "c"), ("main".to_string(), 0));
+        assert_eq!(position(&conn, "a"), ("main".to_string(), 2));
+        assert_eq!(position(&conn, "b"), ("main".to_string(), 5));
+        assert_eq!(position(&conn, "w"), ("work".to_string(), 3));
+    }
+}
@@ -0,0 +1,95 @@
+use std::path::Path;
+use rayon::prelude::*;
+use tauri::State;
+use crate::commands::file::{query_file, read_file_info, update_item_target};
+use crate::db::DbState;
+use crate::file_watcher::FileWatcher;
+use crate::health::{check_target, find_candidates, InstallIndex};
+use crate::icon_cache::prune_unused_icons;
+use crate::journal::Recorder;
+use crate::models::{EditAction, FileInfo, ItemHealth};
+use crate::thumbnail::prune_stale_thumbnails;
+
+// 检查所有文件启动项的目标，返回失效的启动项和可能的新位置
+// 查找新位置需要遍历目录，在后台线程中并行检查，期间不持有数据库连接
+#[tauri::command]
+pub async fn check_item_health(db: State<'_, DbState>) -> Result<Vec<ItemHealth>, String> {
+    let items: Vec<(String, String, String)> = {
+        let conn = db.conn()?;
+        let mut stmt = conn
+            .prepare_cached("SELECT id, display_name, path FROM files WHERE kind = 'file' AND deleted_at IS NULL ORDER BY category, sort_order")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let problems: Vec<_> = items
+            .into_par_iter()
+            .filter_map(|(id, display_name, path)| {
+                let problem = check_target(Path::new(&path))?;
+                Some((id, display_name, path, problem))
+            })
+            .collect();
+
+        // 有需要重新查找位置的启动项时才遍历安装目录
+        let install_index = if problems.iter().any(|(_, _, _, problem)| problem.missing.is_some()) {
+            InstallIndex::build()
+        } else {
+            InstallIndex::default()
+        };
+        problems
+            .into_par_iter()
+            .map(|(id, display_name, path, problem)| {
+                let candidates = problem
+                    .missing
+                    .as_deref()
+                    .map(|missing| find_candidates(missing, &install_index))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|candidate| candidate.to_string_lossy().to_string())
+                    .collect();
+                ItemHealth {
+                    id,
+                    display_name,
+                    path,
+                    issue: problem.issue,
+                    detail: problem.detail,
+                    candidates,
+                }
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+// 将启动项指向新的位置，重新读取名称、大小、类型、图标和预览内容
+// 显示名称、分类、打开次数和启动选项保持不变
+#[tauri::command]
+pub fn relocate_item(
+    db: State<'_, DbState>,
+    watcher: State<'_, FileWatcher>,
+    id: String,
+    path: String,
+) -> Result<FileInfo, String> {
+    let info = read_file_info(&path)?;
+    let mut conn = db.conn()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut recorder = Recorder::new(EditAction::RelocateItem);
+    recorder.files(&tx, [&id])?;
+    if update_item_target(&tx, &id, &info)? == 0 {
+        return Err(format!("File not found: {}", id));
+    }
+    recorder.finish(&tx)?;
+
+    prune_unused_icons(&tx).map_err(|e| e.to_string())?;
+    prune_stale_thumbnails(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    // 新位置可能在未监视的目录中
+    watcher.sync(&conn);
+
+    query_file(&conn, &id)?.ok_or_else(|| format!("File not found: {}", id))
+}
@@ -0,0 +1,38 @@
+use tauri::State;
+use crate::db::DbState;
+use crate::file_watcher::FileWatcher;
+use crate::icon_cache::prune_unused_icons;
+use crate::journal;
+use crate::models::JournalEntry;
+
+// 撤销最近一次编辑，返回被撤销的编辑，没有可以撤销的编辑时返回空
+#[tauri::command]
+pub fn undo(db: State<'_, DbState>, watcher: State<'_, FileWatcher>) -> Result<Option<JournalEntry>, String> {
+    let mut conn = db.conn()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let entry = journal::undo(&tx)?;
+    prune_unused_icons(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    if let Some(entry) = &entry {
+        println!("Undid {} #{}", entry.action.as_str(), entry.id);
+        watcher.sync(&conn);
+    }
+    Ok(entry)
+}
+
+// 重做最近一次撤销的编辑，返回被重做的编辑，没有可以重做的编辑时返回空
+#[tauri::command]
+pub fn redo(db: State<'_, DbState>, watcher: State<'_, FileWatcher>) -> Result<Option<JournalEntry>, String> {
+    let mut conn = db.conn()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let entry = journal::redo(&tx)?;
+    prune_unused_icons(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    if let Some(entry) = &entry {
+        println!("Redid {} #{}", entry.action.as_str(), entry.id);
+        watcher.sync(&conn);
+    }
+    Ok(entry)
+}
@@ -0,0 +1,265 @@
+use crate::commands::file::open_command;
+use crate::db::DbState;
+use crate::launch_tracker::{mark_failed, ExitReport, LaunchTarget, LaunchTracker};
+use crate::launcher;
+use crate::shell_command;
+use crate::utils::now_millis;
+use crate::models::{ItemKind, LaunchOptions, LaunchRecord, RunningLaunch};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+// 按指定的参数、工作目录和环境变量直接启动程序，不记录启动历史
+#[tauri::command]
+pub fn launch_with_options(path: String, options: LaunchOptions) -> Result<(), String> {
+    launcher::spawn(Path::new(&path), &options).map(|_| ())
+}
+
+// 启动文件并记录启动历史，成功时打开次数加一
+// 返回更新后的打开次数
+#[tauri::command]
+pub fn launch_item(
+    app: AppHandle,
+    db: State<'_, DbState>,
+    tracker: State<'_, LaunchTracker>,
+    id: String,
+) -> Result<u64, String> {
+    let (path, display_name, options, kind, shell, keep_open): (String, Option<String>, LaunchOptions, ItemKind, Option<String>, bool) = {
+        let conn = db.conn()?;
+        let item = conn
+            .prepare_cached("SELECT path, display_name, launch_args, working_dir, env, run_in_terminal, kind, shell, keep_open FROM files WHERE id = ?")
+            .map_err(|e| e.to_string())?
+            .query_row([&id], |row| {
+                let options = LaunchOptions {
+                    launch_args: row.get(2)?,
+                    working_dir: row.get(3)?,
+                    env: row
+                        .get::<_, Option<String>>(4)?
+                        .and_then(|env| serde_json::from_str(&env).ok())
+                        .unwrap_or_default(),
+                    run_in_terminal: row.get(5)?,
+                };
+                let kind = ItemKind::from_db(&row.get::<_, String>(6)?);
+                Ok((row.get(0)?, row.get(1)?, options, kind, row.get(7)?, row.get(8)?))
+            })
+            .optional()
+            .map_err(|e| e.to_string())?;
+        item.ok_or_else(|| format!("File not found in database: {}", id))?
+    };
+
+    // 命令由 shell 执行，不在终端中运行时捕获输出并通过系统通知显示结果
+    // 文件设置了启动选项时直接启动程序，否则交给系统默认程序打开，URL 总是交给系统处理
+    // Windows 上 explorer.exe 成功打开时也会返回 1，此时不根据退出码判断失败
+    let (command, report, via_opener) = match kind {
+        ItemKind::Command => {
+            let command = shell_command::build_command(
+                &path,
+                shell.as_deref(),
+                options.working_dir.as_deref(),
+                &options.env,
+                options.run_in_terminal,
+                keep_open,
+            );
+            let report = if options.run_in_terminal { ExitReport::Ignore } else { ExitReport::Notify };
+            (command, report, false)
+        }
+        ItemKind::File if launcher::has_options(&options) => {
+            (launcher::build_command(Path::new(&path), &options), ExitReport::EarlyFailure, false)
+        }
+        _ if cfg!(target_os = "windows") => (open_command(&path), ExitReport::Ignore, true),
+        _ => (open_command(&path), ExitReport::EarlyFailure, true),
+    };
+
+    // 先写入启动历史，进程跟踪按记录 ID 标记之后的失败；启动过程不持有数据库锁
+    let history_id = {
+        let conn = db.conn()?;
+        insert_history(&conn, &id, now_millis())?
+    };
+    let target = LaunchTarget {
+        file_id: &id,
+        display_name,
+        path: &path,
+        via_opener,
+        history_id,
+    };
+    let result = command.and_then(|command| tracker.spawn(&app, command, target, report));
+    let conn = db.conn()?;
+    let open_count = record_result(&conn, &id, history_id, result.as_ref().err())?;
+    result.map(|_| open_count)
+}
+
+// 写入一条启动成功的历史记录，返回记录 ID
+fn insert_history(conn: &Connection, file_id: &str, launched_at: i64) -> Result<i64, String> {
+    conn.prepare_cached("INSERT INTO launch_history (file_id, launched_at, success, error) VALUES (?, ?, 1, NULL)")
+        .map_err(|e| e.to_string())?
+        .execute(params![file_id, launched_at])
+        .map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+// 启动失败时更新历史记录，成功时打开次数加一，返回当前的打开次数
+fn record_result(conn: &Connection, file_id: &str, history_id: i64, error: Option<&String>) -> Result<u64, String> {
+    match error {
+        Some(error) => mark_failed(conn, history_id, error)?,
+        None => {
+            conn.prepare_cached("UPDATE files SET open_count = IFNULL(open_count, 0) + 1 WHERE id = ?")
+                .map_err(|e| e.to_string())?
+                .execute([file_id])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    let open_count: i64 = conn
+        .prepare_cached("SELECT IFNULL(open_count, 0) FROM files WHERE id = ?")
+        .map_err(|e| e.to_string())?
+        .query_row([file_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    Ok(open_count as u64)
+}
+
+// 由启动器启动、仍在运行的进程
+#[tauri::command]
+pub fn list_running_launches(tracker: State<'_, LaunchTracker>) -> Result<Vec<RunningLaunch>, String> {
+    tracker.running()
+}
+
+// 结束由启动器启动的进程
+#[tauri::command]
+pub fn kill_launch(tracker: State<'_, LaunchTracker>, launch_id: u64) -> Result<(), String> {
+    tracker.kill(launch_id)
+}
+
+// 查询启动历史，按时间倒序
+// 传入 file_id 时只返回该文件的记录
+#[tauri::command]
+pub fn get_launch_history(
+    db: State<'_, DbState>,
+    file_id: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<LaunchRecord>, String> {
+    let conn = db.conn()?;
+    query_history(&conn, file_id.as_deref(), limit)
+}
+
+fn query_history(conn: &Connection, file_id: Option<&str>, limit: Option<u32>) -> Result<Vec<LaunchRecord>, String> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT h.id, h.file_id, f.display_name, f.path, h.launched_at, h.success, h.error
+         FROM launch_history h
+         LEFT JOIN files f ON f.id = h.file_id
+         WHERE ?1 IS NULL OR h.file_id = ?1
+         ORDER BY h.launched_at DESC, h.id DESC
+         LIMIT ?2"
+    ).map_err(|e| e.to_string())?;
+
+    let records_iter = stmt.query_map(params![file_id, limit.unwrap_or(100)], |row| {
+        Ok(LaunchRecord {
+            id: row.get(0)?,
+            file_id: row.get(1)?,
+            display_name: row.get(2)?,
+            path: row.get(3)?,
+            launched_at: row.get(4)?,
+            success: row.get(5)?,
+            error: row.get(6)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut records = Vec::new();
+    for record in records_iter {
+        records.push(record.map_err(|e| e.to_string())?);
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::init_database(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO files (id, name, display_name, path, open_count) VALUES
+                ('a', 'a', 'App A', '/bin/a', 2),
+                ('b', 'b', 'App B', '/bin/b', NULL);",
+        )
+        .unwrap();
+        conn
+    }
+
+    // (launched_at, success, error)
+    fn history_row(conn: &Connection, id: i64) -> (i64, bool, Option<String>) {
+        conn.query_row("SELECT launched_at, success, error FROM launch_history WHERE id = ?", [id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn successful_launch_records_history_and_counts() {
+        let conn = test_db();
+
+        let history_id = insert_history(&conn, "a", 100).unwrap();
+        let open_count = record_result(&conn, "a", history_id, None).unwrap();
+
+        assert_eq!(open_count, 3);
+        assert_eq!(history_row(&conn, history_id), (100, true, None));
+
+        // 没有打开次数的文件从 0 开始计数
+        let history_id = insert_history(&conn, "b", 200).unwrap();
+        assert_eq!(record_result(&conn, "b", history_id, None).unwrap(), 1);
+    }
+
+    #[test]
+    fn failed_launch_keeps_open_count() {
+        let conn = test_db();
+        let error = "Failed to launch /bin/a: not found".to_string();
+
+        let history_id = insert_history(&conn, "a", 100).unwrap();
+        let open_count = record_result(&conn, "a", history_id, Some(&error)).unwrap();
+
+        assert_eq!(open_count, 2);
+        assert_eq!(history_row(&conn, history_id), (100, false, Some(error)));
+    }
+
+    #[test]
+    fn failure_marks_only_its_own_record() {
+        let conn = test_db();
+        // 同一毫秒内启动两次
+        let first = insert_history(&conn, "a", 100).unwrap();
+        let second = insert_history(&conn, "a", 100).unwrap();
+
+        mark_failed(&conn, second, "Process exited with code 1").unwrap();
+
+        assert_eq!(history_row(&conn, first), (100, true, None));
+        assert_eq!(
+            history_row(&conn, second),
+            (100, false, Some("Process exited with code 1".to_string()))
+        );
+    }
+
+    #[test]
+    fn history_is_newest_first_and_limited() {
+        let conn = test_db();
+        let old = insert_history(&conn, "a", 100).unwrap();
+        let newest = insert_history(&conn, "b", 300).unwrap();
+        // 启动时间相同时按记录 ID 倒序
+        let tied_first = insert_history(&conn, "a", 200).unwrap();
+        let tied_second = insert_history(&conn, "a", 200).unwrap();
+        let orphan = insert_history(&conn, "gone", 50).unwrap();
+
+        let ids = |records: Vec<LaunchRecord>| records.into_iter().map(|r| r.id).collect::<Vec<_>>();
+
+        let all = query_history(&conn, None, None).unwrap();
+        assert_eq!(all[0].display_name.as_deref(), Some("App B"));
+        assert_eq!(all[4].path, None);
+        assert_eq!(ids(all), vec![newest, tied_second, tied_first, old, orphan]);
+
+        assert_eq!(ids(query_history(&conn, None, Some(2)).unwrap()), vec![newest, tied_second]);
+        assert_eq!(
+            ids(query_history(&conn, Some("a"), None).unwrap()),
+            vec![tied_second, tied_first, old]
+        );
+        assert_eq!(ids(query_history(&conn, Some("a"), Some(1)).unwrap()), vec![tied_second]);
+    }
+}
@@ -1,9 +1,19 @@
 pub mod file;
 pub mod category;
+pub mod launch;
+pub mod search;
 pub mod notification;
 pub mod app;
+pub mod health;
+pub mod trash;
+pub mod journal;
 
 pub use file::*;
 pub use category::*;
+pub use launch::*;
+pub use search::*;
 pub use notification::*;
 pub use app::*;
+pub use health::*;
+pub use trash::*;
+pub use journal::*;
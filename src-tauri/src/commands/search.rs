@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use crate::db::DbState;
+use crate::models::SearchHit;
+use crate::pinyin_index;
+use crate::search::{fuzzy_match, pinyin_match, snippet_around, substring_match, to_ranges};
+use tauri::State;
+
+const DEFAULT_LIMIT: usize = 50;
+
+// 各字段命中时的权重（百分比）
+const WEIGHT_DISPLAY_NAME: i64 = 100;
+const WEIGHT_PINYIN: i64 = 95;
+const WEIGHT_NAME: i64 = 90;
+const WEIGHT_PATH: i64 = 50;
+
+// 全文检索命中时的额外加分，按排名递减
+const FTS_BONUS: i64 = 40;
+
+// trigram 分词要求查询至少 3 个字符
+const FTS_MIN_CHARS: usize = 3;
+
+// 单个字段的命中结果：(得分, 字段名, 高亮区间)
+type FieldMatch = (i64, &'static str, Vec<(usize, usize)>);
+
+// 用 FTS5 在名称、路径和文本内容中检索，返回按相关度排序的文件 ID，回收站中的文件不计入
+fn full_text_hits(conn: &rusqlite::Connection, query: &str, limit: usize) -> Result<Vec<String>, String> {
+    if query.chars().count() < FTS_MIN_CHARS {
+        return Ok(Vec::new());
+    }
+
+    // 作为短语查询，避免用户输入被解析成 FTS 语法
+    let phrase = format!("\"{}\"", query.replace('"', "\"\""));
+    let mut stmt = conn.prepare_cached(
+        "SELECT files_fts.file_id FROM files_fts JOIN files ON files.id = files_fts.file_id
+         WHERE files_fts MATCH ? AND files.deleted_at IS NULL
+         ORDER BY bm25(files_fts, 10.0, 10.0, 2.0, 1.0) LIMIT ?"
+    ).map_err(|e| e.to_string())?;
+
+    let ids = stmt
+        .query_map(rusqlite::params![phrase, limit as i64], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(ids)
+}
+
+// 在启动器文件库中搜索，结合全文检索与名称的模糊匹配（支持全拼、首字母及混合输入）
+#[tauri::command]
+pub fn search_items(db: State<'_, DbState>, query: String, limit: Option<usize>) -> Result<Vec<SearchHit>, String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+
+    let conn = db.conn()?;
+
+    let fts_rank: HashMap<String, usize> = full_text_hits(&conn, query, limit.max(DEFAULT_LIMIT))?
+        .into_iter()
+        .enumerate()
+        .map(|(rank, id)| (id, rank))
+        .collect();
+
+    let mut stmt = conn
+        .prepare_cached("SELECT id, name, display_name, path, category, content, pinyin FROM files WHERE deleted_at IS NULL")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, Option<String>>(5)?,
+            row.get::<_, Option<String>>(6)?,
+        ))
+    }).map_err(|e| e.to_string())?;
+
+    let mut hits = Vec::new();
+    for row in rows {
+        let (id, name, display_name, path, category, content, pinyin) = row.map_err(|e| e.to_string())?;
+
+        // 依次尝试各字段，保留加权后得分最高的一个
+        let mut best: Option<FieldMatch> = None;
+        let mut consider = |score: i64, field: &'static str, ranges: Vec<(usize, usize)>| {
+            if best.as_ref().map(|(s, _, _)| score > *s).unwrap_or(true) {
+                best = Some((score, field, ranges));
+            }
+        };
+
+        if let Some(m) = fuzzy_match(query, &display_name) {
+            consider(m.score * WEIGHT_DISPLAY_NAME / 100, "display_name", to_ranges(&m.positions));
+        }
+        if let Some(readings) = pinyin_index::readings(&display_name, pinyin.as_deref()) {
+            if let Some(m) = pinyin_match(query, &display_name, &readings) {
+                consider(m.score * WEIGHT_PINYIN / 100, "display_name", to_ranges(&m.positions));
+            }
+        }
+        if let Some(m) = fuzzy_match(query, &name) {
+            consider(m.score * WEIGHT_NAME / 100, "name", to_ranges(&m.positions));
+        }
+        // 路径只接受连续子串，避免长路径上的子序列噪声
+        if let Some(m) = substring_match(query, &path) {
+            consider(m.score * WEIGHT_PATH / 100, "path", to_ranges(&m.positions));
+        }
+
+        let mut snippet = None;
+        if let Some(rank) = fts_rank.get(&id) {
+            let bonus = (FTS_BONUS - *rank as i64).max(1);
+            match best.as_mut() {
+                Some((score, _, _)) => *score += bonus,
+                None => {
+                    // 只在文本内容中命中
+                    let (text, ranges) = content
+                        .as_deref()
+                        .and_then(|c| snippet_around(c, query, 20))
+                        .unwrap_or_default();
+                    snippet = Some(text);
+                    best = Some((bonus, "content", ranges));
+                }
+            }
+        }
+
+        if let Some((score, field, highlights)) = best {
+            hits.push(SearchHit {
+                id,
+                name,
+                display_name,
+                path,
+                category,
+                score,
+                matched_field: field.to_string(),
+                highlights,
+                snippet,
+            });
+        }
+    }
+
+    hits.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.display_name.cmp(&b.display_name))
+    });
+    hits.truncate(limit);
+
+    Ok(hits)
+}
@@ -0,0 +1,394 @@
+use std::collections::HashSet;
+use std::path::Path;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use tauri::State;
+use crate::commands::category::query_trashed_categories;
+use crate::commands::file::{next_sort_order, query_trashed_files};
+use crate::db::DbState;
+use crate::file_watcher::FileWatcher;
+use crate::icon_cache::prune_unused_icons;
+use crate::journal::Recorder;
+use crate::models::{EditAction, TrashContents, TrashSummary};
+use crate::thumbnail::prune_stale_thumbnails;
+use crate::utils::now_millis;
+
+// 回收站：删除的项目和分类记录删除时间，可以恢复，清空或过期后才真正删除
+// 同一次删除的分类和项目删除时间相同，恢复分类时按删除时间一起恢复
+
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+// 读取回收站中的项目和分类
+#[tauri::command]
+pub fn list_trash(db: State<'_, DbState>) -> Result<TrashContents, String> {
+    let conn = db.conn()?;
+    Ok(TrashContents {
+        items: query_trashed_files(&conn)?,
+        categories: query_trashed_categories(&conn)?,
+    })
+}
+
+// 恢复分类和它所有在回收站中的上级分类，返回恢复的分类数量
+// 分类不存在时返回 None，上级分类已被清除时改为顶级分类
+fn restore_category_chain(tx: &Transaction, id: &str) -> Result<Option<usize>, String> {
+    let mut restored = 0;
+    let mut child: Option<String> = None;
+    let mut current = Some(id.to_string());
+    let mut seen = HashSet::new();
+
+    while let Some(category) = current.take() {
+        // parent_id 出现环时停止
+        if !seen.insert(category.clone()) {
+            break;
+        }
+        let row: Option<(Option<String>, Option<i64>)> = tx
+            .prepare_cached("SELECT parent_id, deleted_at FROM categories WHERE id = ?")
+            .and_then(|mut stmt| stmt.query_row([&category], |row| Ok((row.get(0)?, row.get(1)?))).optional())
+            .map_err(|e| e.to_string())?;
+
+        let Some((parent_id, deleted_at)) = row else {
+            match &child {
+                Some(child) => {
+                    tx.execute("UPDATE categories SET parent_id = NULL WHERE id = ?", [child])
+                        .map_err(|e| e.to_string())?;
+                }
+                None => return Ok(None),
+            }
+            break;
+        };
+
+        if deleted_at.is_some() {
+            restored += tx
+                .execute("UPDATE categories SET deleted_at = NULL WHERE id = ?", [&category])
+                .map_err(|e| e.to_string())?;
+        }
+        child = Some(category);
+        current = parent_id;
+    }
+    Ok(Some(restored))
+}
+
+// 回收站中的项目不跟随文件监视更新，恢复后重新检查目标是否存在
+fn refresh_missing(tx: &Transaction, ids: &[String]) -> Result<(), String> {
+    for id in ids {
+        let path: Option<String> = tx
+            .prepare_cached("SELECT path FROM files WHERE id = ? AND kind = 'file' AND deleted_at IS NULL")
+            .and_then(|mut stmt| stmt.query_row([id], |row| row.get(0)).optional())
+            .map_err(|e| e.to_string())?;
+        if let Some(path) = path {
+            tx.prepare_cached("UPDATE files SET missing = ? WHERE id = ?")
+                .and_then(|mut stmt| stmt.execute(params![!Path::new(&path).exists(), id]))
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+// 从回收站恢复项目，追加到原分类末尾
+// 原分类在回收站中时一起恢复，原分类已被清除时恢复到主分类
+fn restore_trashed_items(tx: &Transaction, ids: &[String]) -> Result<TrashSummary, String> {
+    // 恢复的分类和上级分类事先不知道，分类数量不多，全部记录
+    let mut recorder = Recorder::new(EditAction::RestoreItems);
+    recorder.files(tx, ids)?;
+    recorder.categories_where(tx, "SELECT id FROM categories", [])?;
+
+    let mut summary = TrashSummary::default();
+    for id in ids {
+        let category: Option<String> = tx
+            .prepare_cached("SELECT category FROM files WHERE id = ? AND deleted_at IS NOT NULL")
+            .and_then(|mut stmt| stmt.query_row([id], |row| row.get(0)).optional())
+            .map_err(|e| e.to_string())?;
+        let Some(category) = category else {
+            continue;
+        };
+
+        let target = match restore_category_chain(tx, &category)? {
+            Some(restored) => {
+                summary.categories += restored;
+                category
+            }
+            None => "main".to_string(),
+        };
+        let sort_order = next_sort_order(tx, &target)?;
+        summary.items += tx
+            .prepare_cached("UPDATE files SET deleted_at = NULL, category = ?, sort_order = ? WHERE id = ?")
+            .and_then(|mut stmt| stmt.execute(params![&target, sort_order, id]))
+            .map_err(|e| e.to_string())?;
+    }
+    refresh_missing(tx, ids)?;
+    recorder.finish(tx)?;
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn restore_items(db: State<'_, DbState>, watcher: State<'_, FileWatcher>, ids: Vec<String>) -> Result<TrashSummary, String> {
+    let mut conn = db.conn()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let summary = restore_trashed_items(&tx, &ids)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    watcher.sync(&conn);
+    println!("Restored {} items and {} categories", summary.items, summary.categories);
+    Ok(summary)
+}
+
+// 从回收站恢复分类，同一次删除的子分类和项目一起恢复
+// 上级分类在回收站中时一起恢复，之后单独删除的项目仍留在回收站中
+fn restore_trashed_categories(tx: &Transaction, ids: &[String]) -> Result<TrashSummary, String> {
+    let mut recorder = Recorder::new(EditAction::RestoreCategories);
+    recorder.files_where(tx, "SELECT id FROM files WHERE deleted_at IS NOT NULL", [])?;
+    recorder.categories_where(tx, "SELECT id FROM categories", [])?;
+
+    let mut summary = TrashSummary::default();
+    let mut restored_items = Vec::new();
+    for id in ids {
+        let deleted_at: Option<i64> = tx
+            .prepare_cached("SELECT deleted_at FROM categories WHERE id = ? AND deleted_at IS NOT NULL")
+            .and_then(|mut stmt| stmt.query_row([id], |row| row.get(0)).optional())
+            .map_err(|e| e.to_string())?;
+        let Some(deleted_at) = deleted_at else {
+            continue;
+        };
+
+        // 同一次删除的子分类，UNION 去重，parent_id 出现环时也能结束
+        let subtree: Vec<String> = {
+            let mut stmt = tx
+                .prepare_cached(
+                    "WITH RECURSIVE subtree(id) AS (
+                        SELECT ?1
+                        UNION
+                        SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id WHERE c.deleted_at = ?2
+                    )
+                    SELECT id FROM subtree",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![id, deleted_at], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+        };
+
+        summary.categories += restore_category_chain(tx, id)?.unwrap_or(0);
+        for category in &subtree {
+            restored_items.extend(
+                tx.prepare_cached("SELECT id FROM files WHERE category = ? AND deleted_at = ?")
+                    .and_then(|mut stmt| {
+                        stmt.query_map(params![category, deleted_at], |row| row.get::<_, String>(0))?
+                            .collect::<rusqlite::Result<Vec<_>>>()
+                    })
+                    .map_err(|e| e.to_string())?,
+            );
+            summary.categories += tx
+                .execute(
+                    "UPDATE categories SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL",
+                    [category],
+                )
+                .map_err(|e| e.to_string())?;
+            summary.items += tx
+                .execute(
+                    "UPDATE files SET deleted_at = NULL WHERE category = ? AND deleted_at = ?",
+                    params![category, deleted_at],
+                )
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    refresh_missing(tx, &restored_items)?;
+    recorder.finish(tx)?;
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn restore_categories(db: State<'_, DbState>, watcher: State<'_, FileWatcher>, ids: Vec<String>) -> Result<TrashSummary, String> {
+    let mut conn = db.conn()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let summary = restore_trashed_categories(&tx, &ids)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    watcher.sync(&conn);
+    println!("Restored {} categories and {} items", summary.categories, summary.items);
+    Ok(summary)
+}
+
+// 彻底删除在 before 之前移到回收站的项目和分类，并清理不再使用的图标和缩略图
+fn purge(conn: &mut Connection, before: i64) -> Result<TrashSummary, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let summary = TrashSummary {
+        items: tx
+            .execute("DELETE FROM files WHERE deleted_at IS NOT NULL AND deleted_at < ?", [before])
+            .map_err(|e| e.to_string())?,
+        categories: tx
+            .execute("DELETE FROM categories WHERE deleted_at IS NOT NULL AND deleted_at < ?", [before])
+            .map_err(|e| e.to_string())?,
+    };
+    prune_unused_icons(&tx).map_err(|e| e.to_string())?;
+    prune_stale_thumbnails(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+// 清空回收站
+#[tauri::command]
+pub fn empty_trash(db: State<'_, DbState>) -> Result<TrashSummary, String> {
+    let mut conn = db.conn()?;
+    let summary = purge(&mut conn, i64::MAX)?;
+    println!("Emptied trash: {} items, {} categories", summary.items, summary.categories);
+    Ok(summary)
+}
+
+// 清除在回收站中超过 days 天的项目和分类，程序启动时调用
+#[tauri::command]
+pub fn purge_trash(db: State<'_, DbState>, days: u32) -> Result<TrashSummary, String> {
+    let before = now_millis() - days as i64 * DAY_MILLIS;
+    let mut conn = db.conn()?;
+    let summary = purge(&mut conn, before)?;
+    if summary.items > 0 || summary.categories > 0 {
+        println!(
+            "Purged {} items and {} categories older than {} days from trash",
+            summary.items, summary.categories, days
+        );
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icon_cache::intern_icon;
+
+    // work 和子分类 dev 在时间 10 一起删除，d2 在此之前单独删除，x 的分类已被清除
+    fn test_db(existing: &Path) -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::init_database(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO categories (id, parent_id, name, sort_order, deleted_at) VALUES
+                ('main', NULL, 'Main', 0, NULL),
+                ('work', NULL, 'Work', 1, 10),
+                ('dev', 'work', 'Dev', 0, 10);",
+        )
+        .unwrap();
+        let existing = existing.to_string_lossy();
+        for (id, category, path, sort_order, deleted_at) in [
+            ("m1", "main", "/nonexistent/m1", 0, None),
+            ("w1", "work", "/nonexistent/w1", 0, Some(10)),
+            ("d1", "dev", existing.as_ref(), 0, Some(10)),
+            ("d2", "dev", "/nonexistent/d2", 1, Some(5)),
+            ("x", "gone", "/nonexistent/x", 0, Some(10)),
+            ("late", "main", "/nonexistent/late", 1, Some(20)),
+        ] {
+            conn.execute(
+                "INSERT INTO files (id, name, display_name, path, kind, category, sort_order, deleted_at)
+                 VALUES (?1, ?1, ?1, ?2, 'file', ?3, ?4, ?5)",
+                params![id, path, category, sort_order, deleted_at],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    // (category, sort_order, deleted_at, missing)
+    fn file(conn: &Connection, id: &str) -> Option<(String, i64, Option<i64>, bool)> {
+        conn.query_row(
+            "SELECT category, sort_order, deleted_at, missing FROM files WHERE id = ?",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()
+        .unwrap()
+    }
+
+    fn category_deleted_at(conn: &Connection, id: &str) -> Option<Option<i64>> {
+        conn.query_row("SELECT deleted_at FROM categories WHERE id = ?", [id], |row| row.get(0))
+            .optional()
+            .unwrap()
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn restoring_item_restores_its_category_chain() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut conn = test_db(temp.path());
+        let tx = conn.transaction().unwrap();
+        let summary = restore_trashed_items(&tx, &ids(&["d1", "x", "m1"])).unwrap();
+        tx.commit().unwrap();
+
+        // m1 不在回收站中，不计入
+        assert_eq!((summary.items, summary.categories), (2, 2));
+        assert_eq!(category_deleted_at(&conn, "dev"), Some(None));
+        assert_eq!(category_deleted_at(&conn, "work"), Some(None));
+        // 追加到原分类末尾，重新检查目标是否存在
+        assert_eq!(file(&conn, "d1"), Some(("dev".to_string(), 2, None, false)));
+        // 原分类已被清除时恢复到主分类
+        assert_eq!(file(&conn, "x"), Some(("main".to_string(), 2, None, true)));
+        // 同一分类中的其他项目仍在回收站中
+        assert_eq!(file(&conn, "w1").unwrap().2, Some(10));
+        assert_eq!(file(&conn, "d2").unwrap().2, Some(5));
+    }
+
+    #[test]
+    fn restore_category_chain_detaches_from_purged_parent() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut conn = test_db(temp.path());
+        conn.execute("DELETE FROM categories WHERE id = 'work'", []).unwrap();
+        let tx = conn.transaction().unwrap();
+        assert_eq!(restore_category_chain(&tx, "dev").unwrap(), Some(1));
+        assert_eq!(restore_category_chain(&tx, "gone").unwrap(), None);
+        let parent: Option<String> = tx
+            .query_row("SELECT parent_id FROM categories WHERE id = 'dev'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(parent, None);
+    }
+
+    #[test]
+    fn restoring_category_keeps_separately_trashed_items() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut conn = test_db(temp.path());
+        let tx = conn.transaction().unwrap();
+        let summary = restore_trashed_categories(&tx, &ids(&["dev"])).unwrap();
+        tx.commit().unwrap();
+
+        // dev 和上级分类 work 一起恢复
+        assert_eq!((summary.items, summary.categories), (1, 2));
+        assert_eq!(file(&conn, "d1"), Some(("dev".to_string(), 0, None, false)));
+        // 在分类之前单独删除的项目留在回收站中
+        assert_eq!(file(&conn, "d2").unwrap().2, Some(5));
+        // 上级分类中的项目不属于 dev 的子树，留在回收站中
+        assert_eq!(file(&conn, "w1").unwrap().2, Some(10));
+    }
+
+    #[test]
+    fn purge_removes_only_rows_before_cutoff() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut conn = test_db(temp.path());
+        let purged_icon = intern_icon(&conn, "data:image/png;base64,AAAA").unwrap();
+        let kept_icon = intern_icon(&conn, "data:image/png;base64,BBBB").unwrap();
+        conn.execute("UPDATE files SET icon = ? WHERE id = 'w1'", [&purged_icon]).unwrap();
+        conn.execute("UPDATE files SET icon = ? WHERE id = 'late'", [&kept_icon]).unwrap();
+        for path in ["/nonexistent/w1", "/nonexistent/late"] {
+            conn.execute(
+                "INSERT INTO thumbnail_cache (path, size, mtime, file_size, data, created_at) VALUES (?, 64, 0, 0, x'00', 0)",
+                [path],
+            )
+            .unwrap();
+        }
+
+        let summary = purge(&mut conn, 15).unwrap();
+        assert_eq!((summary.items, summary.categories), (4, 2));
+        for id in ["w1", "d1", "d2", "x"] {
+            assert_eq!(file(&conn, id), None, "file {}", id);
+        }
+        assert_eq!(file(&conn, "late").unwrap().2, Some(20));
+        assert!(file(&conn, "m1").is_some());
+        assert_eq!(category_deleted_at(&conn, "work"), None);
+
+        let icons: i64 = conn.query_row("SELECT COUNT(*) FROM icon_cache", [], |row| row.get(0)).unwrap();
+        assert_eq!(icons, 1);
+        let thumbnails: Vec<String> = conn
+            .prepare("SELECT path FROM thumbnail_cache")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(thumbnails, ["/nonexistent/late"]);
+    }
+}
@@ -0,0 +1,794 @@
+use rusqlite::{Connection, Transaction, TransactionBehavior};
+use base64::engine::general_purpose;
+use base64::Engine as _;
+use sha2::{Digest, Sha256};
+
+// 单个数据库结构迁移
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub up: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+// 按版本号递增排列的迁移列表
+// 已发布的迁移不要再修改，新的结构变更只能追加到末尾
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline files and categories tables",
+        up: migrate_v1_baseline,
+    },
+    Migration {
+        version: 2,
+        description: "manual sort order for files",
+        up: migrate_v2_file_sort_order,
+    },
+    Migration {
+        version: 3,
+        description: "launch history",
+        up: migrate_v3_launch_history,
+    },
+    Migration {
+        version: 4,
+        description: "full-text search index",
+        up: migrate_v4_full_text_search,
+    },
+    Migration {
+        version: 5,
+        description: "pinyin index for display names",
+        up: migrate_v5_pinyin_index,
+    },
+    Migration {
+        version: 6,
+        description: "deduplicated icon cache",
+        up: migrate_v6_icon_cache,
+    },
+    Migration {
+        version: 7,
+        description: "image thumbnail cache",
+        up: migrate_v7_thumbnail_cache,
+    },
+    Migration {
+        version: 8,
+        description: "content-based mime type for files",
+        up: migrate_v8_file_mime,
+    },
+    Migration {
+        version: 9,
+        description: "launch arguments, working directory and environment",
+        up: migrate_v9_launch_options,
+    },
+    Migration {
+        version: 10,
+        description: "item kind for url items",
+        up: migrate_v10_item_kind,
+    },
+    Migration {
+        version: 11,
+        description: "shell and keep-open flag for command items",
+        up: migrate_v11_command_items,
+    },
+    Migration {
+        version: 12,
+        description: "missing flag maintained by the file watcher",
+        up: migrate_v12_missing_flag,
+    },
+    Migration {
+        version: 13,
+        description: "watched folder for categories",
+        up: migrate_v13_category_folder,
+    },
+    Migration {
+        version: 14,
+        description: "soft delete for files",
+        up: migrate_v14_file_deleted_at,
+    },
+    Migration {
+        version: 15,
+        description: "soft delete for categories",
+        up: migrate_v15_category_deleted_at,
+    },
+    Migration {
+        version: 16,
+        description: "undo journal",
+        up: migrate_v16_journal,
+    },
+];
+
+// 当前程序支持的最新结构版本
+pub fn latest_version(migrations: &[Migration]) -> i32 {
+    migrations.last().map(|m| m.version).unwrap_or(0)
+}
+
+// 读取数据库当前的结构版本
+pub fn current_version(conn: &Connection) -> Result<i32, String> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+// 依次执行尚未应用的迁移，每个迁移在独立事务中完成
+// 迁移失败时事务回滚，user_version 保持在上一个成功的版本
+pub fn run_migrations(conn: &mut Connection, migrations: &[Migration]) -> Result<(), String> {
+    let current = current_version(conn)?;
+    let latest = latest_version(migrations);
+
+    // 拒绝打开比当前程序更新的数据库，避免旧版本写坏新结构
+    if current > latest {
+        return Err(format!(
+            "Database schema version {} is newer than the supported version {}, please upgrade OopsLauncher",
+            current, latest
+        ));
+    }
+
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| e.to_string())?;
+
+        (migration.up)(&tx).map_err(|e| {
+            format!(
+                "Migration {} ({}) failed: {}",
+                migration.version, migration.description, e
+            )
+        })?;
+
+        tx.pragma_update(None, "user_version", migration.version)
+            .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+
+        println!(
+            "Applied database migration {}: {}",
+            migration.version, migration.description
+        );
+    }
+
+    Ok(())
+}
+
+// 检查表中是否存在指定列
+fn has_column(tx: &Transaction, table: &str, column: &str) -> rusqlite::Result<bool> {
+    let mut stmt = tx.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(columns.iter().any(|c| c == column))
+}
+
+// 版本 1：引入版本号之前的所有历史结构统一升级到基线结构
+// 老数据库的 user_version 均为 0，列可能缺失任意一部分，因此这里仍需逐列检查
+fn migrate_v1_baseline(tx: &Transaction) -> rusqlite::Result<()> {
+    // 创建文件表
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS files (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            display_name TEXT NOT NULL,
+            path TEXT NOT NULL,
+            size INTEGER,
+            type TEXT,
+            icon TEXT,
+            content TEXT,
+            category TEXT NOT NULL DEFAULT 'main',
+            open_count INTEGER DEFAULT 0,
+            created_at INTEGER
+        )",
+        [],
+    )?;
+
+    // 创建分类表
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS categories (
+            id TEXT PRIMARY KEY,
+            parent_id TEXT,
+            name TEXT NOT NULL,
+            icon TEXT,
+            sort_order INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+
+    let category_columns = [
+        ("parent_id", "TEXT"),
+        ("icon", "TEXT"),
+        ("sort_order", "INTEGER NOT NULL DEFAULT 0"),
+    ];
+    for (column, definition) in category_columns {
+        if !has_column(tx, "categories", column)? {
+            tx.execute(
+                &format!("ALTER TABLE categories ADD COLUMN {} {}", column, definition),
+                [],
+            )?;
+        }
+    }
+
+    let file_columns = [
+        ("category", "TEXT NOT NULL DEFAULT 'main'"),
+        ("open_count", "INTEGER DEFAULT 0"),
+        ("content", "TEXT"),
+        ("display_name", "TEXT NOT NULL DEFAULT ''"),
+        ("created_at", "INTEGER"),
+    ];
+    for (column, definition) in file_columns {
+        if !has_column(tx, "files", column)? {
+            tx.execute(
+                &format!("ALTER TABLE files ADD COLUMN {} {}", column, definition),
+                [],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+// 版本 2：文件支持手动排序，已有数据按当前的打开次数顺序初始化
+fn migrate_v2_file_sort_order(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE files ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0;
+         UPDATE files SET sort_order = (
+             SELECT COUNT(*) FROM files AS f
+             WHERE f.category = files.category
+               AND (IFNULL(f.open_count, 0) > IFNULL(files.open_count, 0)
+                    OR (IFNULL(f.open_count, 0) = IFNULL(files.open_count, 0)
+                        AND f.rowid < files.rowid))
+         );
+         CREATE INDEX IF NOT EXISTS idx_files_category_sort ON files (category, sort_order);",
+    )
+}
+
+// 版本 3：记录每次启动的时间和结果
+fn migrate_v3_launch_history(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS launch_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            file_id TEXT NOT NULL,
+            launched_at INTEGER NOT NULL,
+            success INTEGER NOT NULL,
+            error TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_launch_history_file ON launch_history (file_id, launched_at);
+        CREATE INDEX IF NOT EXISTS idx_launch_history_time ON launch_history (launched_at);",
+    )
+}
+
+// 版本 4：为名称、路径和文本内容建立 FTS5 全文索引，通过触发器与 files 表保持同步
+// 使用 trigram 分词，中文和路径片段都可以按子串检索
+fn migrate_v4_full_text_search(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS files_fts USING fts5(
+            file_id UNINDEXED,
+            name,
+            display_name,
+            path,
+            content,
+            tokenize = 'trigram'
+        );
+
+        -- INSERT OR REPLACE 删除旧行时不会触发 DELETE 触发器，插入前先清理同 ID 的索引
+        CREATE TRIGGER IF NOT EXISTS files_fts_insert AFTER INSERT ON files BEGIN
+            DELETE FROM files_fts WHERE file_id = new.id;
+            INSERT INTO files_fts (file_id, name, display_name, path, content)
+                VALUES (new.id, new.name, new.display_name, new.path, new.content);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS files_fts_delete AFTER DELETE ON files BEGIN
+            DELETE FROM files_fts WHERE file_id = old.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS files_fts_update
+        AFTER UPDATE OF id, name, display_name, path, content ON files BEGIN
+            DELETE FROM files_fts WHERE file_id = old.id;
+            INSERT INTO files_fts (file_id, name, display_name, path, content)
+                VALUES (new.id, new.name, new.display_name, new.path, new.content);
+        END;
+
+        INSERT INTO files_fts (file_id, name, display_name, path, content)
+            SELECT id, name, display_name, path, content FROM files;",
+    )
+}
+
+// 版本 5：保存显示名称的拼音索引
+// 索引格式随拼音数据变化，不在迁移中生成，已有数据由启动后的 pinyin_index::backfill 补全
+fn migrate_v5_pinyin_index(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute("ALTER TABLE files ADD COLUMN pinyin TEXT", [])?;
+    Ok(())
+}
+
+// 版本 6 中图标引用的格式，与 icon_cache 模块分开保存，之后修改图标缓存不影响这次迁移
+const V6_ICON_URL_PREFIX: &str = "oops://icon/";
+
+// 将 data:<mime>;base64,<data> 格式的图标保存到 icon_cache，返回引用，无法解析时返回 None
+fn v6_intern_icon(tx: &Transaction, icon: &str) -> rusqlite::Result<Option<String>> {
+    let Some((header, data)) = icon.strip_prefix("data:").and_then(|rest| rest.split_once(',')) else {
+        return Ok(None);
+    };
+    let Some(mime) = header.strip_suffix(";base64") else {
+        return Ok(None);
+    };
+    let Ok(bytes) = general_purpose::STANDARD.decode(data.trim()) else {
+        return Ok(None);
+    };
+
+    let hash: String = Sha256::digest(&bytes).iter().map(|b| format!("{:02x}", b)).collect();
+    tx.execute(
+        "INSERT OR IGNORE INTO icon_cache (hash, mime, data, created_at)
+            VALUES (?, ?, ?, CAST(strftime('%s', 'now') AS INTEGER) * 1000)",
+        rusqlite::params![&hash, mime, &bytes],
+    )?;
+    Ok(Some(format!("{}{}", V6_ICON_URL_PREFIX, hash)))
+}
+
+// 图标改为按内容哈希保存在 icon_cache 中，原来内联的 base64 图标替换为引用
+fn migrate_v6_icon_cache(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE icon_cache (
+            hash TEXT PRIMARY KEY,
+            mime TEXT NOT NULL,
+            data BLOB NOT NULL,
+            created_at INTEGER NOT NULL
+        );",
+    )?;
+
+    for table in ["files", "categories"] {
+        let rows = {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT id, icon FROM {} WHERE icon LIKE 'data:%'",
+                table
+            ))?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            rows
+        };
+
+        let mut stmt = tx.prepare(&format!("UPDATE {} SET icon = ? WHERE id = ?", table))?;
+        for (id, icon) in rows {
+            if let Some(reference) = v6_intern_icon(tx, &icon)? {
+                stmt.execute([&reference, &id])?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// 图片缩略图缓存，按路径和尺寸保存，mtime 和 file_size 用于判断是否需要重新生成
+fn migrate_v7_thumbnail_cache(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE thumbnail_cache (
+            path TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            mtime INTEGER NOT NULL,
+            file_size INTEGER NOT NULL,
+            data BLOB NOT NULL,
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY (path, size)
+        );",
+    )
+}
+
+// 记录根据文件内容判断的 MIME 类型
+// 已有文件需要读取文件头，不在迁移中补全，由启动后的 mime_type::backfill 在后台处理
+fn migrate_v8_file_mime(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE files ADD COLUMN mime TEXT;
+        CREATE INDEX idx_files_mime ON files(mime);",
+    )
+}
+
+// 启动选项：命令行参数、工作目录、环境变量（JSON 对象）和是否在终端中运行
+fn migrate_v9_launch_options(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE files ADD COLUMN launch_args TEXT;
+        ALTER TABLE files ADD COLUMN working_dir TEXT;
+        ALTER TABLE files ADD COLUMN env TEXT;
+        ALTER TABLE files ADD COLUMN run_in_terminal INTEGER NOT NULL DEFAULT 0;",
+    )
+}
+
+// 启动项类型，已有的记录都是文件
+fn migrate_v10_item_kind(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE files ADD COLUMN kind TEXT NOT NULL DEFAULT 'file';")
+}
+
+// 命令启动项的 shell 和结束后是否保持终端打开，命令文本保存在 path 列
+fn migrate_v11_command_items(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE files ADD COLUMN shell TEXT;
+        ALTER TABLE files ADD COLUMN keep_open INTEGER NOT NULL DEFAULT 0;",
+    )
+}
+
+// 文件监视发现目标被删除时标记为缺失，目标恢复或重新定位后清除
+fn migrate_v12_missing_flag(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE files ADD COLUMN missing INTEGER NOT NULL DEFAULT 0;")
+}
+
+// 绑定到文件夹的分类，包含和排除规则以 JSON 数组保存
+fn migrate_v13_category_folder(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE categories ADD COLUMN folder_dir TEXT;
+        ALTER TABLE categories ADD COLUMN folder_include TEXT;
+        ALTER TABLE categories ADD COLUMN folder_exclude TEXT;
+        ALTER TABLE categories ADD COLUMN folder_depth INTEGER NOT NULL DEFAULT 1;",
+    )
+}
+
+// 移到回收站的文件记录删除时间，为空时表示未删除
+fn migrate_v14_file_deleted_at(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE files ADD COLUMN deleted_at INTEGER;")
+}
+
+// 移到回收站的分类记录删除时间，同一次删除的分类和文件删除时间相同
+fn migrate_v15_category_deleted_at(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE categories ADD COLUMN deleted_at INTEGER;")
+}
+
+// 编辑记录，changes 以 JSON 保存每一行修改前后的内容，undone 为 1 的记录可以重做
+// journal_icons 保存每条记录引用的图标哈希，清理图标缓存时按哈希查询
+fn migrate_v16_journal(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE journal (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            action TEXT NOT NULL,
+            changes TEXT NOT NULL,
+            undone INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL
+        );
+        CREATE TABLE journal_icons (
+            journal_id INTEGER NOT NULL,
+            hash TEXT NOT NULL,
+            PRIMARY KEY (journal_id, hash)
+        );
+        CREATE INDEX idx_journal_icons_hash ON journal_icons(hash);",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icon_cache;
+    use crate::pinyin_index;
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(&format!("PRAGMA table_info({})", table))
+            .unwrap();
+        stmt.query_map([], |row| row.get::<_, String>(1))
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<String>>>()
+            .unwrap()
+    }
+
+    fn assert_latest_schema(conn: &Connection) {
+        assert_eq!(current_version(conn).unwrap(), latest_version(MIGRATIONS));
+
+        let file_columns = columns(conn, "files");
+        for column in [
+            "id", "name", "display_name", "path", "size", "type", "icon",
+            "content", "category", "open_count", "created_at", "sort_order", "pinyin", "mime",
+            "launch_args", "working_dir", "env", "run_in_terminal", "kind",
+            "shell", "keep_open", "missing", "deleted_at",
+        ] {
+            assert!(file_columns.iter().any(|c| c == column), "files.{} missing", column);
+        }
+
+        let category_columns = columns(conn, "categories");
+        for column in [
+            "id", "parent_id", "name", "icon", "sort_order",
+            "folder_dir", "folder_include", "folder_exclude", "folder_depth", "deleted_at",
+        ] {
+            assert!(category_columns.iter().any(|c| c == column), "categories.{} missing", column);
+        }
+
+        let history_columns = columns(conn, "launch_history");
+        for column in ["id", "file_id", "launched_at", "success", "error"] {
+            assert!(history_columns.iter().any(|c| c == column), "launch_history.{} missing", column);
+        }
+
+        let icon_columns = columns(conn, "icon_cache");
+        for column in ["hash", "mime", "data", "created_at"] {
+            assert!(icon_columns.iter().any(|c| c == column), "icon_cache.{} missing", column);
+        }
+
+        let thumbnail_columns = columns(conn, "thumbnail_cache");
+        for column in ["path", "size", "mtime", "file_size", "data", "created_at"] {
+            assert!(thumbnail_columns.iter().any(|c| c == column), "thumbnail_cache.{} missing", column);
+        }
+
+        let journal_columns = columns(conn, "journal");
+        for column in ["id", "action", "changes", "undone", "created_at"] {
+            assert!(journal_columns.iter().any(|c| c == column), "journal.{} missing", column);
+        }
+
+        let journal_icon_columns = columns(conn, "journal_icons");
+        for column in ["journal_id", "hash"] {
+            assert!(journal_icon_columns.iter().any(|c| c == column), "journal_icons.{} missing", column);
+        }
+    }
+
+    // 最早的结构：files 表只有基础字段，没有分类表
+    fn fixture_initial(conn: &Connection) {
+        conn.execute_batch(
+            "CREATE TABLE files (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                path TEXT NOT NULL,
+                size INTEGER,
+                type TEXT,
+                icon TEXT
+            );
+            INSERT INTO files (id, name, path, size, type, icon)
+                VALUES ('1', 'notepad.exe', 'C:\\Windows\\notepad.exe', 1024, 'exe', '');",
+        )
+        .unwrap();
+    }
+
+    // 加入分类之后、display_name/content/created_at 之前的结构
+    fn fixture_with_categories(conn: &Connection) {
+        conn.execute_batch(
+            "CREATE TABLE files (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                path TEXT NOT NULL,
+                size INTEGER,
+                type TEXT,
+                icon TEXT,
+                category TEXT NOT NULL DEFAULT 'main',
+                open_count INTEGER DEFAULT 0
+            );
+            CREATE TABLE categories (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL
+            );
+            INSERT INTO categories (id, name) VALUES ('work', 'work');
+            INSERT INTO files (id, name, path, size, type, icon, category, open_count)
+                VALUES ('1', 'code.exe', '/opt/code', 2048, 'exe', '', 'work', 7);",
+        )
+        .unwrap();
+    }
+
+    // 引入版本号之前的最后一个结构，所有列齐全但 user_version 仍为 0
+    fn fixture_unversioned_full(conn: &Connection) {
+        conn.execute_batch(
+            "CREATE TABLE files (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                display_name TEXT NOT NULL,
+                path TEXT NOT NULL,
+                size INTEGER,
+                type TEXT,
+                icon TEXT,
+                content TEXT,
+                category TEXT NOT NULL DEFAULT 'main',
+                open_count INTEGER DEFAULT 0,
+                created_at INTEGER
+            );
+            CREATE TABLE categories (
+                id TEXT PRIMARY KEY,
+                parent_id TEXT,
+                name TEXT NOT NULL,
+                icon TEXT,
+                sort_order INTEGER NOT NULL DEFAULT 0
+            );
+            INSERT INTO files VALUES ('1', 'a.md', 'a', '/tmp/a.md', 3, 'md', '', 'hi', 'main', 2, 1700000000000);",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn migrations_are_strictly_ordered() {
+        let mut previous = 0;
+        for migration in MIGRATIONS {
+            assert!(migration.version > previous, "migration {} out of order", migration.version);
+            previous = migration.version;
+        }
+    }
+
+    #[test]
+    fn fresh_database_gets_latest_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn, MIGRATIONS).unwrap();
+        assert_latest_schema(&conn);
+    }
+
+    #[test]
+    fn initial_schema_upgrades_and_keeps_rows() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        fixture_initial(&conn);
+        run_migrations(&mut conn, MIGRATIONS).unwrap();
+        assert_latest_schema(&conn);
+
+        let (name, category, display_name): (String, String, String) = conn
+            .query_row(
+                "SELECT name, category, display_name FROM files WHERE id = '1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(name, "notepad.exe");
+        assert_eq!(category, "main");
+        assert_eq!(display_name, "");
+    }
+
+    #[test]
+    fn category_schema_upgrades_and_keeps_rows() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        fixture_with_categories(&conn);
+        run_migrations(&mut conn, MIGRATIONS).unwrap();
+        assert_latest_schema(&conn);
+
+        let (category, open_count): (String, i64) = conn
+            .query_row("SELECT category, open_count FROM files WHERE id = '1'", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(category, "work");
+        assert_eq!(open_count, 7);
+
+        let sort_order: i32 = conn
+            .query_row("SELECT sort_order FROM categories WHERE id = 'work'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(sort_order, 0);
+    }
+
+    #[test]
+    fn unversioned_full_schema_is_stamped() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        fixture_unversioned_full(&conn);
+        run_migrations(&mut conn, MIGRATIONS).unwrap();
+        assert_latest_schema(&conn);
+
+        let content: Option<String> = conn
+            .query_row("SELECT content FROM files WHERE id = '1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(content.as_deref(), Some("hi"));
+    }
+
+    #[test]
+    fn full_text_index_follows_files() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        fixture_unversioned_full(&conn);
+        run_migrations(&mut conn, MIGRATIONS).unwrap();
+
+        let count = |conn: &Connection, query: &str| -> i64 {
+            conn.query_row(
+                "SELECT COUNT(*) FROM files_fts WHERE files_fts MATCH ?",
+                [query],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+
+        // 迁移前已有的行会被回填
+        assert_eq!(count(&conn, "\"a.md\""), 1);
+
+        conn.execute(
+            "INSERT INTO files (id, name, display_name, path, content) VALUES ('2', '微信.exe', '微信', 'C:/Apps/WeChat.exe', NULL)",
+            [],
+        )
+        .unwrap();
+        assert_eq!(count(&conn, "\"wechat\""), 1);
+
+        conn.execute("UPDATE files SET path = 'C:/Apps/Weixin.exe' WHERE id = '2'", [])
+            .unwrap();
+        assert_eq!(count(&conn, "\"wechat\""), 0);
+        assert_eq!(count(&conn, "\"weixin\""), 1);
+
+        conn.execute("DELETE FROM files WHERE id = '2'", []).unwrap();
+        assert_eq!(count(&conn, "\"weixin\""), 0);
+    }
+
+    #[test]
+    fn pinyin_index_is_backfilled() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        fixture_unversioned_full(&conn);
+        conn.execute(
+            "INSERT INTO files (id, name, display_name, path) VALUES ('2', '微信.exe', '微信', 'C:/Apps/WeChat.exe')",
+            [],
+        )
+        .unwrap();
+        run_migrations(&mut conn, MIGRATIONS).unwrap();
+
+        let pinyin = |id: &str| -> Option<String> {
+            conn.query_row("SELECT pinyin FROM files WHERE id = ?", [id], |row| row.get(0))
+                .unwrap()
+        };
+        // 迁移只添加列，启动后补全
+        assert_eq!(pinyin("2"), None);
+        assert_eq!(pinyin_index::backfill(&conn).unwrap(), 1);
+        assert_eq!(pinyin("1"), None);
+        assert_eq!(pinyin("2"), pinyin_index::build("微信"));
+        assert!(pinyin("2").is_some());
+    }
+
+    #[test]
+    fn inline_icons_move_to_cache() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        fixture_unversioned_full(&conn);
+        // 两个文件使用相同的图标，另一个使用 emoji
+        conn.execute_batch(
+            "UPDATE files SET icon = 'data:image/png;base64,iVBORw0KGgo=' WHERE id = '1';
+            INSERT INTO files (id, name, display_name, path, icon)
+                VALUES ('2', 'b.md', 'b', '/tmp/b.md', 'data:image/png;base64,iVBORw0KGgo=');
+            INSERT INTO files (id, name, display_name, path, icon)
+                VALUES ('3', 'c.md', 'c', '/tmp/c.md', '📄');",
+        )
+        .unwrap();
+        run_migrations(&mut conn, MIGRATIONS).unwrap();
+
+        let icon = |id: &str| -> String {
+            conn.query_row("SELECT icon FROM files WHERE id = ?", [id], |row| row.get(0))
+                .unwrap()
+        };
+        assert!(icon("1").starts_with(icon_cache::ICON_URL_PREFIX));
+        assert_eq!(icon("1"), icon("2"));
+        assert_eq!(icon("3"), "📄");
+
+        let cached: i64 = conn
+            .query_row("SELECT COUNT(*) FROM icon_cache", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(cached, 1);
+
+        let hash = icon_cache::icon_hash(&icon("1")).unwrap().to_string();
+        let (mime, data) = icon_cache::load_icon(&conn, &hash).unwrap().unwrap();
+        assert_eq!(mime, "image/png");
+        assert_eq!(data, b"\x89PNG\r\n\x1a\n");
+
+        conn.execute("DELETE FROM files WHERE id IN ('1', '2')", []).unwrap();
+        assert_eq!(icon_cache::prune_unused_icons(&conn).unwrap(), 1);
+    }
+
+    #[test]
+    fn running_twice_is_a_no_op() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn, MIGRATIONS).unwrap();
+        run_migrations(&mut conn, MIGRATIONS).unwrap();
+        assert_latest_schema(&conn);
+    }
+
+    #[test]
+    fn newer_database_is_rejected() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn, MIGRATIONS).unwrap();
+        conn.pragma_update(None, "user_version", latest_version(MIGRATIONS) + 1)
+            .unwrap();
+
+        let err = run_migrations(&mut conn, MIGRATIONS).unwrap_err();
+        assert!(err.contains("newer"), "unexpected error: {}", err);
+    }
+
+    #[test]
+    fn failed_migration_is_rolled_back() {
+        fn create_then_fail(tx: &Transaction) -> rusqlite::Result<()> {
+            tx.execute("CREATE TABLE half_applied (id INTEGER)", [])?;
+            tx.execute("THIS IS NOT SQL", [])?;
+            Ok(())
+        }
+
+        let broken = [
+            Migration {
+                version: 1,
+                description: "baseline",
+                up: migrate_v1_baseline,
+            },
+            Migration {
+                version: 2,
+                description: "broken",
+                up: create_then_fail,
+            },
+        ];
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert!(run_migrations(&mut conn, &broken).is_err());
+        assert_eq!(current_version(&conn).unwrap(), 1);
+
+        let exists: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'half_applied'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(exists, 0);
+    }
+}
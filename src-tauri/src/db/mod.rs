@@ -0,0 +1,65 @@
+pub mod migrations;
+
+use rusqlite::Connection;
+use std::fs;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use tauri::Manager;
+
+// 预编译语句缓存容量，覆盖所有命令里用到的 SQL 即可
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+
+// 全局共享的数据库连接，在 setup 中打开并注册为 Tauri 托管状态
+pub struct DbState(Mutex<Connection>);
+
+impl DbState {
+    pub fn new(conn: Connection) -> Self {
+        DbState(Mutex::new(conn))
+    }
+
+    // 获取连接的独占访问权
+    pub fn conn(&self) -> Result<MutexGuard<'_, Connection>, String> {
+        self.0
+            .lock()
+            .map_err(|_| "Database connection lock poisoned".to_string())
+    }
+}
+
+// 打开数据库连接，应用启动时调用一次
+pub fn open_database(app: &tauri::AppHandle) -> Result<Connection, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let db_path = dir.join("oopslauncher.db");
+
+    let mut conn = Connection::open(db_path)
+        .map_err(|e| e.to_string())?;
+
+    configure_connection(&conn)?;
+
+    // 初始化数据库表结构
+    init_database(&mut conn)?;
+
+    Ok(conn)
+}
+
+// 设置连接参数：WAL 模式、忙等待超时和语句缓存
+pub fn configure_connection(conn: &Connection) -> Result<(), String> {
+    // journal_mode 会返回设置后的模式，需要用带结果的版本读取
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+    conn.busy_timeout(Duration::from_secs(5))
+        .map_err(|e| e.to_string())?;
+    conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+    Ok(())
+}
+
+// 初始化数据库表结构
+// 通过 PRAGMA user_version 记录当前结构版本，只执行尚未应用的迁移
+pub fn init_database(conn: &mut Connection) -> Result<(), String> {
+    migrations::run_migrations(conn, migrations::MIGRATIONS)
+}
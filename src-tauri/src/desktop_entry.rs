@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use crate::utils::{find_in_path, terminal_command};
+
+// freedesktop Desktop Entry 文件（.desktop）中启动器关心的字段
+// NoDisplay 只影响菜单中是否显示，用户添加的启动项仍然可以启动，因此不读取
+#[derive(Debug, Clone, Default)]
+pub struct DesktopEntry {
+    pub entry_type: String,
+    pub name: String,
+    pub exec: Option<String>,
+    pub try_exec: Option<String>,
+    pub icon: Option<String>,
+    pub working_dir: Option<String>,
+    pub url: Option<String>,
+    pub terminal: bool,
+    pub hidden: bool,
+}
+
+pub fn is_desktop_file(path: &str) -> bool {
+    path.to_lowercase().ends_with(".desktop")
+}
+
+// 按规范的优先级生成候选 locale：lang_COUNTRY@MODIFIER、lang_COUNTRY、lang@MODIFIER、lang
+fn locale_candidates() -> Vec<String> {
+    let raw = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|key| env::var(key).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+
+    // 去掉编码部分，例如 zh_CN.UTF-8@mod -> zh_CN@mod
+    let (base, modifier) = match raw.split_once('@') {
+        Some((base, modifier)) => (base, Some(modifier)),
+        None => (raw.as_str(), None),
+    };
+    let base = base.split('.').next().unwrap_or_default();
+    let (lang, country) = match base.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (base, None),
+    };
+    if lang.is_empty() || lang == "C" || lang == "POSIX" {
+        return Vec::new();
+    }
+
+    let mut candidates = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        candidates.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        candidates.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        candidates.push(format!("{}@{}", lang, modifier));
+    }
+    candidates.push(lang.to_string());
+    candidates
+}
+
+// 处理值中的转义序列 \s \n \t \r \\
+fn unescape_value(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('\\') => result.push('\\'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+fn parse_bool(value: &str) -> bool {
+    value.trim() == "true"
+}
+
+// 解析 .desktop 文件内容，只读取 [Desktop Entry] 分组
+pub fn parse(content: &str, locales: &[String]) -> Result<DesktopEntry, String> {
+    let mut in_main_group = false;
+    let mut found_group = false;
+    let mut values: HashMap<String, String> = HashMap::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            in_main_group = &line[1..line.len() - 1] == "Desktop Entry";
+            found_group |= in_main_group;
+            continue;
+        }
+        if !in_main_group {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            values
+                .entry(key.trim().to_string())
+                .or_insert_with(|| unescape_value(value.trim()));
+        }
+    }
+
+    if !found_group {
+        return Err("Missing [Desktop Entry] group".to_string());
+    }
+
+    // 本地化的键，例如 Name[zh_CN]，找不到时回退到默认值
+    let localized = |key: &str| -> Option<String> {
+        locales
+            .iter()
+            .find_map(|locale| values.get(&format!("{}[{}]", key, locale)))
+            .or_else(|| values.get(key))
+            .cloned()
+    };
+
+    let name = localized("Name").ok_or("Desktop entry has no Name")?;
+    let non_empty = |key: &str| values.get(key).filter(|v| !v.is_empty()).cloned();
+
+    Ok(DesktopEntry {
+        entry_type: values.get("Type").cloned().unwrap_or_else(|| "Application".to_string()),
+        name,
+        exec: non_empty("Exec"),
+        try_exec: non_empty("TryExec"),
+        icon: localized("Icon").filter(|v| !v.is_empty()),
+        working_dir: non_empty("Path"),
+        url: non_empty("URL"),
+        terminal: values.get("Terminal").map(|v| parse_bool(v)).unwrap_or(false),
+        hidden: values.get("Hidden").map(|v| parse_bool(v)).unwrap_or(false),
+    })
+}
+
+// 读取并解析 .desktop 文件，使用当前环境的 locale
+pub fn load(path: &Path) -> Result<DesktopEntry, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    parse(&content, &locale_candidates())
+}
+
+// 按规范拆分 Exec 中的参数，双引号内的 \" \` \$ \\ 需要转义
+fn split_exec(exec: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut in_quotes = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                in_arg = true;
+            }
+            '\\' if in_quotes => match chars.next() {
+                Some(escaped) => current.push(escaped),
+                None => return Err("Unterminated escape in Exec".to_string()),
+            },
+            ' ' | '\t' if !in_quotes => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            _ => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if in_quotes {
+        return Err("Unterminated quote in Exec".to_string());
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+impl DesktopEntry {
+    // 展开 Exec 中的字段代码，得到不带文件参数的启动命令
+    // %f %F %u %U 在没有传入文件时直接去掉，%i %c %k 按规范替换
+    pub fn command_args(&self, desktop_file: &Path) -> Result<Vec<String>, String> {
+        let exec = self.exec.as_deref().ok_or("Desktop entry has no Exec")?;
+        let mut args = Vec::new();
+
+        for arg in split_exec(exec)? {
+            match arg.as_str() {
+                "%f" | "%F" | "%u" | "%U" | "%d" | "%D" | "%n" | "%N" | "%v" | "%m" => continue,
+                "%i" => {
+                    if let Some(icon) = &self.icon {
+                        args.push("--icon".to_string());
+                        args.push(icon.clone());
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
+            let mut expanded = String::with_capacity(arg.len());
+            let mut chars = arg.chars();
+            while let Some(c) = chars.next() {
+                if c != '%' {
+                    expanded.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('%') => expanded.push('%'),
+                    Some('c') => expanded.push_str(&self.name),
+                    Some('k') => expanded.push_str(&desktop_file.to_string_lossy()),
+                    // 其余字段代码在参数中间出现时直接忽略
+                    Some(_) | None => {}
+                }
+            }
+            args.push(expanded);
+        }
+
+        if args.is_empty() {
+            return Err("Desktop entry Exec is empty".to_string());
+        }
+        Ok(args)
+    }
+
+    // 用于展示和搜索的启动命令
+    pub fn command_line(&self, desktop_file: &Path) -> Option<String> {
+        match self.entry_type.as_str() {
+            "Link" => self.url.clone(),
+            _ => self.command_args(desktop_file).ok().map(|args| args.join(" ")),
+        }
+    }
+
+    // 检查条目是否可以启动：Hidden 表示已被删除，TryExec 指向的程序必须存在
+    pub fn check_usable(&self) -> Result<(), String> {
+        if self.hidden {
+            return Err(format!("Desktop entry is hidden: {}", self.name));
+        }
+        if let Some(try_exec) = &self.try_exec {
+            let exists = if Path::new(try_exec).is_absolute() {
+                Path::new(try_exec).exists()
+            } else {
+                find_in_path(try_exec).is_some()
+            };
+            if !exists {
+                return Err(format!("Program not found: {}", try_exec));
+            }
+        }
+        Ok(())
+    }
+
+    // 按条目内容生成启动命令：Application 直接执行 Exec，Link 交给系统打开 URL
+    pub fn command(&self, desktop_file: &Path) -> Result<Command, String> {
+        self.check_usable()?;
+
+        if self.entry_type == "Link" {
+            let url = self.url.as_deref().ok_or("Desktop entry has no URL")?;
+            let mut command = Command::new("xdg-open");
+            command.arg(url);
+            return Ok(command);
+        }
+
+        let args = self.command_args(desktop_file)?;
+        let mut command = if self.terminal {
+            terminal_command(&args)?
+        } else {
+            let mut command = Command::new(&args[0]);
+            command.args(&args[1..]);
+            command
+        };
+
+        if let Some(dir) = &self.working_dir {
+            command.current_dir(dir);
+        }
+
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENTRY: &str = "\
+[Desktop Entry]
+Type=Application
+Name=Text Editor
+Name[zh_CN]=文本编辑器
+Name[zh]=编辑器
+Icon=editor
+Exec=\"/opt/my editor/bin/edit\" --new-window %U
+Terminal=true
+
+[Desktop Action new]
+Name=Ignored
+Exec=ignored
+";
+
+    fn locales(locales: &[&str]) -> Vec<String> {
+        locales.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_falls_back_through_locales() {
+        let name = |l: &[&str]| parse(ENTRY, &locales(l)).unwrap().name;
+        assert_eq!(name(&["zh_CN@mod", "zh_CN", "zh@mod", "zh"]), "文本编辑器");
+        assert_eq!(name(&["zh_TW", "zh"]), "编辑器");
+        assert_eq!(name(&["fr_FR", "fr"]), "Text Editor");
+        assert_eq!(name(&[]), "Text Editor");
+    }
+
+    #[test]
+    fn parse_reads_only_main_group() {
+        let entry = parse(ENTRY, &[]).unwrap();
+        assert_eq!(entry.entry_type, "Application");
+        assert_eq!(entry.exec.as_deref(), Some("\"/opt/my editor/bin/edit\" --new-window %U"));
+        assert!(entry.terminal);
+        assert!(!entry.hidden);
+
+        assert!(parse("[Other]\nName=x\n", &[]).is_err());
+        assert!(parse("[Desktop Entry]\nExec=x\n", &[]).is_err());
+        // 值中的转义序列
+        let entry = parse("[Desktop Entry]\nName=a\\sb\\\\c\n", &[]).unwrap();
+        assert_eq!(entry.name, "a b\\c");
+    }
+
+    #[test]
+    fn command_args_strips_field_codes() {
+        let file = Path::new("/usr/share/applications/editor.desktop");
+        let entry = parse(ENTRY, &[]).unwrap();
+        assert_eq!(entry.command_args(file).unwrap(), ["/opt/my editor/bin/edit", "--new-window"]);
+
+        let entry = parse(
+            "[Desktop Entry]\nName=App\nIcon=app\nExec=app %f %i --name=%c --file=%k 100%% --x=%u\n",
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            entry.command_args(file).unwrap(),
+            [
+                "app",
+                "--icon",
+                "app",
+                "--name=App",
+                "--file=/usr/share/applications/editor.desktop",
+                "100%",
+                "--x="
+            ]
+        );
+
+        let entry = parse("[Desktop Entry]\nName=App\nExec=%F\n", &[]).unwrap();
+        assert!(entry.command_args(file).is_err());
+        let entry = parse("[Desktop Entry]\nName=App\nExec=\"app\n", &[]).unwrap();
+        assert!(entry.command_args(file).is_err());
+    }
+
+    #[test]
+    fn check_usable_honors_try_exec_and_hidden() {
+        let usable = |extra: &str| {
+            parse(&format!("[Desktop Entry]\nName=App\nExec=app\n{}", extra), &[])
+                .unwrap()
+                .check_usable()
+        };
+        assert!(usable("").is_ok());
+        // NoDisplay 的条目仍可启动
+        assert!(usable("NoDisplay=true\n").is_ok());
+        assert!(usable("Hidden=true\n").is_err());
+        assert!(usable("Hidden=false\n").is_ok());
+        assert!(usable("TryExec=/nonexistent/desktop-entry-test-app\n").is_err());
+        assert!(usable("TryExec=desktop-entry-test-app-not-in-path\n").is_err());
+
+        let temp = tempfile::tempdir().unwrap();
+        let program = temp.path().join("app");
+        fs::write(&program, "").unwrap();
+        assert!(usable(&format!("TryExec={}\n", program.display())).is_ok());
+    }
+}
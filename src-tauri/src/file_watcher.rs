@@ -0,0 +1,486 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use rusqlite::{params, Connection};
+use tauri::{AppHandle, Emitter, Manager};
+use crate::commands::file::{read_file_info, update_item_target};
+use crate::db::DbState;
+use crate::folder_category::{folder_rules, sync_category};
+use crate::icon_cache::prune_unused_icons;
+use crate::models::{FileChange, FileChangeKind};
+use crate::thumbnail::prune_stale_thumbnails;
+
+// 文件监视更新启动项后发送的事件，内容为 Vec<FileChange>
+pub const FILES_CHANGED_EVENT: &str = "files-changed";
+
+// 保存文件时会连续产生多个事件，事件停止这么久后再一起处理
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+// 目录中持续有文件写入时，最多等待这么久就处理已收到的事件
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(3);
+
+// 监视的目录中发生的变化
+#[derive(Debug, Clone, PartialEq)]
+enum PathEvent {
+    Renamed(PathBuf, PathBuf),
+    Removed(PathBuf),
+    // 创建或修改，处理时目标已不存在的按删除处理
+    Touched(PathBuf),
+}
+
+struct WatchedDirs {
+    watcher: RecommendedWatcher,
+    dirs: HashMap<PathBuf, RecursiveMode>,
+}
+
+// 监视所有文件启动项所在的目录，在 setup 中注册为 Tauri 托管状态
+// 系统不支持文件监视时不监视，其他功能不受影响
+pub struct FileWatcher {
+    watched: Mutex<Option<WatchedDirs>>,
+}
+
+impl FileWatcher {
+    // 创建监视器并在后台线程处理事件，需要调用 sync 后才开始监视
+    pub fn start(app: &AppHandle) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let watched = match notify::recommended_watcher(tx) {
+            Ok(watcher) => {
+                let app = app.clone();
+                thread::spawn(move || process_events(app, rx));
+                Some(WatchedDirs {
+                    watcher,
+                    dirs: HashMap::new(),
+                })
+            }
+            Err(e) => {
+                println!("Failed to start file watcher: {}", e);
+                None
+            }
+        };
+        FileWatcher {
+            watched: Mutex::new(watched),
+        }
+    }
+
+    // 按数据库中的文件启动项和文件夹分类更新监视的目录，启动项增删或移动、文件夹分类变化后调用
+    // 无法监视的目录只记录日志
+    pub fn sync(&self, conn: &Connection) {
+        let Ok(mut watched) = self.watched.lock() else {
+            return;
+        };
+        let Some(watched) = watched.as_mut() else {
+            return;
+        };
+        let wanted = match watched_dirs(conn) {
+            Ok(dirs) => dirs,
+            Err(e) => {
+                println!("Failed to list watched directories: {}", e);
+                return;
+            }
+        };
+
+        for (dir, mode) in &watched.dirs {
+            if wanted.get(dir) != Some(mode) {
+                // 目录被删除时监视已经失效，忽略错误
+                let _ = watched.watcher.unwatch(dir);
+            }
+        }
+
+        let mut dirs = HashMap::new();
+        for (dir, mode) in wanted {
+            if watched.dirs.get(&dir) == Some(&mode) {
+                dirs.insert(dir, mode);
+                continue;
+            }
+            match watched.watcher.watch(&dir, mode) {
+                Ok(()) => {
+                    dirs.insert(dir, mode);
+                }
+                Err(e) => println!("Failed to watch {}: {}", dir.display(), e),
+            }
+        }
+        watched.dirs = dirs;
+    }
+}
+
+// 需要监视的目录，只包含仍然存在的目录
+// 文件启动项所在的目录不递归，枚举多层的文件夹分类递归监视
+fn watched_dirs(conn: &Connection) -> Result<HashMap<PathBuf, RecursiveMode>, String> {
+    let mut stmt = conn
+        .prepare_cached("SELECT DISTINCT path FROM files WHERE kind = 'file' AND deleted_at IS NULL")
+        .map_err(|e| e.to_string())?;
+    let paths = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+
+    let mut dirs = HashMap::new();
+    for path in paths {
+        let path = path.map_err(|e| e.to_string())?;
+        if let Some(parent) = Path::new(&path).parent().filter(|parent| parent.is_dir()) {
+            dirs.insert(parent.to_path_buf(), RecursiveMode::NonRecursive);
+        }
+    }
+    drop(stmt);
+
+    for (_, rule) in folder_rules(conn)? {
+        if !rule.dir.is_dir() {
+            continue;
+        }
+        if rule.recursive() {
+            dirs.insert(rule.dir, RecursiveMode::Recursive);
+        } else {
+            dirs.entry(rule.dir).or_insert(RecursiveMode::NonRecursive);
+        }
+    }
+    Ok(dirs)
+}
+
+// 将 notify 的事件转换为启动器关心的变化，访问事件等忽略
+fn path_events(event: Event) -> Vec<PathEvent> {
+    match event.kind {
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => match &event.paths[..] {
+            [from, to] => vec![PathEvent::Renamed(from.clone(), to.clone())],
+            _ => Vec::new(),
+        },
+        // 移出到未监视的目录时只有 From 事件，移入监视的目录时随后还有 Both 事件
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) | EventKind::Remove(_) => {
+            event.paths.into_iter().map(PathEvent::Removed).collect()
+        }
+        EventKind::Create(_) | EventKind::Modify(_) => {
+            event.paths.into_iter().map(PathEvent::Touched).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+// 收集一批事件后更新数据库并通知前端
+fn process_events(app: AppHandle, rx: Receiver<notify::Result<Event>>) {
+    while let Ok(first) = rx.recv() {
+        let started = Instant::now();
+        let mut events = vec![first];
+        while started.elapsed() < MAX_BATCH_DELAY {
+            match rx.recv_timeout(DEBOUNCE) {
+                Ok(event) => events.push(event),
+                Err(_) => break,
+            }
+        }
+
+        let events: Vec<PathEvent> = events
+            .into_iter()
+            .filter_map(|event| match event {
+                Ok(event) => Some(event),
+                Err(e) => {
+                    println!("File watcher error: {}", e);
+                    None
+                }
+            })
+            .flat_map(path_events)
+            .collect();
+        if events.is_empty() {
+            continue;
+        }
+
+        match apply_events(&app, events) {
+            Ok(changes) if !changes.is_empty() => {
+                println!("File watcher updated {} items", changes.len());
+                if let Err(e) = app.emit(FILES_CHANGED_EVENT, changes) {
+                    println!("Failed to emit {}: {}", FILES_CHANGED_EVENT, e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => println!("Failed to apply file changes: {}", e),
+        }
+    }
+}
+
+fn apply_events(app: &AppHandle, events: Vec<PathEvent>) -> Result<Vec<FileChange>, String> {
+    let db = app.state::<DbState>();
+    let mut changes = Vec::new();
+    let mut handled = HashSet::new();
+
+    let paths: Vec<PathBuf> = events
+        .iter()
+        .flat_map(|event| match event {
+            PathEvent::Renamed(from, to) => vec![from.clone(), to.clone()],
+            PathEvent::Removed(path) | PathEvent::Touched(path) => vec![path.clone()],
+        })
+        .collect();
+
+    for event in events {
+        match event {
+            PathEvent::Renamed(from, to) => {
+                let conn = db.conn()?;
+                changes.extend(rename_items(&conn, &from, &to)?);
+            }
+            // 同一批事件中的路径只需要按最终状态处理一次
+            PathEvent::Removed(path) | PathEvent::Touched(path) => {
+                if !handled.insert(path.clone()) {
+                    continue;
+                }
+                if path.exists() {
+                    changes.extend(refresh_items(&db, &path)?);
+                } else {
+                    let conn = db.conn()?;
+                    changes.extend(mark_missing(&conn, &path)?);
+                }
+            }
+        }
+    }
+
+    // 文件夹分类中的变化重新枚举整个文件夹，新增和不再匹配的项目由同步处理
+    let folders: Vec<String> = folder_rules(&*db.conn()?)?
+        .into_iter()
+        .filter(|(_, rule)| paths.iter().any(|path| rule.contains(path)))
+        .map(|(id, _)| id)
+        .collect();
+    for category in folders {
+        match sync_category(&db, &category) {
+            Ok((_, synced)) => changes.extend(synced),
+            Err(e) => println!("Failed to sync folder category {}: {}", category, e),
+        }
+    }
+
+    if !changes.is_empty() {
+        let conn = db.conn()?;
+        prune_unused_icons(&conn).map_err(|e| e.to_string())?;
+        prune_stale_thumbnails(&conn).map_err(|e| e.to_string())?;
+        // 启动项可能被移动到了其他目录；setup 中注册状态之前收到的事件不需要更新监视
+        if let Some(watcher) = app.try_state::<FileWatcher>() {
+            watcher.sync(&conn);
+        }
+    }
+    Ok(changes)
+}
+
+// 路径本身或其下的文件启动项，返回 (id, path)
+// 回收站中的项目不跟随文件变化更新，恢复时重新检查目标是否存在
+fn items_under(conn: &Connection, path: &Path, only_present: bool) -> Result<Vec<(String, String)>, String> {
+    let path = path.to_string_lossy();
+    let prefix = format!("{}{}", path.trim_end_matches(MAIN_SEPARATOR), MAIN_SEPARATOR);
+    let sql = if only_present {
+        "SELECT id, path FROM files WHERE kind = 'file' AND deleted_at IS NULL AND missing = 0 AND (path = ?1 OR substr(path, 1, length(?2)) = ?2)"
+    } else {
+        "SELECT id, path FROM files WHERE kind = 'file' AND deleted_at IS NULL AND (path = ?1 OR substr(path, 1, length(?2)) = ?2)"
+    };
+    let mut stmt = conn.prepare_cached(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![path.as_ref(), &prefix], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+// 重命名的可能是启动项本身，也可能是启动项所在的目录
+// 处理时原路径又存在的启动项不移动：编辑器保存时常先把原文件改名为备份，再写入新的原文件，
+// 原路径上的文件由同一批事件中的创建或修改事件刷新
+fn rename_items(conn: &Connection, from: &Path, to: &Path) -> Result<Vec<FileChange>, String> {
+    let mut changes = Vec::new();
+    for (id, path) in items_under(conn, from, false)? {
+        if Path::new(&path).exists() {
+            continue;
+        }
+        let new_path = match Path::new(&path).strip_prefix(from) {
+            Ok(rest) if !rest.as_os_str().is_empty() => to.join(rest),
+            _ => to.to_path_buf(),
+        };
+        let new_path_str = new_path.to_string_lossy().to_string();
+        let name = new_path.file_name().map(|name| name.to_string_lossy().to_string());
+
+        let updated = conn
+            .prepare_cached("UPDATE files SET path = ?, name = IFNULL(?, name), missing = ? WHERE id = ?")
+            .and_then(|mut stmt| stmt.execute(params![&new_path_str, name, !new_path.exists(), &id]));
+        match updated {
+            Ok(_) => changes.push(FileChange {
+                id,
+                path: new_path_str,
+                kind: FileChangeKind::Renamed,
+            }),
+            Err(e) => println!("Failed to update renamed item {}: {}", path, e),
+        }
+    }
+    Ok(changes)
+}
+
+// 目标被删除或移出监视的目录，保留启动项并标记为缺失
+fn mark_missing(conn: &Connection, path: &Path) -> Result<Vec<FileChange>, String> {
+    let mut changes = Vec::new();
+    for (id, path) in items_under(conn, path, true)? {
+        conn.prepare_cached("UPDATE files SET missing = 1 WHERE id = ?")
+            .and_then(|mut stmt| stmt.execute([&id]))
+            .map_err(|e| e.to_string())?;
+        changes.push(FileChange {
+            id,
+            path,
+            kind: FileChangeKind::Deleted,
+        });
+    }
+    Ok(changes)
+}
+
+// 目标被修改或重新出现，重新读取大小、图标和预览内容
+fn refresh_items(db: &DbState, path: &Path) -> Result<Vec<FileChange>, String> {
+    let path_str = path.to_string_lossy().to_string();
+    let ids: Vec<String> = {
+        let conn = db.conn()?;
+        let mut stmt = conn
+            .prepare_cached("SELECT id FROM files WHERE kind = 'file' AND deleted_at IS NULL AND path = ?")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([&path_str], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // 提取图标较慢，期间不持有数据库连接
+    let mut info = match read_file_info(&path_str) {
+        Ok(info) => info,
+        Err(e) => {
+            println!("Failed to refresh {}: {}", path_str, e);
+            return Ok(Vec::new());
+        }
+    };
+    // 快捷方式会被解析为目标路径，启动项仍然指向快捷方式本身
+    info.path = path_str.clone();
+
+    let conn = db.conn()?;
+    let mut changes = Vec::new();
+    for id in ids {
+        if update_item_target(&conn, &id, &info)? > 0 {
+            changes.push(FileChange {
+                id,
+                path: path_str.clone(),
+                kind: FileChangeKind::Modified,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use notify::event::{AccessKind, CreateKind, DataChange, RemoveKind};
+    use crate::db::init_database;
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        init_database(&mut conn).unwrap();
+        conn
+    }
+
+    fn insert_item(conn: &Connection, id: &str, path: &Path) {
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        conn.execute(
+            "INSERT INTO files (id, name, display_name, path, kind) VALUES (?, ?, '', ?, 'file')",
+            params![id, name, path.to_string_lossy()],
+        )
+        .unwrap();
+    }
+
+    fn item(conn: &Connection, id: &str) -> (String, String, bool) {
+        conn.query_row("SELECT path, name, missing FROM files WHERE id = ?", [id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn path_events_maps_notify_kinds() {
+        let a = PathBuf::from("/tmp/a.txt");
+        let b = PathBuf::from("/tmp/b.txt");
+        let event = |kind| Event::new(kind).add_path(a.clone());
+
+        let renamed = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+            .add_path(a.clone())
+            .add_path(b.clone());
+        assert_eq!(path_events(renamed), vec![PathEvent::Renamed(a.clone(), b.clone())]);
+        // 缺少目标路径的 Both 事件无法处理
+        assert!(path_events(event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))).is_empty());
+
+        assert_eq!(
+            path_events(event(EventKind::Modify(ModifyKind::Name(RenameMode::From)))),
+            vec![PathEvent::Removed(a.clone())]
+        );
+        assert_eq!(
+            path_events(event(EventKind::Remove(RemoveKind::File))),
+            vec![PathEvent::Removed(a.clone())]
+        );
+        assert_eq!(
+            path_events(event(EventKind::Create(CreateKind::File))),
+            vec![PathEvent::Touched(a.clone())]
+        );
+        assert_eq!(
+            path_events(event(EventKind::Modify(ModifyKind::Data(DataChange::Content)))),
+            vec![PathEvent::Touched(a.clone())]
+        );
+        assert!(path_events(event(EventKind::Access(AccessKind::Read))).is_empty());
+    }
+
+    #[test]
+    fn rename_items_follows_item_and_directory_renames() {
+        let temp = tempfile::tempdir().unwrap();
+        let old_dir = temp.path().join("tools");
+        let new_dir = temp.path().join("apps");
+        fs::create_dir_all(new_dir.join("sub")).unwrap();
+        fs::write(new_dir.join("a.txt"), "a").unwrap();
+        fs::write(temp.path().join("c.txt"), "c").unwrap();
+
+        let conn = test_db();
+        insert_item(&conn, "a", &old_dir.join("a.txt"));
+        insert_item(&conn, "b", &old_dir.join("sub").join("b.txt"));
+        // 前缀相同但不在目录下的项目不受影响
+        insert_item(&conn, "other", &temp.path().join("tools2").join("x.txt"));
+        insert_item(&conn, "file", &temp.path().join("c.txt.tmp"));
+
+        let changes = rename_items(&conn, &old_dir, &new_dir).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(item(&conn, "a"), (new_dir.join("a.txt").to_string_lossy().to_string(), "a.txt".to_string(), false));
+        // 新位置不存在的项目标记为缺失
+        assert_eq!(
+            item(&conn, "b"),
+            (new_dir.join("sub").join("b.txt").to_string_lossy().to_string(), "b.txt".to_string(), true)
+        );
+        assert_eq!(item(&conn, "other").0, temp.path().join("tools2").join("x.txt").to_string_lossy());
+
+        rename_items(&conn, &temp.path().join("c.txt.tmp"), &temp.path().join("c.txt")).unwrap();
+        assert_eq!(item(&conn, "file"), (temp.path().join("c.txt").to_string_lossy().to_string(), "c.txt".to_string(), false));
+    }
+
+    #[test]
+    fn rename_items_keeps_item_whose_path_reappears() {
+        // 编辑器保存：a.txt 改名为 a.txt~，再写入新的 a.txt
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("a.txt");
+        let backup = temp.path().join("a.txt~");
+        fs::write(&path, "new").unwrap();
+        fs::write(&backup, "old").unwrap();
+
+        let conn = test_db();
+        insert_item(&conn, "a", &path);
+        assert!(rename_items(&conn, &path, &backup).unwrap().is_empty());
+        assert_eq!(item(&conn, "a"), (path.to_string_lossy().to_string(), "a.txt".to_string(), false));
+    }
+
+    #[test]
+    fn rename_items_skips_trashed_items() {
+        let temp = tempfile::tempdir().unwrap();
+        let old_path = temp.path().join("a.txt");
+        let new_path = temp.path().join("b.txt");
+        fs::write(&new_path, "b").unwrap();
+
+        let conn = test_db();
+        insert_item(&conn, "a", &old_path);
+        conn.execute("UPDATE files SET deleted_at = 1 WHERE id = 'a'", []).unwrap();
+        // 回收站中的项目保持原路径，恢复时再检查
+        assert!(rename_items(&conn, &old_path, &new_path).unwrap().is_empty());
+        assert_eq!(item(&conn, "a").0, old_path.to_string_lossy());
+    }
+}
@@ -0,0 +1,423 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use rusqlite::{params, Connection, OptionalExtension};
+use tauri::{AppHandle, Emitter, Manager};
+use walkdir::WalkDir;
+use crate::commands::file::{insert_file, read_file_info};
+use crate::db::DbState;
+use crate::file_watcher::FILES_CHANGED_EVENT;
+use crate::icon_cache::{content_hash, prune_unused_icons};
+use crate::models::{FileChange, FileChangeKind, FolderSyncSummary, WatchedFolder};
+use crate::shell_command::expand_home;
+use crate::thumbnail::prune_stale_thumbnails;
+
+// 文件夹分类：分类绑定到一个文件夹，同步添加的项目与文件夹内容保持一致
+// 手动添加的项目不受影响，即使位于文件夹之中
+
+// 枚举层数的上限，避免绑定到 ~ 之类的目录时遍历整个磁盘
+pub const MAX_FOLDER_DEPTH: u32 = 8;
+
+// 同步添加的项目的 ID 前缀，同步时只移除这些项目
+const FOLDER_ITEM_PREFIX: &str = "folder-";
+
+// 编译后的文件夹规则
+pub struct FolderRule {
+    pub dir: PathBuf,
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+    depth: usize,
+}
+
+// Windows 的文件名不区分大小写，规则同样不区分
+fn build_globs(patterns: &[String]) -> Result<Option<GlobSet>, String> {
+    let patterns: Vec<&str> = patterns
+        .iter()
+        .map(|pattern| pattern.trim())
+        .filter(|pattern| !pattern.is_empty())
+        .collect();
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .case_insensitive(cfg!(windows))
+            .build()
+            .map_err(|e| format!("Invalid pattern {}: {}", pattern, e))?;
+        builder.add(glob);
+    }
+    builder.build().map(Some).map_err(|e| e.to_string())
+}
+
+impl FolderRule {
+    pub fn new(folder: &WatchedFolder) -> Result<Self, String> {
+        Ok(FolderRule {
+            dir: expand_home(folder.dir.trim()),
+            include: build_globs(&folder.include)?,
+            exclude: build_globs(&folder.exclude)?,
+            depth: folder.depth.clamp(1, MAX_FOLDER_DEPTH) as usize,
+        })
+    }
+
+    // 枚举文件夹中匹配的文件和子文件夹，规则按相对路径匹配，分隔符统一为 /
+    // 以 . 开头的隐藏项目不包含，被排除的文件夹不再进入
+    pub fn scan(&self) -> Result<Vec<PathBuf>, String> {
+        if !self.dir.is_dir() {
+            return Err(format!("Folder does not exist: {}", self.dir.display()));
+        }
+
+        let relative = |path: &Path| {
+            path.strip_prefix(&self.dir)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .replace('\\', "/")
+        };
+
+        let entries = WalkDir::new(&self.dir)
+            .min_depth(1)
+            .max_depth(self.depth)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| {
+                let hidden = entry.file_name().to_string_lossy().starts_with('.');
+                let excluded = self
+                    .exclude
+                    .as_ref()
+                    .is_some_and(|exclude| exclude.is_match(relative(entry.path())));
+                !hidden && !excluded
+            })
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                self.include
+                    .as_ref()
+                    .map_or(true, |include| include.is_match(relative(entry.path())))
+            })
+            .map(|entry| entry.into_path())
+            .collect();
+        Ok(entries)
+    }
+
+    // 路径是否位于文件夹之中
+    pub fn contains(&self, path: &Path) -> bool {
+        path.starts_with(&self.dir) && path != self.dir
+    }
+
+    // 文件监视是否需要递归
+    pub fn recursive(&self) -> bool {
+        self.depth > 1
+    }
+}
+
+// 读取分类绑定的文件夹
+pub fn row_to_folder(dir: Option<String>, include: Option<String>, exclude: Option<String>, depth: u32) -> Option<WatchedFolder> {
+    let parse = |patterns: Option<String>| {
+        patterns
+            .and_then(|patterns| serde_json::from_str(&patterns).ok())
+            .unwrap_or_default()
+    };
+    dir.filter(|dir| !dir.trim().is_empty()).map(|dir| WatchedFolder {
+        dir,
+        include: parse(include),
+        exclude: parse(exclude),
+        depth,
+    })
+}
+
+// 所有文件夹分类的 ID 和规则，规则无效的分类只记录日志
+pub fn folder_rules(conn: &Connection) -> Result<Vec<(String, FolderRule)>, String> {
+    let mut stmt = conn
+        .prepare_cached("SELECT id, folder_dir, folder_include, folder_exclude, folder_depth FROM categories WHERE folder_dir IS NOT NULL AND deleted_at IS NULL")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row_to_folder(row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?),
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut rules = Vec::new();
+    for row in rows {
+        let (id, folder) = row.map_err(|e| e.to_string())?;
+        let Some(folder) = folder else {
+            continue;
+        };
+        match FolderRule::new(&folder) {
+            Ok(rule) => rules.push((id, rule)),
+            Err(e) => println!("Invalid folder rule for category {}: {}", id, e),
+        }
+    }
+    Ok(rules)
+}
+
+fn folder_rule(conn: &Connection, category: &str) -> Result<Option<FolderRule>, String> {
+    let folder = conn
+        .prepare_cached("SELECT folder_dir, folder_include, folder_exclude, folder_depth FROM categories WHERE id = ?")
+        .map_err(|e| e.to_string())?
+        .query_row([category], |row| {
+            Ok(row_to_folder(row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Category not found: {}", category))?;
+    folder.as_ref().map(FolderRule::new).transpose()
+}
+
+// 文件夹中的项目使用固定的 ID，重新同步时不会产生新的 ID
+fn folder_item_id(category: &str, path: &str) -> String {
+    let hash = content_hash(format!("{}\0{}", category, path).as_bytes());
+    format!("{}{}", FOLDER_ITEM_PREFIX, &hash[..16])
+}
+
+// 按文件夹的当前内容更新分类：添加新出现的匹配项目，移除同步添加的、文件夹中已不存在或不再匹配的项目
+// 枚举文件夹和读取文件信息较慢，期间不持有数据库连接
+pub fn sync_category(db: &DbState, category: &str) -> Result<(FolderSyncSummary, Vec<FileChange>), String> {
+    let mut summary = FolderSyncSummary {
+        category: category.to_string(),
+        ..Default::default()
+    };
+    let mut changes = Vec::new();
+
+    let Some(rule) = folder_rule(&*db.conn()?, category)? else {
+        return Ok((summary, changes));
+    };
+    let scanned: Vec<String> = rule
+        .scan()?
+        .into_iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+
+    let existing: Vec<(String, String)> = {
+        let conn = db.conn()?;
+        let mut stmt = conn
+            .prepare_cached("SELECT id, path FROM files WHERE category = ? AND kind = 'file' AND deleted_at IS NULL")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([category], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+
+    let scanned_set: HashSet<&str> = scanned.iter().map(String::as_str).collect();
+    let existing_paths: HashSet<&str> = existing.iter().map(|(_, path)| path.as_str()).collect();
+    let removed: Vec<&(String, String)> = existing
+        .iter()
+        .filter(|(id, path)| {
+            id.starts_with(FOLDER_ITEM_PREFIX) && rule.contains(Path::new(path)) && !scanned_set.contains(path.as_str())
+        })
+        .collect();
+
+    let mut added = Vec::new();
+    for path in scanned.iter().filter(|path| !existing_paths.contains(path.as_str())) {
+        match read_file_info(path) {
+            Ok(mut info) => {
+                // 快捷方式会被解析为目标路径，分类中仍然显示快捷方式本身
+                info.path = path.clone();
+                info.id = folder_item_id(category, path);
+                added.push(info);
+            }
+            Err(e) => {
+                println!("Failed to read {}: {}", path, e);
+                summary.failed += 1;
+            }
+        }
+    }
+
+    if removed.is_empty() && added.is_empty() {
+        return Ok((summary, changes));
+    }
+
+    let mut conn = db.conn()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for (id, path) in removed {
+        summary.removed += tx
+            .prepare_cached("DELETE FROM files WHERE id = ?")
+            .and_then(|mut stmt| stmt.execute(params![id]))
+            .map_err(|e| e.to_string())?;
+        changes.push(FileChange {
+            id: id.clone(),
+            path: path.clone(),
+            kind: FileChangeKind::Removed,
+        });
+    }
+    for mut info in added {
+        // 同一文件夹可能同时被文件监视和手动同步；移到回收站的项目也算存在，不会被重新添加
+        let exists: bool = tx
+            .prepare_cached("SELECT EXISTS(SELECT 1 FROM files WHERE category = ? AND path = ?)")
+            .and_then(|mut stmt| stmt.query_row(params![category, &info.path], |row| row.get(0)))
+            .map_err(|e| e.to_string())?;
+        if exists {
+            continue;
+        }
+        insert_file(&tx, &mut info, category)?;
+        summary.added += 1;
+        changes.push(FileChange {
+            id: info.id,
+            path: info.path,
+            kind: FileChangeKind::Added,
+        });
+    }
+    prune_unused_icons(&tx).map_err(|e| e.to_string())?;
+    prune_stale_thumbnails(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!(
+        "Synced folder category {}: {} added, {} removed",
+        category, summary.added, summary.removed
+    );
+    Ok((summary, changes))
+}
+
+// 同步所有文件夹分类，程序启动时在后台调用，程序未运行期间文件夹的内容可能已经变化
+pub fn sync_all(app: &AppHandle) {
+    let db = app.state::<DbState>();
+    let categories = match db.conn().and_then(|conn| folder_rules(&conn)) {
+        Ok(rules) => rules.into_iter().map(|(id, _)| id).collect::<Vec<_>>(),
+        Err(e) => {
+            println!("Failed to load folder categories: {}", e);
+            return;
+        }
+    };
+
+    let mut changes = Vec::new();
+    for category in categories {
+        match sync_category(&db, &category) {
+            Ok((_, synced)) => changes.extend(synced),
+            Err(e) => println!("Failed to sync folder category {}: {}", category, e),
+        }
+    }
+    if !changes.is_empty() {
+        if let Err(e) = app.emit(FILES_CHANGED_EVENT, changes) {
+            println!("Failed to emit {}: {}", FILES_CHANGED_EVENT, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::db::init_database;
+
+    // a.exe  b.txt  .hidden.exe  .git/config  skip/e.exe  tools/c.exe  tools/deep/d.exe
+    fn fixture_folder() -> tempfile::TempDir {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        for dir in [".git", "skip", "tools/deep"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in ["a.exe", "b.txt", ".hidden.exe", ".git/config", "skip/e.exe", "tools/c.exe", "tools/deep/d.exe"] {
+            fs::write(root.join(file), file).unwrap();
+        }
+        temp
+    }
+
+    fn scan(root: &Path, include: &[&str], exclude: &[&str], depth: u32) -> Vec<String> {
+        let folder = WatchedFolder {
+            dir: root.to_string_lossy().to_string(),
+            include: include.iter().map(|p| p.to_string()).collect(),
+            exclude: exclude.iter().map(|p| p.to_string()).collect(),
+            depth,
+        };
+        FolderRule::new(&folder)
+            .unwrap()
+            .scan()
+            .unwrap()
+            .iter()
+            .map(|path| path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect()
+    }
+
+    #[test]
+    fn scan_limits_depth_and_skips_hidden_entries() {
+        let temp = fixture_folder();
+        assert_eq!(scan(temp.path(), &[], &[], 1), ["a.exe", "b.txt", "skip", "tools"]);
+        assert_eq!(
+            scan(temp.path(), &[], &[], 2),
+            ["a.exe", "b.txt", "skip", "skip/e.exe", "tools", "tools/c.exe", "tools/deep"]
+        );
+        // 层数限制在 1 到 MAX_FOLDER_DEPTH 之间
+        assert_eq!(scan(temp.path(), &[], &[], 0), scan(temp.path(), &[], &[], 1));
+    }
+
+    #[test]
+    fn scan_applies_include_and_exclude_rules() {
+        let temp = fixture_folder();
+        assert_eq!(
+            scan(temp.path(), &["*.exe"], &[], 3),
+            ["a.exe", "skip/e.exe", "tools/c.exe", "tools/deep/d.exe"]
+        );
+        assert_eq!(scan(temp.path(), &["tools/**"], &[], 3), ["tools/c.exe", "tools/deep", "tools/deep/d.exe"]);
+        // 被排除的文件夹不再进入
+        assert_eq!(
+            scan(temp.path(), &["*.exe"], &["skip", "tools/deep"], 3),
+            ["a.exe", "tools/c.exe"]
+        );
+        // 空白规则忽略
+        assert_eq!(scan(temp.path(), &[" "], &[""], 1), scan(temp.path(), &[], &[], 1));
+    }
+
+    #[test]
+    fn scan_rules_ignore_case_only_on_windows() {
+        let temp = fixture_folder();
+        let matched = scan(temp.path(), &["*.EXE"], &[], 1);
+        if cfg!(windows) {
+            assert_eq!(matched, ["a.exe"]);
+        } else {
+            assert!(matched.is_empty());
+        }
+    }
+
+    #[test]
+    fn scan_rejects_missing_folder_and_invalid_rules() {
+        let temp = fixture_folder();
+        let folder = |dir: &Path, include: &str| WatchedFolder {
+            dir: dir.to_string_lossy().to_string(),
+            include: vec![include.to_string()],
+            exclude: Vec::new(),
+            depth: 1,
+        };
+        assert!(FolderRule::new(&folder(&temp.path().join("missing"), "*")).unwrap().scan().is_err());
+        assert!(FolderRule::new(&folder(temp.path(), "[")).is_err());
+    }
+
+    #[test]
+    fn sync_removes_only_synced_items() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().to_string_lossy().to_string();
+        let gone = temp.path().join("gone.exe").to_string_lossy().to_string();
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        init_database(&mut conn).unwrap();
+        conn.execute(
+            "INSERT INTO categories (id, name, folder_dir, folder_depth) VALUES ('tools', 'tools', ?, 1)",
+            [&dir],
+        )
+        .unwrap();
+        let synced = folder_item_id("tools", &gone);
+        conn.execute(
+            "INSERT INTO files (id, name, display_name, path, category) VALUES (?1, 'gone.exe', '', ?2, 'tools'), ('manual', 'gone.exe', '', ?2, 'tools')",
+            params![&synced, &gone],
+        )
+        .unwrap();
+        let db = DbState::new(conn);
+
+        let (summary, changes) = sync_category(&db, "tools").unwrap();
+        assert_eq!(summary.removed, 1);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].id, synced);
+        let remaining: Vec<String> = db
+            .conn()
+            .unwrap()
+            .prepare("SELECT id FROM files")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(remaining, ["manual"]);
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use rusqlite::Connection;
+
+// 参与计算的最近启动次数，与 Firefox 一致只取最近 10 次
+pub const SAMPLE_SIZE: usize = 10;
+
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+// 按距今天数划分的权重区间 (天数上限, 权重)，参考 Firefox 的 frecency 算法
+const BUCKETS: [(i64, f64); 4] = [(4, 100.0), (14, 70.0), (31, 50.0), (90, 30.0)];
+
+// 超出所有区间的启动，以及只有打开次数而没有启动记录的老数据使用的权重
+const OLDEST_WEIGHT: f64 = 10.0;
+
+// 单次启动按时间衰减后的权重
+fn bucket_weight(launched_at: i64, now: i64) -> f64 {
+    let age_days = (now - launched_at).max(0) / DAY_MILLIS;
+    BUCKETS
+        .iter()
+        .find(|(max_days, _)| age_days <= *max_days)
+        .map(|(_, weight)| *weight)
+        .unwrap_or(OLDEST_WEIGHT)
+}
+
+// 计算 frecency 分数：总打开次数 × 最近启动的平均时间权重
+// recent_launches 为最近若干次成功启动的时间戳（毫秒）
+pub fn frecency_score(open_count: u64, recent_launches: &[i64], now: i64) -> f64 {
+    if open_count == 0 {
+        return 0.0;
+    }
+
+    if recent_launches.is_empty() {
+        return open_count as f64 * OLDEST_WEIGHT;
+    }
+
+    let samples = &recent_launches[..recent_launches.len().min(SAMPLE_SIZE)];
+    let points: f64 = samples.iter().map(|&t| bucket_weight(t, now)).sum();
+    open_count as f64 * points / samples.len() as f64
+}
+
+// 读取每个文件最近的成功启动时间，按时间倒序
+pub fn recent_launches(conn: &Connection) -> Result<HashMap<String, Vec<i64>>, String> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT file_id, launched_at FROM (
+            SELECT file_id, launched_at,
+                   ROW_NUMBER() OVER (PARTITION BY file_id ORDER BY launched_at DESC) AS rn
+            FROM launch_history
+            WHERE success = 1
+        )
+        WHERE rn <= ?
+        ORDER BY file_id, launched_at DESC"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map([SAMPLE_SIZE as i64], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    }).map_err(|e| e.to_string())?;
+
+    let mut launches: HashMap<String, Vec<i64>> = HashMap::new();
+    for row in rows {
+        let (file_id, launched_at) = row.map_err(|e| e.to_string())?;
+        launches.entry(file_id).or_default().push(launched_at);
+    }
+
+    Ok(launches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    const NOW: i64 = 1_000 * DAY_MILLIS;
+
+    fn days_ago(days: i64) -> i64 {
+        NOW - days * DAY_MILLIS
+    }
+
+    #[test]
+    fn bucket_weight_follows_age() {
+        assert_eq!(bucket_weight(NOW, NOW), 100.0);
+        assert_eq!(bucket_weight(days_ago(4), NOW), 100.0);
+        assert_eq!(bucket_weight(days_ago(5), NOW), 70.0);
+        assert_eq!(bucket_weight(days_ago(14), NOW), 70.0);
+        assert_eq!(bucket_weight(days_ago(15), NOW), 50.0);
+        assert_eq!(bucket_weight(days_ago(31), NOW), 50.0);
+        assert_eq!(bucket_weight(days_ago(32), NOW), 30.0);
+        assert_eq!(bucket_weight(days_ago(90), NOW), 30.0);
+        assert_eq!(bucket_weight(days_ago(91), NOW), OLDEST_WEIGHT);
+        // 不足一天按 0 天计算，时钟回拨时不会得到负数天数
+        assert_eq!(bucket_weight(days_ago(4) - DAY_MILLIS + 1, NOW), 100.0);
+        assert_eq!(bucket_weight(NOW + DAY_MILLIS, NOW), 100.0);
+    }
+
+    #[test]
+    fn frecency_score_averages_recent_launches() {
+        assert_eq!(frecency_score(0, &[NOW], NOW), 0.0);
+        // 没有启动记录的老数据
+        assert_eq!(frecency_score(3, &[], NOW), 30.0);
+        assert_eq!(frecency_score(2, &[NOW, days_ago(20)], NOW), 2.0 * (100.0 + 50.0) / 2.0);
+
+        // 只取前 SAMPLE_SIZE 次
+        let mut launches = vec![NOW; SAMPLE_SIZE];
+        launches.push(days_ago(100));
+        assert_eq!(frecency_score(20, &launches, NOW), 2000.0);
+    }
+
+    #[test]
+    fn frecency_score_prefers_recent_items() {
+        // 最近常用的项目排在很久以前打开更多次的项目前面
+        let recent = frecency_score(5, &[days_ago(1); 5], NOW);
+        let stale = frecency_score(30, &[days_ago(200); 10], NOW);
+        assert!(recent > stale);
+        assert!(frecency_score(5, &[days_ago(1)], NOW) > frecency_score(5, &[days_ago(10)], NOW));
+    }
+
+    #[test]
+    fn recent_launches_keeps_latest_successes() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::init_database(&mut conn).unwrap();
+        let insert = |file_id: &str, launched_at: i64, success: bool| {
+            conn.execute(
+                "INSERT INTO launch_history (file_id, launched_at, success) VALUES (?, ?, ?)",
+                params![file_id, launched_at, success],
+            )
+            .unwrap();
+        };
+        for i in 0..SAMPLE_SIZE as i64 + 2 {
+            insert("a", i, true);
+        }
+        insert("a", 100, false);
+        insert("b", 5, true);
+        insert("c", 5, false);
+
+        let launches = recent_launches(&conn).unwrap();
+        let expected: Vec<i64> = (2..SAMPLE_SIZE as i64 + 2).rev().collect();
+        assert_eq!(launches["a"], expected);
+        assert_eq!(launches["b"], [5]);
+        assert!(!launches.contains_key("c"));
+    }
+}
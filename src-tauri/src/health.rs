@@ -0,0 +1,324 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use crate::models::HealthIssue;
+use crate::shell_link;
+use crate::utils::find_in_path;
+#[cfg(all(unix, not(target_os = "macos")))]
+use crate::desktop_entry::{self, is_desktop_file};
+
+// 每个启动项最多给出的候选位置数量
+const MAX_CANDIDATES: usize = 5;
+
+// 在原位置附近查找的深度
+const NEARBY_DEPTH: usize = 3;
+
+// 在程序安装目录中查找的深度，例如 Program Files\Vendor\App\app.exe
+const INSTALL_DIR_DEPTH: usize = 3;
+
+// 启动项目标存在的问题
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub issue: HealthIssue,
+    pub detail: Option<String>,
+    // 需要重新查找位置的目标，快捷方式损坏时为快捷方式原来指向的目标
+    pub missing: Option<PathBuf>,
+}
+
+impl Problem {
+    fn new(issue: HealthIssue, detail: Option<String>, missing: Option<PathBuf>) -> Self {
+        Problem { issue, detail, missing }
+    }
+}
+
+// 检查启动项的目标，没有问题时返回 None
+pub fn check_target(path: &Path) -> Option<Problem> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return Some(Problem::new(HealthIssue::Missing, None, Some(path.to_path_buf())));
+        }
+        Err(e) => return Some(Problem::new(HealthIssue::Unreadable, Some(e.to_string()), None)),
+    };
+
+    let readable = if metadata.is_dir() {
+        fs::read_dir(path).map(|_| ())
+    } else {
+        File::open(path).map(|_| ())
+    };
+    if let Err(e) = readable {
+        return Some(Problem::new(HealthIssue::Unreadable, Some(e.to_string()), None));
+    }
+
+    let is_lnk = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("lnk"));
+    if is_lnk {
+        let link = match shell_link::load(path) {
+            Ok(link) => link,
+            Err(e) => return Some(Problem::new(HealthIssue::BrokenShortcut, Some(e), None)),
+        };
+        match link.target_path(path) {
+            None => {
+                let detail = "Shortcut has no target".to_string();
+                return Some(Problem::new(HealthIssue::BrokenShortcut, Some(detail), None));
+            }
+            Some(target) if !Path::new(&target).exists() => {
+                let detail = format!("Shortcut target does not exist: {}", target);
+                return Some(Problem::new(HealthIssue::BrokenShortcut, Some(detail), Some(PathBuf::from(target))));
+            }
+            Some(_) => {}
+        }
+    }
+
+    // 桌面启动器引用的程序被卸载
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if is_desktop_file(&path.to_string_lossy()) {
+        if let Err(e) = desktop_entry::load(path).and_then(|entry| entry.check_usable()) {
+            return Some(Problem::new(HealthIssue::BrokenShortcut, Some(e), None));
+        }
+    }
+
+    None
+}
+
+// Windows 的文件名不区分大小写
+fn same_name(a: &OsStr, b: &OsStr) -> bool {
+    if cfg!(target_os = "windows") {
+        a.to_string_lossy().eq_ignore_ascii_case(&b.to_string_lossy())
+    } else {
+        a == b
+    }
+}
+
+// 文件名索引中使用的键，与 same_name 的比较方式一致
+fn name_key(name: &OsStr) -> OsString {
+    if cfg!(target_os = "windows") {
+        name.to_string_lossy().to_ascii_lowercase().into()
+    } else {
+        name.to_os_string()
+    }
+}
+
+// 在目录下查找同名文件，不跟随符号链接
+fn search_dir<'a>(dir: &Path, file_name: &'a OsStr, depth: usize) -> impl Iterator<Item = PathBuf> + 'a {
+    WalkDir::new(dir)
+        .min_depth(1)
+        .max_depth(depth)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(move |entry| same_name(entry.file_name(), file_name))
+        .map(|entry| entry.into_path())
+}
+
+// 常见的程序安装目录
+fn install_dirs() -> Vec<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+
+    #[cfg(target_os = "windows")]
+    let dirs = {
+        let mut dirs: Vec<PathBuf> = ["ProgramFiles", "ProgramFiles(x86)", "ProgramW6432"]
+            .iter()
+            .filter_map(|var| env::var_os(var).map(PathBuf::from))
+            .collect();
+        if let Some(local) = env::var_os("LOCALAPPDATA") {
+            dirs.push(PathBuf::from(local).join("Programs"));
+        }
+        let _ = home;
+        dirs
+    };
+
+    #[cfg(target_os = "macos")]
+    let dirs = {
+        let mut dirs = vec![PathBuf::from("/Applications")];
+        dirs.extend(home.map(|home| home.join("Applications")));
+        dirs
+    };
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let dirs = {
+        let mut dirs: Vec<PathBuf> = ["/usr/bin", "/usr/local/bin", "/opt", "/usr/share/applications"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        if let Some(home) = home {
+            dirs.push(home.join(".local/bin"));
+            dirs.push(home.join(".local/share/applications"));
+        }
+        dirs
+    };
+
+    let mut seen = HashSet::new();
+    dirs.into_iter()
+        .filter(|dir| dir.is_dir() && seen.insert(dir.clone()))
+        .collect()
+}
+
+// 程序安装目录中的文件按文件名建立的索引
+// 安装目录很大，一次检查只遍历一遍，所有失效的启动项共用
+#[derive(Debug, Default)]
+pub struct InstallIndex {
+    files: HashMap<OsString, Vec<PathBuf>>,
+}
+
+impl InstallIndex {
+    pub fn build() -> Self {
+        Self::from_dirs(&install_dirs())
+    }
+
+    fn from_dirs(dirs: &[PathBuf]) -> Self {
+        let mut files: HashMap<OsString, Vec<PathBuf>> = HashMap::new();
+        for dir in dirs {
+            let entries = WalkDir::new(dir)
+                .min_depth(1)
+                .max_depth(INSTALL_DIR_DEPTH)
+                .into_iter()
+                .filter_map(Result::ok);
+            for entry in entries {
+                files.entry(name_key(entry.file_name())).or_default().push(entry.into_path());
+            }
+        }
+        InstallIndex { files }
+    }
+
+    fn get(&self, file_name: &OsStr) -> &[PathBuf] {
+        self.files.get(&name_key(file_name)).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+// 查找目标移动或重新安装后可能的位置，按可信程度排序：
+// 原位置附近的同名文件，其次是程序安装目录，最后是 PATH
+pub fn find_candidates(missing: &Path, install_index: &InstallIndex) -> Vec<PathBuf> {
+    let Some(file_name) = missing.file_name() else {
+        return Vec::new();
+    };
+
+    let mut candidates = Vec::new();
+    let mut seen = HashSet::new();
+    let mut push = |path: PathBuf| {
+        if candidates.len() < MAX_CANDIDATES && path != missing && seen.insert(path.clone()) {
+            candidates.push(path);
+        }
+    };
+
+    // 从最近的现存上级目录开始查找；原来的目录还在时从它的上一级开始，覆盖同级目录
+    if let Some(existing) = missing.ancestors().skip(1).find(|dir| dir.is_dir()) {
+        let root = if Some(existing) == missing.parent() {
+            existing.parent().unwrap_or(existing)
+        } else {
+            existing
+        };
+        // 文件系统根目录下只查找一层
+        let depth = if root.parent().is_none() { 1 } else { NEARBY_DEPTH };
+        search_dir(root, file_name, depth).for_each(&mut push);
+    }
+
+    install_index.get(file_name).iter().cloned().for_each(&mut push);
+
+    if let Some(path) = find_in_path(&file_name.to_string_lossy()) {
+        push(path);
+    }
+
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 不会在 PATH 中出现的文件名
+    const APP: &str = "health-test-app.exe";
+
+    fn touch(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+    }
+
+    #[test]
+    fn check_target_reports_missing_and_unusable_targets() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("a.txt");
+        touch(&file);
+        assert_eq!(check_target(&file), None);
+        assert_eq!(check_target(temp.path()), None);
+
+        let missing = temp.path().join("gone.txt");
+        assert_eq!(
+            check_target(&missing),
+            Some(Problem::new(HealthIssue::Missing, None, Some(missing.clone())))
+        );
+
+        // 无法解析的快捷方式没有可以重新查找的目标
+        let link = temp.path().join("broken.lnk");
+        fs::write(&link, "not a shell link").unwrap();
+        let problem = check_target(&link).unwrap();
+        assert_eq!(problem.issue, HealthIssue::BrokenShortcut);
+        assert_eq!(problem.missing, None);
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn check_target_reports_unusable_desktop_entries() {
+        let temp = tempfile::tempdir().unwrap();
+        let entry = |name: &str, extra: &str| {
+            let path = temp.path().join(name);
+            fs::write(&path, format!("[Desktop Entry]\nType=Application\nName=App\nExec=app\n{}", extra)).unwrap();
+            path
+        };
+
+        assert_eq!(check_target(&entry("ok.desktop", "")), None);
+        let uninstalled = entry("uninstalled.desktop", "TryExec=/nonexistent/health-test-app\n");
+        assert_eq!(check_target(&uninstalled).unwrap().issue, HealthIssue::BrokenShortcut);
+        let hidden = entry("hidden.desktop", "Hidden=true\n");
+        assert_eq!(check_target(&hidden).unwrap().issue, HealthIssue::BrokenShortcut);
+    }
+
+    #[test]
+    fn find_candidates_prefers_nearby_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let missing = temp.path().join("tools").join("old").join(APP);
+        fs::create_dir_all(missing.parent().unwrap()).unwrap();
+        // 原目录还在时，同级目录中的同名文件
+        let nearby = temp.path().join("tools").join("new").join(APP);
+        touch(&nearby);
+        let install_dir = temp.path().join("install");
+        let installed = install_dir.join("Vendor").join("App").join(APP);
+        touch(&installed);
+        // 超过安装目录查找深度的文件不索引
+        touch(&install_dir.join("a").join("b").join("c").join(APP));
+
+        let index = InstallIndex::from_dirs(&[install_dir]);
+        assert_eq!(find_candidates(&missing, &index), vec![nearby, installed]);
+    }
+
+    #[test]
+    fn find_candidates_searches_from_nearest_existing_ancestor() {
+        let temp = tempfile::tempdir().unwrap();
+        // 原目录已被删除，从现存的上级目录开始查找
+        let missing = temp.path().join("gone").join("deeper").join(APP);
+        let moved = temp.path().join("moved").join(APP);
+        touch(&moved);
+
+        let index = InstallIndex::default();
+        assert_eq!(find_candidates(&missing, &index), vec![moved]);
+        assert!(find_candidates(&temp.path().join("other.exe"), &index).is_empty());
+    }
+
+    #[test]
+    fn find_candidates_limits_results() {
+        let temp = tempfile::tempdir().unwrap();
+        let missing = temp.path().join("old").join(APP);
+        let install_dir = temp.path().join("install");
+        for i in 0..MAX_CANDIDATES + 2 {
+            touch(&install_dir.join(i.to_string()).join(APP));
+        }
+
+        let index = InstallIndex::from_dirs(&[install_dir]);
+        assert_eq!(index.get(OsStr::new(APP)).len(), MAX_CANDIDATES + 2);
+        assert_eq!(find_candidates(&missing, &index).len(), MAX_CANDIDATES);
+    }
+}
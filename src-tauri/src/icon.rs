@@ -1,5 +1,6 @@
+use std::fs;
 use std::path::Path;
-use image::{ImageBuffer, Rgba, ImageFormat, imageops::flip_vertical};
+use image::{DynamicImage, ImageBuffer, Rgba, RgbaImage, ImageFormat, imageops::flip_vertical};
 use base64::Engine as _;
 use base64::engine::general_purpose;
 use std::io::Cursor;
@@ -123,12 +124,144 @@ mod win_icon {
     }
 }
 
-#[cfg(not(target_os = "windows"))]
+// 图标的最大边长，与 Windows 下提取的大图标一致
+const ICON_SIZE: u32 = 256;
+
+// PNG 内容转换为前端可以直接显示的 data URL
+pub fn png_to_data_url(png: &[u8]) -> String {
+    format!("data:image/png;base64,{}", general_purpose::STANDARD.encode(png))
+}
+
+fn image_to_base64(img: &DynamicImage) -> Result<String, String> {
+    let mut cursor = Cursor::new(Vec::new());
+    img.write_to(&mut cursor, ImageFormat::Png).map_err(|e| e.to_string())?;
+    Ok(png_to_data_url(&cursor.into_inner()))
+}
+
+// 将 SVG 光栅化为较长边等于 size 的位图
+fn render_svg(path: &Path, size: u32) -> Result<DynamicImage, String> {
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    let tree = resvg::usvg::Tree::from_data(&data, &resvg::usvg::Options::default())
+        .map_err(|e| e.to_string())?;
+
+    let svg_size = tree.size();
+    let scale = size as f32 / svg_size.width().max(svg_size.height());
+    let width = ((svg_size.width() * scale).round() as u32).max(1);
+    let height = ((svg_size.height() * scale).round() as u32).max(1);
+
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(width, height).ok_or("Invalid SVG size")?;
+    resvg::render(&tree, resvg::tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+
+    // tiny-skia 使用预乘 alpha，转换回普通 RGBA
+    let mut buffer = RgbaImage::new(width, height);
+    for (dst, src) in buffer.pixels_mut().zip(pixmap.pixels()) {
+        let color = src.demultiply();
+        *dst = Rgba([color.red(), color.green(), color.blue(), color.alpha()]);
+    }
+    Ok(DynamicImage::ImageRgba8(buffer))
+}
+
+fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.eq_ignore_ascii_case("svg") || ext.eq_ignore_ascii_case("svgz"))
+        .unwrap_or(false)
+}
+
+// 读取图片文件作为图标，过大的图片按比例缩小，SVG 按图标尺寸光栅化
+pub fn image_file_to_base64(path: &Path) -> Result<String, String> {
+    if is_svg(path) {
+        return image_to_base64(&render_svg(path, ICON_SIZE)?);
+    }
+
+    let img = image::open(path).map_err(|e| e.to_string())?;
+    let img = if img.width() > ICON_SIZE || img.height() > ICON_SIZE {
+        img.thumbnail(ICON_SIZE, ICON_SIZE)
+    } else {
+        img
+    };
+    image_to_base64(&img)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod linux_icon {
+    use super::*;
+    use crate::icon_theme::{lookup_icon, mime_icon_names, mime_type_from_name};
+    use crate::mime_type::{detect, OCTET_STREAM};
+    use std::os::unix::fs::PermissionsExt;
+
+    // 找不到更具体的图标时使用的通用图标
+    const GENERIC_ICONS: [&str; 2] = ["text-x-generic", "unknown"];
+
+    // 按文件的 MIME 类型得到候选图标名称
+    fn icon_names(path: &Path) -> Vec<String> {
+        let metadata = fs::metadata(path).ok();
+        if metadata.as_ref().is_some_and(|m| m.is_dir()) {
+            return vec!["folder".to_string()];
+        }
+
+        // 文件名无法判断时再读取文件头
+        let mime = path
+            .file_name()
+            .and_then(|name| mime_type_from_name(&name.to_string_lossy()))
+            .or_else(|| {
+                detect(path)
+                    .ok()
+                    .map(|detected| detected.mime)
+                    .filter(|mime| mime != OCTET_STREAM)
+            });
+        let mut names = match mime {
+            Some(mime) => mime_icon_names(&mime),
+            None => Vec::new(),
+        };
+
+        // 没有匹配规则的可执行文件
+        let executable = metadata.is_some_and(|m| m.permissions().mode() & 0o111 != 0);
+        if names.is_empty() && executable {
+            names.extend(mime_icon_names("application/x-executable"));
+        }
+        names.extend(GENERIC_ICONS.iter().map(|name| name.to_string()));
+        names
+    }
+
+    pub fn get_file_icon_base64(path: &Path) -> Result<String, String> {
+        let icon = icon_names(path)
+            .iter()
+            .find_map(|name| lookup_icon(name, ICON_SIZE))
+            .ok_or_else(|| format!("No icon found for {}", path.display()))?;
+        image_file_to_base64(&icon)
+    }
+
+    pub fn get_named_icon_base64(icon: &str) -> Result<String, String> {
+        let path = lookup_icon(icon, ICON_SIZE).ok_or_else(|| format!("Icon not found: {}", icon))?;
+        image_file_to_base64(&path)
+    }
+}
+
+#[cfg(target_os = "macos")]
 pub fn get_file_icon_base64(_path: &Path) -> Result<String, String> {
     Ok("".to_string())
 }
 
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn get_file_icon_base64(path: &Path) -> Result<String, String> {
+    linux_icon::get_file_icon_base64(path)
+}
+
 #[cfg(target_os = "windows")]
 pub fn get_file_icon_base64(path: &Path) -> Result<String, String> {
     win_icon::get_file_icon_base64(path)
 }
+
+// 按图标名称或绝对路径读取图标，用于 .desktop 条目的 Icon 字段
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn get_named_icon_base64(icon: &str) -> Result<String, String> {
+    linux_icon::get_named_icon_base64(icon)
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+pub fn get_named_icon_base64(icon: &str) -> Result<String, String> {
+    if !Path::new(icon).is_absolute() {
+        return Err(format!("Icon not found: {}", icon));
+    }
+    image_file_to_base64(Path::new(icon))
+}
@@ -0,0 +1,68 @@
+use base64::Engine as _;
+use base64::engine::general_purpose;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use crate::utils::now_millis;
+
+// 图标按内容哈希去重保存在 icon_cache 表中，files.icon 和 categories.icon 只保存引用
+// 引用格式为 oops://icon/<hash>，由自定义协议读取图标内容
+pub const ICON_URL_PREFIX: &str = "oops://icon/";
+
+// 解析 data:<mime>;base64,<data> 格式的图标
+fn decode_data_url(url: &str) -> Option<(String, Vec<u8>)> {
+    let rest = url.strip_prefix("data:")?;
+    let (header, data) = rest.split_once(',')?;
+    let mime = header.strip_suffix(";base64")?;
+    let bytes = general_purpose::STANDARD.decode(data.trim()).ok()?;
+    Some((mime.to_string(), bytes))
+}
+
+pub fn content_hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// 从引用中取出哈希，不是图标引用时返回 None
+pub fn icon_hash(icon: &str) -> Option<&str> {
+    icon.strip_prefix(ICON_URL_PREFIX)
+        .filter(|hash| !hash.is_empty() && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+// 保存图标内容，相同内容只保存一份，返回图标引用
+pub fn store_icon_bytes(conn: &Connection, mime: &str, bytes: &[u8]) -> rusqlite::Result<String> {
+    let hash = content_hash(bytes);
+    conn.prepare_cached(
+        "INSERT OR IGNORE INTO icon_cache (hash, mime, data, created_at) VALUES (?, ?, ?, ?)",
+    )?
+    .execute(params![&hash, mime, bytes, now_millis()])?;
+    Ok(format!("{}{}", ICON_URL_PREFIX, hash))
+}
+
+// 将 base64 图标转存到缓存并返回引用，emoji、空字符串和已有引用原样返回
+pub fn intern_icon(conn: &Connection, icon: &str) -> rusqlite::Result<String> {
+    match decode_data_url(icon) {
+        Some((mime, bytes)) => store_icon_bytes(conn, &mime, &bytes),
+        None => Ok(icon.to_string()),
+    }
+}
+
+// 读取图标内容和 MIME 类型
+pub fn load_icon(conn: &Connection, hash: &str) -> rusqlite::Result<Option<(String, Vec<u8>)>> {
+    conn.prepare_cached("SELECT mime, data FROM icon_cache WHERE hash = ?")?
+        .query_row([hash], |row| Ok((row.get(0)?, row.get(1)?)))
+        .optional()
+}
+
+// 删除不再被任何文件或分类引用的图标
+// 编辑记录中的图标在撤销时还会用到，同样保留
+pub fn prune_unused_icons(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.prepare_cached(
+        "DELETE FROM icon_cache
+         WHERE ?1 || hash NOT IN (SELECT icon FROM files WHERE icon IS NOT NULL)
+           AND ?1 || hash NOT IN (SELECT icon FROM categories WHERE icon IS NOT NULL)
+           AND hash NOT IN (SELECT hash FROM journal_icons)",
+    )?
+    .execute([ICON_URL_PREFIX])
+}
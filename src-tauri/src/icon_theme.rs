@@ -0,0 +1,671 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+// 按 freedesktop Icon Theme 规范查找图标文件
+// XPM 格式无法解码，只查找 PNG 和 SVG
+
+const ICON_EXTENSIONS: [&str; 2] = ["png", "svg"];
+const FALLBACK_THEME: &str = "hicolor";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DirType {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+// index.theme 中一个图标目录的尺寸描述
+#[derive(Debug, Clone)]
+struct ThemeDir {
+    path: String,
+    size: u32,
+    scale: u32,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+    dir_type: DirType,
+}
+
+impl ThemeDir {
+    fn matches_size(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+        match self.dir_type {
+            DirType::Fixed => self.size == size,
+            DirType::Scalable => self.min_size <= size && size <= self.max_size,
+            DirType::Threshold => {
+                self.size.saturating_sub(self.threshold) <= size && size <= self.size + self.threshold
+            }
+        }
+    }
+
+    fn size_distance(&self, size: u32, scale: u32) -> u32 {
+        let target = size * scale;
+        let (min, max) = match self.dir_type {
+            DirType::Fixed => (self.size, self.size),
+            DirType::Scalable => (self.min_size, self.max_size),
+            DirType::Threshold => (self.size.saturating_sub(self.threshold), self.size + self.threshold),
+        };
+        let (min, max) = (min * self.scale, max * self.scale);
+        if target < min {
+            min - target
+        } else if target > max {
+            target - max
+        } else if self.dir_type == DirType::Fixed {
+            0
+        } else {
+            // Threshold 和 Scalable 目录在范围内时按与标称尺寸的差值排序
+            (self.size * self.scale).abs_diff(target)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct IconTheme {
+    // 主题在各个基础目录下的位置，例如 ~/.icons/Adwaita 和 /usr/share/icons/Adwaita
+    roots: Vec<PathBuf>,
+    dirs: Vec<ThemeDir>,
+    inherits: Vec<String>,
+}
+
+// 图标基础目录：$HOME/.icons 和各个数据目录下的 icons
+fn base_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(home) = env::var_os("HOME") {
+        dirs.push(PathBuf::from(&home).join(".icons"));
+    }
+    for data_dir in data_dirs() {
+        dirs.push(data_dir.join("icons"));
+    }
+    dirs
+}
+
+// $XDG_DATA_HOME 和 $XDG_DATA_DIRS，未设置时使用规范中的默认值
+fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    match env::var_os("XDG_DATA_HOME").filter(|v| !v.is_empty()) {
+        Some(dir) => dirs.push(PathBuf::from(dir)),
+        None => {
+            if let Some(home) = env::var_os("HOME") {
+                dirs.push(PathBuf::from(home).join(".local/share"));
+            }
+        }
+    }
+    let system = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(system.split(':').filter(|d| !d.is_empty()).map(PathBuf::from));
+    dirs
+}
+
+fn config_home() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
+
+// 简单的 ini 解析，返回 分组 -> 键 -> 值
+fn parse_ini(content: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut groups: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut current = String::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            current = line[1..line.len() - 1].to_string();
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            groups
+                .entry(current.clone())
+                .or_default()
+                .entry(key.trim().to_string())
+                .or_insert_with(|| value.trim().to_string());
+        }
+    }
+    groups
+}
+
+// 读取桌面环境配置的图标主题：GTK 的 settings.ini 或 KDE 的 kdeglobals
+fn configured_theme() -> Option<String> {
+    let config = config_home()?;
+    let sources = [
+        ("gtk-3.0/settings.ini", "Settings", "gtk-icon-theme-name"),
+        ("gtk-4.0/settings.ini", "Settings", "gtk-icon-theme-name"),
+        ("kdeglobals", "Icons", "Theme"),
+    ];
+    for (file, group, key) in sources {
+        let Ok(content) = fs::read_to_string(config.join(file)) else {
+            continue;
+        };
+        let theme = parse_ini(&content)
+            .get(group)
+            .and_then(|values| values.get(key))
+            .map(|v| v.trim_matches('"').to_string())
+            .filter(|v| !v.is_empty());
+        if theme.is_some() {
+            return theme;
+        }
+    }
+    None
+}
+
+// 没有配置时按桌面环境选择默认主题
+fn default_theme() -> &'static str {
+    let desktop = env::var("XDG_CURRENT_DESKTOP").unwrap_or_default().to_uppercase();
+    if desktop.contains("KDE") {
+        "breeze"
+    } else {
+        "Adwaita"
+    }
+}
+
+fn parse_dir_type(value: Option<&String>) -> DirType {
+    match value.map(String::as_str) {
+        Some("Fixed") => DirType::Fixed,
+        Some("Scalable") => DirType::Scalable,
+        _ => DirType::Threshold,
+    }
+}
+
+fn load_theme(base_dirs: &[PathBuf], name: &str) -> Option<IconTheme> {
+    let roots: Vec<PathBuf> = base_dirs
+        .iter()
+        .map(|dir| dir.join(name))
+        .filter(|dir| dir.is_dir())
+        .collect();
+    // 规范要求读取找到的第一个 index.theme
+    let index = roots
+        .iter()
+        .find_map(|root| fs::read_to_string(root.join("index.theme")).ok())?;
+    let groups = parse_ini(&index);
+    let main = groups.get("Icon Theme")?;
+
+    let list = |key: &str| -> Vec<String> {
+        main.get(key)
+            .map(|v| v.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect())
+            .unwrap_or_default()
+    };
+
+    let mut dirs = Vec::new();
+    for path in list("Directories").into_iter().chain(list("ScaledDirectories")) {
+        let Some(values) = groups.get(&path) else {
+            continue;
+        };
+        let number = |key: &str| values.get(key).and_then(|v| v.trim().parse::<u32>().ok());
+        let Some(size) = number("Size") else {
+            continue;
+        };
+        dirs.push(ThemeDir {
+            size,
+            scale: number("Scale").unwrap_or(1),
+            min_size: number("MinSize").unwrap_or(size),
+            max_size: number("MaxSize").unwrap_or(size),
+            threshold: number("Threshold").unwrap_or(2),
+            dir_type: parse_dir_type(values.get("Type")),
+            path,
+        });
+    }
+
+    Some(IconTheme {
+        roots,
+        dirs,
+        inherits: list("Inherits"),
+    })
+}
+
+fn icon_in_dir(theme: &IconTheme, dir: &ThemeDir, icon: &str) -> Option<PathBuf> {
+    theme.roots.iter().find_map(|root| {
+        ICON_EXTENSIONS
+            .iter()
+            .map(|ext| root.join(&dir.path).join(format!("{}.{}", icon, ext)))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+fn lookup_in_theme(theme: &IconTheme, icon: &str, size: u32, scale: u32) -> Option<PathBuf> {
+    // 先找尺寸完全匹配的目录
+    if let Some(path) = theme
+        .dirs
+        .iter()
+        .filter(|dir| dir.matches_size(size, scale))
+        .find_map(|dir| icon_in_dir(theme, dir, icon))
+    {
+        return Some(path);
+    }
+
+    // 再找尺寸最接近的
+    let mut best: Option<(u32, PathBuf)> = None;
+    for dir in &theme.dirs {
+        let distance = dir.size_distance(size, scale);
+        if best.as_ref().is_some_and(|(d, _)| *d <= distance) {
+            continue;
+        }
+        if let Some(path) = icon_in_dir(theme, dir, icon) {
+            best = Some((distance, path));
+        }
+    }
+    best.map(|(_, path)| path)
+}
+
+// 在一组基础目录中查找图标，主题解析结果缓存，index.theme 在运行期间不会频繁变化
+struct IconThemes {
+    base_dirs: Vec<PathBuf>,
+    // 不属于任何主题的图标所在的目录，例如 /usr/share/pixmaps
+    pixmap_dirs: Vec<PathBuf>,
+    cache: Mutex<HashMap<String, Option<Arc<IconTheme>>>>,
+}
+
+impl IconThemes {
+    fn new(base_dirs: Vec<PathBuf>, pixmap_dirs: Vec<PathBuf>) -> Self {
+        IconThemes {
+            base_dirs,
+            pixmap_dirs,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn theme(&self, name: &str) -> Option<Arc<IconTheme>> {
+        let mut cache = self.cache.lock().ok()?;
+        cache
+            .entry(name.to_string())
+            .or_insert_with(|| load_theme(&self.base_dirs, name).map(Arc::new))
+            .clone()
+    }
+
+    // 在主题及其父主题中查找，visited 防止 Inherits 成环
+    fn find_in_theme(&self, name: &str, icon: &str, size: u32, visited: &mut HashSet<String>) -> Option<PathBuf> {
+        if !visited.insert(name.to_string()) {
+            return None;
+        }
+        let theme = self.theme(name)?;
+        if let Some(path) = lookup_in_theme(&theme, icon, size, 1) {
+            return Some(path);
+        }
+        theme
+            .inherits
+            .iter()
+            .find_map(|parent| self.find_in_theme(parent, icon, size, visited))
+    }
+
+    // 不属于任何主题的图标，直接放在基础目录或 pixmaps 目录中
+    fn lookup_fallback(&self, icon: &str) -> Option<PathBuf> {
+        self.base_dirs.iter().chain(&self.pixmap_dirs).find_map(|dir| {
+            ICON_EXTENSIONS
+                .iter()
+                .map(|ext| dir.join(format!("{}.{}", icon, ext)))
+                .find(|candidate| candidate.is_file())
+        })
+    }
+
+    // 依次查找指定主题、hicolor 和独立图标
+    fn lookup(&self, theme: &str, icon: &str, size: u32) -> Option<PathBuf> {
+        let mut visited = HashSet::new();
+        self.find_in_theme(theme, icon, size, &mut visited)
+            .or_else(|| self.find_in_theme(FALLBACK_THEME, icon, size, &mut visited))
+            .or_else(|| self.lookup_fallback(icon))
+    }
+}
+
+fn icon_themes() -> &'static IconThemes {
+    static THEMES: OnceLock<IconThemes> = OnceLock::new();
+    THEMES.get_or_init(|| IconThemes::new(base_dirs(), vec![PathBuf::from("/usr/share/pixmaps")]))
+}
+
+// 当前使用的图标主题，桌面环境的配置文件只在第一次查找时读取
+fn user_theme() -> &'static str {
+    static THEME: OnceLock<String> = OnceLock::new();
+    THEME.get_or_init(|| configured_theme().unwrap_or_else(|| default_theme().to_string()))
+}
+
+// 按图标名称查找最接近 size 的图标文件，依次查找当前主题、hicolor 和独立图标
+pub fn lookup_icon(icon: &str, size: u32) -> Option<PathBuf> {
+    // .desktop 的 Icon 可能直接是绝对路径
+    if Path::new(icon).is_absolute() {
+        return Path::new(icon).is_file().then(|| PathBuf::from(icon));
+    }
+
+    // 名称中不应带扩展名，兼容一些写成 foo.png 的条目
+    let icon = ICON_EXTENSIONS
+        .iter()
+        .find_map(|ext| icon.strip_suffix(&format!(".{}", ext)))
+        .unwrap_or(icon);
+
+    icon_themes().lookup(user_theme(), icon, size)
+}
+
+// shared-mime-info 中的一条文件名匹配规则
+#[derive(Debug)]
+struct MimeGlob {
+    weight: u32,
+    mime: String,
+    pattern: String,
+    case_sensitive: bool,
+}
+
+#[derive(Debug, Default)]
+struct MimeDatabase {
+    globs: Vec<MimeGlob>,
+    icons: HashMap<String, String>,
+    generic_icons: HashMap<String, String>,
+}
+
+fn read_mime_map(path: &Path, map: &mut HashMap<String, String>) {
+    let Ok(content) = fs::read_to_string(path) else {
+        return;
+    };
+    for line in content.lines() {
+        if let Some((mime, icon)) = line.split_once(':') {
+            map.entry(mime.to_string()).or_insert_with(|| icon.to_string());
+        }
+    }
+}
+
+impl MimeDatabase {
+    fn load(data_dirs: &[PathBuf]) -> Self {
+        let mut db = MimeDatabase::default();
+        for dir in data_dirs.iter().map(|dir| dir.join("mime")) {
+            if let Ok(content) = fs::read_to_string(dir.join("globs2")) {
+                for line in content.lines().filter(|l| !l.starts_with('#')) {
+                    // weight:mime:glob[:flags]
+                    let mut parts = line.splitn(4, ':');
+                    let (Some(weight), Some(mime), Some(pattern)) = (parts.next(), parts.next(), parts.next()) else {
+                        continue;
+                    };
+                    let flags = parts.next().unwrap_or_default();
+                    db.globs.push(MimeGlob {
+                        weight: weight.parse().unwrap_or(50),
+                        mime: mime.to_string(),
+                        pattern: pattern.to_string(),
+                        case_sensitive: flags.split(',').any(|f| f == "cs"),
+                    });
+                }
+            }
+            read_mime_map(&dir.join("icons"), &mut db.icons);
+            read_mime_map(&dir.join("generic-icons"), &mut db.generic_icons);
+        }
+        db
+    }
+
+    // 按文件名推断 MIME 类型，权重相同时选择更长的规则
+    fn mime_type(&self, file_name: &str) -> Option<String> {
+        let lower: Vec<char> = file_name.to_lowercase().chars().collect();
+        let exact: Vec<char> = file_name.chars().collect();
+
+        self.globs
+            .iter()
+            .filter(|glob| !glob.pattern.contains('['))
+            .filter(|glob| {
+                if glob.case_sensitive {
+                    let pattern: Vec<char> = glob.pattern.chars().collect();
+                    glob_matches(&pattern, &exact)
+                } else {
+                    let pattern: Vec<char> = glob.pattern.to_lowercase().chars().collect();
+                    glob_matches(&pattern, &lower)
+                }
+            })
+            .max_by_key(|glob| (glob.weight, glob.pattern.len()))
+            .map(|glob| glob.mime.clone())
+    }
+
+    // MIME 类型对应的图标名称，按优先级排列：
+    // 专用图标、text/plain -> text-plain、generic-icon、媒体类型的通用图标
+    fn icon_names(&self, mime: &str) -> Vec<String> {
+        let mut names = Vec::new();
+        if let Some(icon) = self.icons.get(mime) {
+            names.push(icon.clone());
+        }
+        names.push(mime.replace('/', "-"));
+        if let Some(icon) = self.generic_icons.get(mime) {
+            names.push(icon.clone());
+        }
+        if let Some((media, _)) = mime.split_once('/') {
+            names.push(format!("{}-x-generic", media));
+        }
+        names
+    }
+}
+
+fn mime_database() -> &'static MimeDatabase {
+    static DATABASE: OnceLock<MimeDatabase> = OnceLock::new();
+    DATABASE.get_or_init(|| MimeDatabase::load(&data_dirs()))
+}
+
+// 支持 * 和 ? 的通配符匹配，[...] 形式的规则较少见，直接跳过
+fn glob_matches(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|i| glob_matches(rest, &name[i..])),
+        Some(('?', rest)) => !name.is_empty() && glob_matches(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && glob_matches(rest, &name[1..]),
+    }
+}
+
+// 按文件名推断 MIME 类型
+pub fn mime_type_from_name(file_name: &str) -> Option<String> {
+    mime_database().mime_type(file_name)
+}
+
+// MIME 类型对应的图标名称，按优先级排列
+pub fn mime_icon_names(mime: &str) -> Vec<String> {
+    mime_database().icon_names(mime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir(dir_type: DirType, size: u32, min_size: u32, max_size: u32, scale: u32) -> ThemeDir {
+        ThemeDir {
+            path: String::new(),
+            size,
+            scale,
+            min_size,
+            max_size,
+            threshold: 2,
+            dir_type,
+        }
+    }
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    // Test 继承 Parent，Parent 又继承 Test；hicolor 和 pixmaps 中各有一个独立的图标
+    fn fixture(root: &Path) -> IconThemes {
+        let icons = root.join("icons");
+        write(
+            &icons.join("Test/index.theme"),
+            "[Icon Theme]
+Name=Test
+Inherits=Parent
+Directories=16x16/apps,48x48/apps,scalable/apps
+
+[16x16/apps]
+Size=16
+Type=Fixed
+
+[48x48/apps]
+Size=48
+Type=Threshold
+
+[scalable/apps]
+Size=64
+MinSize=32
+MaxSize=512
+Type=Scalable
+",
+        );
+        write(&icons.join("Test/16x16/apps/app.png"), "");
+        write(&icons.join("Test/scalable/apps/app.svg"), "");
+        write(&icons.join("Test/16x16/apps/small.png"), "");
+        write(&icons.join("Test/48x48/apps/small.png"), "");
+        write(
+            &icons.join("Parent/index.theme"),
+            "[Icon Theme]\nInherits=Test\nDirectories=32x32/apps\n\n[32x32/apps]\nSize=32\nType=Fixed\n",
+        );
+        write(&icons.join("Parent/32x32/apps/parent-only.png"), "");
+        write(
+            &icons.join("hicolor/index.theme"),
+            "[Icon Theme]\nDirectories=48x48/apps\n\n[48x48/apps]\nSize=48\nType=Threshold\n",
+        );
+        write(&icons.join("hicolor/48x48/apps/hicolor-only.png"), "");
+        write(&icons.join("loose.svg"), "");
+        write(&root.join("pixmaps/pixmap-only.png"), "");
+        IconThemes::new(vec![icons], vec![root.join("pixmaps")])
+    }
+
+    #[test]
+    fn matches_size_by_dir_type() {
+        let fixed = dir(DirType::Fixed, 32, 32, 32, 1);
+        assert!(fixed.matches_size(32, 1));
+        assert!(!fixed.matches_size(31, 1));
+        assert!(!fixed.matches_size(32, 2));
+
+        let scalable = dir(DirType::Scalable, 48, 16, 256, 1);
+        assert!(scalable.matches_size(16, 1));
+        assert!(scalable.matches_size(256, 1));
+        assert!(!scalable.matches_size(8, 1));
+
+        let threshold = dir(DirType::Threshold, 48, 48, 48, 1);
+        assert!(threshold.matches_size(46, 1));
+        assert!(threshold.matches_size(50, 1));
+        assert!(!threshold.matches_size(45, 1));
+        assert!(!threshold.matches_size(51, 1));
+    }
+
+    #[test]
+    fn size_distance_by_dir_type() {
+        let fixed = dir(DirType::Fixed, 32, 32, 32, 1);
+        assert_eq!(fixed.size_distance(32, 1), 0);
+        assert_eq!(fixed.size_distance(24, 1), 8);
+        assert_eq!(fixed.size_distance(40, 1), 8);
+
+        // 范围内按与标称尺寸的差值
+        let scalable = dir(DirType::Scalable, 48, 16, 256, 1);
+        assert_eq!(scalable.size_distance(8, 1), 8);
+        assert_eq!(scalable.size_distance(300, 1), 44);
+        assert_eq!(scalable.size_distance(100, 1), 52);
+
+        let threshold = dir(DirType::Threshold, 48, 48, 48, 1);
+        assert_eq!(threshold.size_distance(40, 1), 6);
+        assert_eq!(threshold.size_distance(49, 1), 1);
+        assert_eq!(threshold.size_distance(60, 1), 10);
+
+        // 按实际像素比较
+        let scaled = dir(DirType::Fixed, 16, 16, 16, 2);
+        assert_eq!(scaled.size_distance(32, 1), 0);
+        assert_eq!(scaled.size_distance(16, 2), 0);
+    }
+
+    #[test]
+    fn lookup_prefers_matching_then_closest_size() {
+        let temp = tempfile::tempdir().unwrap();
+        let themes = fixture(temp.path());
+        let theme_dir = temp.path().join("icons/Test");
+
+        assert_eq!(themes.lookup("Test", "app", 16), Some(theme_dir.join("16x16/apps/app.png")));
+        // 48 在 Threshold 目录中没有，Scalable 目录覆盖 32 到 512
+        assert_eq!(themes.lookup("Test", "app", 48), Some(theme_dir.join("scalable/apps/app.svg")));
+        // 没有匹配的目录时取最接近的：16 相差 84，48 的阈值上限相差 50
+        assert_eq!(themes.lookup("Test", "small", 100), Some(theme_dir.join("48x48/apps/small.png")));
+        assert_eq!(themes.lookup("Test", "small", 20), Some(theme_dir.join("16x16/apps/small.png")));
+        // 名称不带扩展名时才会命中
+        assert_eq!(themes.lookup("Test", "app.png", 16), None);
+    }
+
+    #[test]
+    fn lookup_follows_inherits_without_looping() {
+        let temp = tempfile::tempdir().unwrap();
+        let themes = fixture(temp.path());
+
+        assert_eq!(
+            themes.lookup("Test", "parent-only", 32),
+            Some(temp.path().join("icons/Parent/32x32/apps/parent-only.png"))
+        );
+        // Test 和 Parent 互相继承，找不到时也能结束
+        let mut visited = HashSet::new();
+        assert_eq!(themes.find_in_theme("Test", "nowhere", 32, &mut visited), None);
+        assert_eq!(visited.len(), 2);
+        assert_eq!(themes.lookup("Parent", "nowhere", 32), None);
+    }
+
+    #[test]
+    fn lookup_falls_back_to_hicolor_then_pixmaps() {
+        let temp = tempfile::tempdir().unwrap();
+        let themes = fixture(temp.path());
+
+        assert_eq!(
+            themes.lookup("Test", "hicolor-only", 16),
+            Some(temp.path().join("icons/hicolor/48x48/apps/hicolor-only.png"))
+        );
+        // 主题不存在时同样回退
+        assert_eq!(
+            themes.lookup("Missing", "hicolor-only", 16),
+            Some(temp.path().join("icons/hicolor/48x48/apps/hicolor-only.png"))
+        );
+        assert_eq!(themes.lookup("Test", "loose", 16), Some(temp.path().join("icons/loose.svg")));
+        assert_eq!(
+            themes.lookup("Test", "pixmap-only", 16),
+            Some(temp.path().join("pixmaps/pixmap-only.png"))
+        );
+    }
+
+    fn mime_fixture(root: &Path) -> MimeDatabase {
+        write(
+            &root.join("first/mime/globs2"),
+            "# weight:mime:glob[:flags]
+50:text/plain:*.txt
+50:text/x-cmake:CMakeLists.txt
+50:application/gzip:*.gz
+50:application/x-compressed-tar:*.tar.gz
+80:text/x-readme:README*:cs
+50:text/x-bracket:*.[ab]
+",
+        );
+        write(&root.join("first/mime/icons"), "application/x-compressed-tar:package-x-compressed\n");
+        write(&root.join("first/mime/generic-icons"), "text/x-cmake:text-x-script\n");
+        // 靠后的数据目录中的图标不覆盖前面的
+        write(&root.join("second/mime/icons"), "application/x-compressed-tar:ignored\ntext/plain:text-plain-custom\n");
+        MimeDatabase::load(&[root.join("first"), root.join("second")])
+    }
+
+    #[test]
+    fn mime_type_prefers_weight_then_longest_pattern() {
+        let temp = tempfile::tempdir().unwrap();
+        let db = mime_fixture(temp.path());
+
+        assert_eq!(db.mime_type("a.tar.gz").as_deref(), Some("application/x-compressed-tar"));
+        assert_eq!(db.mime_type("A.GZ").as_deref(), Some("application/gzip"));
+        assert_eq!(db.mime_type("CMakeLists.txt").as_deref(), Some("text/x-cmake"));
+        assert_eq!(db.mime_type("notes.txt").as_deref(), Some("text/plain"));
+        // 权重高的规则优先，cs 规则区分大小写
+        assert_eq!(db.mime_type("README.txt").as_deref(), Some("text/x-readme"));
+        assert_eq!(db.mime_type("readme.txt").as_deref(), Some("text/plain"));
+        // [...] 形式的规则跳过
+        assert_eq!(db.mime_type("x.a"), None);
+    }
+
+    #[test]
+    fn mime_icon_names_include_generic_icons() {
+        let temp = tempfile::tempdir().unwrap();
+        let db = mime_fixture(temp.path());
+
+        assert_eq!(
+            db.icon_names("application/x-compressed-tar"),
+            ["package-x-compressed", "application-x-compressed-tar", "application-x-generic"]
+        );
+        assert_eq!(db.icon_names("text/x-cmake"), ["text-x-cmake", "text-x-script", "text-x-generic"]);
+        assert_eq!(db.icon_names("text/plain"), ["text-plain-custom", "text-plain", "text-x-generic"]);
+        assert_eq!(db.icon_names("weird"), ["weird"]);
+    }
+}
@@ -0,0 +1,484 @@
+use std::collections::HashSet;
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, OptionalExtension, Params};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use crate::icon_cache::icon_hash;
+use crate::models::{EditAction, JournalEntry};
+use crate::utils::now_millis;
+
+// 编辑记录：每次编辑保存受影响的行修改前后的内容，撤销时写回修改前的内容，重做时写回修改后的内容
+// 修改前后都存在的行只保存有变化的列，新增或彻底删除的行保存全部列
+// 记录中引用的图标保存在 journal_icons 表中，清理图标时保留
+// 只记录用户的编辑，文件监视和文件夹同步产生的修改不记录
+
+// 保留的记录数量，超出时删除最早的记录
+const MAX_JOURNAL_ENTRIES: i64 = 200;
+
+// 记录中的一行，before 或 after 为空表示该行在修改前不存在或修改后被删除
+// 两者都存在时只包含有变化的列
+#[derive(Debug, Serialize, Deserialize)]
+struct RowChange {
+    id: String,
+    before: Option<Map<String, JsonValue>>,
+    after: Option<Map<String, JsonValue>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Changes {
+    #[serde(default)]
+    files: Vec<RowChange>,
+    #[serde(default)]
+    categories: Vec<RowChange>,
+}
+
+fn to_json(value: Value) -> JsonValue {
+    match value {
+        Value::Null => JsonValue::Null,
+        Value::Integer(i) => i.into(),
+        Value::Real(f) => f.into(),
+        Value::Text(s) => s.into(),
+        Value::Blob(b) => b.into(),
+    }
+}
+
+fn from_json(value: &JsonValue) -> Value {
+    match value {
+        JsonValue::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        JsonValue::String(s) => Value::Text(s.clone()),
+        JsonValue::Bool(b) => Value::Integer(*b as i64),
+        JsonValue::Array(bytes) => Value::Blob(
+            bytes.iter().filter_map(|b| b.as_u64()).map(|b| b as u8).collect(),
+        ),
+        JsonValue::Null | JsonValue::Object(_) => Value::Null,
+    }
+}
+
+// 读取一行的全部列
+fn read_row(conn: &Connection, table: &str, id: &str) -> rusqlite::Result<Option<Map<String, JsonValue>>> {
+    let mut stmt = conn.prepare_cached(&format!("SELECT * FROM {} WHERE id = ?", table))?;
+    let names: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    stmt.query_row([id], |row| {
+        let mut map = Map::new();
+        for (index, name) in names.iter().enumerate() {
+            map.insert(name.clone(), to_json(row.get(index)?));
+        }
+        Ok(map)
+    })
+    .optional()
+}
+
+// 将一行恢复为 target 的内容，other 是撤销或重做前应有的内容
+// 行存在时只写回这次编辑修改过的列，之后其他编辑或文件监视对其余列的修改保留
+// target 只有部分列而行已被彻底删除时无法恢复，跳过
+fn write_row(
+    conn: &Connection,
+    table: &str,
+    id: &str,
+    target: Option<&Map<String, JsonValue>>,
+    other: Option<&Map<String, JsonValue>>,
+) -> rusqlite::Result<()> {
+    let Some(target) = target else {
+        conn.execute(&format!("DELETE FROM {} WHERE id = ?", table), [id])?;
+        return Ok(());
+    };
+
+    let exists: bool = conn.query_row(
+        &format!("SELECT EXISTS(SELECT 1 FROM {} WHERE id = ?)", table),
+        [id],
+        |row| row.get(0),
+    )?;
+    if !exists && other.is_some() {
+        return Ok(());
+    }
+    let columns: Vec<(&String, &JsonValue)> = match other {
+        Some(other) if exists => target
+            .iter()
+            .filter(|(name, value)| other.get(*name) != Some(*value))
+            .collect(),
+        _ => target.iter().collect(),
+    };
+    if columns.is_empty() {
+        return Ok(());
+    }
+
+    let mut values: Vec<Value> = columns.iter().map(|(_, value)| from_json(value)).collect();
+    let sql = if exists {
+        let assignments: Vec<String> = columns.iter().map(|(name, _)| format!("\"{}\" = ?", name)).collect();
+        format!("UPDATE {} SET {} WHERE id = ?", table, assignments.join(", "))
+    } else {
+        let names: Vec<String> = columns.iter().map(|(name, _)| format!("\"{}\"", name)).collect();
+        let placeholders = vec!["?"; names.len()].join(", ");
+        format!("INSERT INTO {} ({}) VALUES ({})", table, names.join(", "), placeholders)
+    };
+    if exists {
+        values.push(Value::Text(id.to_string()));
+    }
+    conn.execute(&sql, rusqlite::params_from_iter(values))?;
+    Ok(())
+}
+
+// 记录一次编辑：修改前调用 files / categories 保存受影响的行，修改后调用 finish 写入记录
+// 应与修改在同一个事务中使用
+pub struct Recorder {
+    action: EditAction,
+    files: Vec<RowChange>,
+    categories: Vec<RowChange>,
+}
+
+impl Recorder {
+    pub fn new(action: EditAction) -> Self {
+        Recorder {
+            action,
+            files: Vec::new(),
+            categories: Vec::new(),
+        }
+    }
+
+    fn track<S: AsRef<str>>(
+        rows: &mut Vec<RowChange>,
+        conn: &Connection,
+        table: &str,
+        ids: impl IntoIterator<Item = S>,
+    ) -> Result<(), String> {
+        let mut seen: HashSet<String> = rows.iter().map(|row| row.id.clone()).collect();
+        for id in ids {
+            let id = id.as_ref();
+            if !seen.insert(id.to_string()) {
+                continue;
+            }
+            rows.push(RowChange {
+                id: id.to_string(),
+                before: read_row(conn, table, id).map_err(|e| e.to_string())?,
+                after: None,
+            });
+        }
+        Ok(())
+    }
+
+    // 保存文件修改前的内容，同一行只保存第一次
+    pub fn files<S: AsRef<str>>(&mut self, conn: &Connection, ids: impl IntoIterator<Item = S>) -> Result<(), String> {
+        Self::track(&mut self.files, conn, "files", ids)
+    }
+
+    // 保存分类修改前的内容，同一行只保存第一次
+    pub fn categories<S: AsRef<str>>(&mut self, conn: &Connection, ids: impl IntoIterator<Item = S>) -> Result<(), String> {
+        Self::track(&mut self.categories, conn, "categories", ids)
+    }
+
+    // 按查询到的 ID 保存文件修改前的内容
+    pub fn files_where<P: Params>(&mut self, conn: &Connection, sql: &str, params: P) -> Result<(), String> {
+        let ids = query_ids(conn, sql, params)?;
+        self.files(conn, ids)
+    }
+
+    // 按查询到的 ID 保存分类修改前的内容
+    pub fn categories_where<P: Params>(&mut self, conn: &Connection, sql: &str, params: P) -> Result<(), String> {
+        let ids = query_ids(conn, sql, params)?;
+        self.categories(conn, ids)
+    }
+
+    // 读取修改后的内容，只保存有变化的行和列；有变化时清除可以重做的记录
+    pub fn finish(mut self, conn: &Connection) -> Result<(), String> {
+        for (table, rows) in [("files", &mut self.files), ("categories", &mut self.categories)] {
+            for row in rows.iter_mut() {
+                row.after = read_row(conn, table, &row.id).map_err(|e| e.to_string())?;
+                if let (Some(before), Some(after)) = (&mut row.before, &mut row.after) {
+                    before.retain(|name, value| after.get(name) != Some(value));
+                    after.retain(|name, _| before.contains_key(name));
+                }
+            }
+            rows.retain(|row| row.before != row.after);
+        }
+        if self.files.is_empty() && self.categories.is_empty() {
+            return Ok(());
+        }
+
+        let icons: HashSet<String> = self
+            .files
+            .iter()
+            .chain(&self.categories)
+            .flat_map(|row| [&row.before, &row.after])
+            .flatten()
+            .filter_map(|columns| columns.get("icon")?.as_str().and_then(icon_hash).map(String::from))
+            .collect();
+
+        let changes = serde_json::to_string(&Changes {
+            files: self.files,
+            categories: self.categories,
+        })
+        .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM journal WHERE undone = 1", [])
+            .map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO journal (action, changes, created_at) VALUES (?, ?, ?)",
+            params![self.action.as_str(), changes, now_millis()],
+        )
+        .map_err(|e| e.to_string())?;
+        let journal_id = conn.last_insert_rowid();
+        for hash in &icons {
+            conn.prepare_cached("INSERT INTO journal_icons (journal_id, hash) VALUES (?, ?)")
+                .and_then(|mut stmt| stmt.execute(params![journal_id, hash]))
+                .map_err(|e| e.to_string())?;
+        }
+        conn.execute(
+            "DELETE FROM journal WHERE id <= (SELECT MAX(id) FROM journal) - ?",
+            [MAX_JOURNAL_ENTRIES],
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM journal_icons WHERE journal_id NOT IN (SELECT id FROM journal)", [])
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+fn query_ids<P: Params>(conn: &Connection, sql: &str, params: P) -> Result<Vec<String>, String> {
+    let mut stmt = conn.prepare_cached(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params, |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+// 撤销最近一次未撤销的编辑，没有可以撤销的编辑时返回 None
+pub fn undo(conn: &Connection) -> Result<Option<JournalEntry>, String> {
+    apply(conn, true)
+}
+
+// 重做最早一次被撤销的编辑，没有可以重做的编辑时返回 None
+pub fn redo(conn: &Connection) -> Result<Option<JournalEntry>, String> {
+    apply(conn, false)
+}
+
+fn apply(conn: &Connection, undo: bool) -> Result<Option<JournalEntry>, String> {
+    let sql = if undo {
+        "SELECT id, action, changes, created_at FROM journal WHERE undone = 0 ORDER BY id DESC LIMIT 1"
+    } else {
+        "SELECT id, action, changes, created_at FROM journal WHERE undone = 1 ORDER BY id ASC LIMIT 1"
+    };
+    let row: Option<(i64, String, String, i64)> = conn
+        .query_row(sql, [], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some((id, action, changes, created_at)) = row else {
+        return Ok(None);
+    };
+
+    let action = EditAction::from_db(&action).ok_or_else(|| format!("Unknown journal action: {}", action))?;
+    let changes: Changes = serde_json::from_str(&changes).map_err(|e| e.to_string())?;
+    for (table, rows) in [("files", &changes.files), ("categories", &changes.categories)] {
+        for row in rows {
+            let (target, other) = if undo {
+                (row.before.as_ref(), row.after.as_ref())
+            } else {
+                (row.after.as_ref(), row.before.as_ref())
+            };
+            write_row(conn, table, &row.id, target, other).map_err(|e| e.to_string())?;
+        }
+    }
+    conn.execute("UPDATE journal SET undone = ? WHERE id = ?", params![undo, id])
+        .map_err(|e| e.to_string())?;
+
+    Ok(Some(JournalEntry { id, action, created_at }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_database;
+    use crate::icon_cache::{prune_unused_icons, store_icon_bytes};
+
+    type Snapshot = Vec<Option<Map<String, JsonValue>>>;
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        init_database(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO categories (id, parent_id, name, sort_order) VALUES
+                ('main', NULL, 'main', 0), ('work', NULL, 'work', 1), ('tools', 'work', 'tools', 2);
+            INSERT INTO files (id, name, display_name, path, content, category, sort_order) VALUES
+                ('a', 'a.md', 'A', '/tmp/a.md', 'hello', 'work', 0),
+                ('b', 'b.md', 'B', '/tmp/b.md', NULL, 'work', 1),
+                ('c', 'c.md', 'C', '/tmp/c.md', NULL, 'tools', 0);",
+        )
+        .unwrap();
+        conn
+    }
+
+    // 所有测试用到的行的完整内容，不存在的行为 None
+    fn snapshot(conn: &Connection) -> Snapshot {
+        let files = ["a", "b", "c", "d"].map(|id| read_row(conn, "files", id).unwrap());
+        let categories = ["main", "work", "tools"].map(|id| read_row(conn, "categories", id).unwrap());
+        files.into_iter().chain(categories).collect()
+    }
+
+    // 撤销后恢复到 before，重做后恢复到 after，之后没有可以重做的编辑
+    fn assert_round_trip(conn: &Connection, action: EditAction, before: &Snapshot, after: &Snapshot) {
+        assert_eq!(undo(conn).unwrap().map(|entry| entry.action), Some(action));
+        assert_eq!(&snapshot(conn), before);
+        assert_eq!(redo(conn).unwrap().map(|entry| entry.action), Some(action));
+        assert_eq!(&snapshot(conn), after);
+        assert!(redo(conn).unwrap().is_none());
+    }
+
+    fn latest_changes(conn: &Connection) -> Changes {
+        let changes: String = conn
+            .query_row("SELECT changes FROM journal ORDER BY id DESC LIMIT 1", [], |row| row.get(0))
+            .unwrap();
+        serde_json::from_str(&changes).unwrap()
+    }
+
+    #[test]
+    fn add_item_round_trip() {
+        let conn = test_db();
+        let before = snapshot(&conn);
+
+        let mut recorder = Recorder::new(EditAction::AddItem);
+        recorder.files(&conn, ["d"]).unwrap();
+        conn.execute(
+            "INSERT INTO files (id, name, display_name, path, category, sort_order) VALUES ('d', 'd.md', '', '/tmp/d.md', 'main', 0)",
+            [],
+        )
+        .unwrap();
+        recorder.finish(&conn).unwrap();
+        let after = snapshot(&conn);
+
+        // 新增的行保存全部列
+        let changes = latest_changes(&conn);
+        assert!(changes.files[0].before.is_none());
+        assert_eq!(changes.files[0].after.as_ref().unwrap()["path"], "/tmp/d.md");
+
+        assert_round_trip(&conn, EditAction::AddItem, &before, &after);
+    }
+
+    #[test]
+    fn update_item_stores_only_changed_columns() {
+        let conn = test_db();
+        let before = snapshot(&conn);
+
+        let mut recorder = Recorder::new(EditAction::UpdateItem);
+        recorder.files(&conn, ["a"]).unwrap();
+        conn.execute("UPDATE files SET display_name = 'Notes' WHERE id = 'a'", []).unwrap();
+        recorder.finish(&conn).unwrap();
+        let after = snapshot(&conn);
+
+        let changes = latest_changes(&conn);
+        assert_eq!(changes.files.len(), 1);
+        let keys = |row: &Option<Map<String, JsonValue>>| row.as_ref().unwrap().keys().cloned().collect::<Vec<_>>();
+        assert_eq!(keys(&changes.files[0].before), ["display_name"]);
+        assert_eq!(keys(&changes.files[0].after), ["display_name"]);
+
+        assert_round_trip(&conn, EditAction::UpdateItem, &before, &after);
+
+        // 之后文件监视修改的其他列在撤销时保留
+        conn.execute("UPDATE files SET content = 'changed' WHERE id = 'a'", []).unwrap();
+        undo(&conn).unwrap();
+        let (display_name, content): (String, String) = conn
+            .query_row("SELECT display_name, content FROM files WHERE id = 'a'", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((display_name.as_str(), content.as_str()), ("A", "changed"));
+    }
+
+    #[test]
+    fn update_of_purged_row_is_skipped() {
+        let conn = test_db();
+        let mut recorder = Recorder::new(EditAction::UpdateItem);
+        recorder.files(&conn, ["a"]).unwrap();
+        conn.execute("UPDATE files SET display_name = 'Notes' WHERE id = 'a'", []).unwrap();
+        recorder.finish(&conn).unwrap();
+
+        // 回收站清空时彻底删除，只有部分列时不重新插入
+        conn.execute("DELETE FROM files WHERE id = 'a'", []).unwrap();
+        assert!(undo(&conn).unwrap().is_some());
+        assert!(read_row(&conn, "files", "a").unwrap().is_none());
+    }
+
+    #[test]
+    fn reorder_items_round_trip() {
+        let conn = test_db();
+        let before = snapshot(&conn);
+
+        let mut recorder = Recorder::new(EditAction::ReorderItems);
+        recorder.files_where(&conn, "SELECT id FROM files WHERE category = ?", ["work"]).unwrap();
+        conn.execute_batch(
+            "UPDATE files SET sort_order = 1 WHERE id = 'a';
+            UPDATE files SET sort_order = 0 WHERE id = 'b';",
+        )
+        .unwrap();
+        recorder.finish(&conn).unwrap();
+        let after = snapshot(&conn);
+
+        assert_round_trip(&conn, EditAction::ReorderItems, &before, &after);
+    }
+
+    #[test]
+    fn delete_category_round_trip() {
+        let conn = test_db();
+        let before = snapshot(&conn);
+
+        // 项目移到上级分类后删除分类
+        let mut recorder = Recorder::new(EditAction::DeleteCategory);
+        recorder.categories(&conn, ["tools"]).unwrap();
+        recorder.files_where(&conn, "SELECT id FROM files WHERE category = ?", ["tools"]).unwrap();
+        conn.execute_batch(
+            "UPDATE files SET category = 'work', sort_order = 2 WHERE category = 'tools';
+            DELETE FROM categories WHERE id = 'tools';",
+        )
+        .unwrap();
+        recorder.finish(&conn).unwrap();
+        let after = snapshot(&conn);
+
+        // 删除的分类保存全部列
+        let changes = latest_changes(&conn);
+        assert_eq!(changes.categories[0].before.as_ref().unwrap()["parent_id"], "work");
+        assert!(changes.categories[0].after.is_none());
+
+        assert_round_trip(&conn, EditAction::DeleteCategory, &before, &after);
+    }
+
+    #[test]
+    fn unchanged_edit_is_not_recorded() {
+        let conn = test_db();
+        let mut recorder = Recorder::new(EditAction::RenameCategory);
+        recorder.categories(&conn, ["work"]).unwrap();
+        conn.execute("UPDATE categories SET name = 'work' WHERE id = 'work'", []).unwrap();
+        recorder.finish(&conn).unwrap();
+        assert!(undo(&conn).unwrap().is_none());
+    }
+
+    #[test]
+    fn icons_are_kept_while_referenced_by_journal() {
+        let conn = test_db();
+        let icon = store_icon_bytes(&conn, "image/png", b"icon").unwrap();
+        let icon_count = |conn: &Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM icon_cache", [], |row| row.get(0)).unwrap()
+        };
+        conn.execute("UPDATE files SET icon = ? WHERE id = 'a'", [&icon]).unwrap();
+
+        let mut recorder = Recorder::new(EditAction::UpdateItem);
+        recorder.files(&conn, ["a"]).unwrap();
+        conn.execute("UPDATE files SET icon = '' WHERE id = 'a'", []).unwrap();
+        recorder.finish(&conn).unwrap();
+
+        // 只有编辑记录引用的图标保留，撤销时恢复
+        prune_unused_icons(&conn).unwrap();
+        assert_eq!(icon_count(&conn), 1);
+        undo(&conn).unwrap();
+        let restored: String = conn.query_row("SELECT icon FROM files WHERE id = 'a'", [], |row| row.get(0)).unwrap();
+        assert_eq!(restored, icon);
+
+        // 图标被不记录的修改替换，之后新的编辑清除可以重做的记录，图标不再被引用
+        conn.execute("UPDATE files SET icon = '' WHERE id = 'a'", []).unwrap();
+        let mut recorder = Recorder::new(EditAction::UpdateItem);
+        recorder.files(&conn, ["b"]).unwrap();
+        conn.execute("UPDATE files SET display_name = 'Notes' WHERE id = 'b'", []).unwrap();
+        recorder.finish(&conn).unwrap();
+        prune_unused_icons(&conn).unwrap();
+        assert_eq!(icon_count(&conn), 0);
+    }
+}
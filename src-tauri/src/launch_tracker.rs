@@ -0,0 +1,398 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+use rusqlite::{params, Connection};
+use tauri::{AppHandle, Emitter, Manager};
+use crate::commands::notification::{send_notification_custom, NotificationParams};
+use crate::db::DbState;
+use crate::models::{LaunchFailure, RunningLaunch};
+use crate::text_preview::decode_preview;
+use crate::utils::now_millis;
+
+// 进程启动后很快以非零状态退出时发送的事件，内容为 LaunchFailure
+pub const LAUNCH_FAILED_EVENT: &str = "launch-failed";
+
+// 在这段时间内以非零状态退出视为启动失败，之后的退出属于正常使用
+const EARLY_EXIT_WINDOW: Duration = Duration::from_secs(10);
+
+// 检查子进程是否退出的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// 失败事件中保留的输出长度
+const OUTPUT_TAIL_BYTES: u64 = 4 * 1024;
+
+// 持续输出的命令，临时文件超过这个大小时只保留末尾部分
+const MAX_OUTPUT_LOG_BYTES: u64 = 1024 * 1024;
+
+// 结果通知中保留的输出行数
+const NOTIFY_TAIL_LINES: usize = 5;
+
+// 输出临时文件名的前缀，文件名为 <前缀><启动器进程 ID>-<launch_id>.log
+const LOG_PREFIX: &str = "oopslauncher-";
+
+// 进程退出时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReport {
+    // 不检查退出状态，例如 Windows 上的 explorer.exe 打开成功时也返回 1
+    Ignore,
+    // 很快以非零状态退出时通知前端，标准错误输出写入临时文件，超过判断时间后不再保留
+    EarlyFailure,
+    // 退出后发送系统通知显示输出的末尾部分，标准输出和标准错误输出都写入临时文件，过大时只保留末尾部分
+    Notify,
+}
+
+// 要启动的启动项
+pub struct LaunchTarget<'a> {
+    pub file_id: &'a str,
+    pub display_name: Option<String>,
+    pub path: &'a str,
+    // 交给系统默认程序打开，跟踪的是很快退出的 xdg-open、open 或 explorer.exe，不列为运行中的进程
+    pub via_opener: bool,
+    // 启动前写入的启动历史记录，进程很快失败时更新这条记录
+    pub history_id: i64,
+}
+
+struct TrackedLaunch {
+    child: Child,
+    info: RunningLaunch,
+    report: ExitReport,
+    // 输出的临时文件，不检查退出状态时为空
+    output_log: Option<PathBuf>,
+    // 是否出现在运行中的进程列表中，可以被结束
+    listed: bool,
+}
+
+// 由启动器启动的子进程，在 setup 中注册为 Tauri 托管状态
+#[derive(Default)]
+pub struct LaunchTracker {
+    next_id: AtomicU64,
+    launches: Mutex<HashMap<u64, TrackedLaunch>>,
+}
+
+impl LaunchTracker {
+    fn launches(&self) -> Result<MutexGuard<'_, HashMap<u64, TrackedLaunch>>, String> {
+        self.launches
+            .lock()
+            .map_err(|_| "Launch tracker lock poisoned".to_string())
+    }
+
+    // 启动命令并跟踪子进程，进程退出后自动移除，退出时按 report 处理
+    pub fn spawn(
+        &self,
+        app: &AppHandle,
+        mut command: Command,
+        target: LaunchTarget,
+        report: ExitReport,
+    ) -> Result<RunningLaunch, String> {
+        let launch_id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let path = target.path;
+
+        // 使用文件而不是管道，启动器退出后子进程继续写入也不会收到 SIGPIPE
+        // 子进程以追加方式写入，清空文件后的输出从头开始
+        let output_log = (report != ExitReport::Ignore)
+            .then(|| std::env::temp_dir().join(format!("{}{}-{}.log", LOG_PREFIX, std::process::id(), launch_id)))
+            .and_then(|log| {
+                let file = OpenOptions::new().create(true).append(true).open(&log).ok()?;
+                file.set_len(0).ok()?;
+                if report == ExitReport::Notify {
+                    command.stdout(Stdio::from(file.try_clone().ok()?));
+                }
+                command.stderr(Stdio::from(file));
+                Some(log)
+            });
+
+        let started = Instant::now();
+        let started_at = now_millis();
+        let child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                remove_log(output_log.as_deref());
+                return Err(format!("Failed to launch {}: {}", path, e));
+            }
+        };
+
+        let info = RunningLaunch {
+            launch_id,
+            file_id: target.file_id.to_string(),
+            display_name: target.display_name,
+            path: path.to_string(),
+            pid: child.id(),
+            started_at,
+            history_id: target.history_id,
+        };
+        self.launches()?.insert(
+            launch_id,
+            TrackedLaunch {
+                child,
+                info: info.clone(),
+                report,
+                output_log,
+                listed: !target.via_opener,
+            },
+        );
+
+        let app = app.clone();
+        thread::spawn(move || watch(app, launch_id, started));
+        Ok(info)
+    }
+
+    // 仍在运行的进程，按启动时间排序
+    pub fn running(&self) -> Result<Vec<RunningLaunch>, String> {
+        let mut running: Vec<RunningLaunch> = self
+            .launches()?
+            .values()
+            .filter(|launch| launch.listed)
+            .map(|launch| launch.info.clone())
+            .collect();
+        running.sort_by_key(|launch| (launch.started_at, launch.launch_id));
+        Ok(running)
+    }
+
+    // 结束进程，被结束的进程不会再发送失败事件
+    pub fn kill(&self, launch_id: u64) -> Result<(), String> {
+        let mut launch = {
+            let mut launches = self.launches()?;
+            match launches.get(&launch_id) {
+                Some(launch) if launch.listed => launches.remove(&launch_id),
+                _ => None,
+            }
+        }
+        .ok_or_else(|| format!("Launch is not running: {}", launch_id))?;
+
+        let result = launch.child.kill();
+        // 回收子进程，避免留下僵尸进程
+        let _ = launch.child.wait();
+        remove_log(launch.output_log.as_deref());
+        result.map_err(|e| e.to_string())
+    }
+}
+
+// 轮询子进程状态，退出后从列表中移除
+fn watch(app: AppHandle, launch_id: u64, started: Instant) {
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        let tracker = app.state::<LaunchTracker>();
+        let Ok(mut launches) = tracker.launches() else {
+            return;
+        };
+        // 已经被 kill 移除
+        let Some(launch) = launches.get_mut(&launch_id) else {
+            return;
+        };
+        let status = match launch.child.try_wait() {
+            Ok(Some(status)) => Some(status),
+            Ok(None) => {
+                // 避免长时间运行的程序写满磁盘：超过判断启动失败的时间后不再需要输出，清空临时文件
+                // 结果通知只需要输出的末尾部分
+                if let Some(log) = launch.output_log.as_deref() {
+                    match launch.report {
+                        ExitReport::EarlyFailure if started.elapsed() >= EARLY_EXIT_WINDOW => truncate_log(log),
+                        ExitReport::Notify => trim_log(log),
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+            Err(e) => {
+                println!("Failed to check launch {}: {}", launch_id, e);
+                None
+            }
+        };
+        let Some(launch) = launches.remove(&launch_id) else {
+            return;
+        };
+        drop(launches);
+
+        let runtime = started.elapsed();
+        if let (Some(status), Some(log)) = (status, launch.output_log.as_deref()) {
+            let output = read_tail(log);
+            let failed = !status.success();
+
+            let error = match status.code() {
+                Some(code) => format!("Process exited with code {}", code),
+                None => "Process was terminated by a signal".to_string(),
+            };
+
+            if launch.report == ExitReport::Notify {
+                // 命令启动项通过系统通知显示结果，失败时只更新启动历史
+                notify_result(&app, &launch.info, status.code(), failed, &output);
+                if failed {
+                    record_failure(&app, &launch.info, &error);
+                }
+            } else if failed && runtime < EARLY_EXIT_WINDOW {
+                record_failure(&app, &launch.info, &error);
+                let failure = LaunchFailure {
+                    launch_id,
+                    file_id: launch.info.file_id,
+                    display_name: launch.info.display_name,
+                    path: launch.info.path,
+                    pid: launch.info.pid,
+                    exit_code: status.code(),
+                    error,
+                    stderr_tail: output,
+                    runtime_ms: runtime.as_millis() as u64,
+                };
+                if let Err(e) = app.emit(LAUNCH_FAILED_EVENT, failure) {
+                    println!("Failed to emit {}: {}", LAUNCH_FAILED_EVENT, e);
+                }
+            }
+        }
+        remove_log(launch.output_log.as_deref());
+        return;
+    }
+}
+
+// 将启动历史中的记录改为失败
+fn record_failure(app: &AppHandle, info: &RunningLaunch, error: &str) {
+    println!("Launch of {} failed: {}", info.path, error);
+
+    let updated = app
+        .state::<DbState>()
+        .conn()
+        .and_then(|conn| mark_failed(&conn, info.history_id, error));
+    if let Err(e) = updated {
+        println!("Failed to record launch failure: {}", e);
+    }
+}
+
+// 按 ID 将一条启动历史标记为失败
+pub fn mark_failed(conn: &Connection, history_id: i64, error: &str) -> Result<(), String> {
+    conn.prepare_cached("UPDATE launch_history SET success = 0, error = ? WHERE id = ?")
+        .map_err(|e| e.to_string())?
+        .execute(params![error, history_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// 发送系统通知，内容为输出的最后几行
+fn notify_result(app: &AppHandle, info: &RunningLaunch, exit_code: Option<i32>, failed: bool, output: &str) {
+    let name = info.display_name.as_deref().unwrap_or(&info.path);
+    let title = match (failed, exit_code) {
+        (false, _) => format!("{} 执行完成", name),
+        (true, Some(code)) => format!("{} 执行失败（退出码 {}）", name, code),
+        (true, None) => format!("{} 已被终止", name),
+    };
+
+    let lines: Vec<&str> = output.lines().filter(|line| !line.trim().is_empty()).collect();
+    let body = if lines.is_empty() {
+        "没有输出".to_string()
+    } else {
+        lines[lines.len().saturating_sub(NOTIFY_TAIL_LINES)..].join("\n")
+    };
+
+    let params = NotificationParams {
+        title,
+        body,
+        icon: None,
+    };
+    if let Err(e) = send_notification_custom(app.clone(), params) {
+        println!("Failed to send notification: {}", e);
+    }
+}
+
+// 读取输出的末尾部分，截断时从下一行开始
+// Windows 控制台程序的输出可能是 GBK 等本地编码，按文本预览的规则检测编码
+fn read_tail(log: &Path) -> String {
+    let Ok(mut file) = File::open(log) else {
+        return String::new();
+    };
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let start = len.saturating_sub(OUTPUT_TAIL_BYTES);
+    let mut bytes = Vec::new();
+    if file.seek(SeekFrom::Start(start)).is_err() || file.read_to_end(&mut bytes).is_err() {
+        return String::new();
+    }
+
+    let text = decode_preview(&bytes, true, usize::MAX);
+    let text = match text.find('\n') {
+        Some(index) if start > 0 => &text[index + 1..],
+        _ => &text[..],
+    };
+    text.trim_end().to_string()
+}
+
+// 清空输出的临时文件
+fn truncate_log(log: &Path) {
+    if fs::metadata(log).is_ok_and(|metadata| metadata.len() > 0) {
+        let _ = OpenOptions::new().write(true).open(log).and_then(|file| file.set_len(0));
+    }
+}
+
+// 输出超过上限时只保留末尾部分
+// 读取末尾和清空之间子进程写入的输出会丢失，写回的末尾部分以追加方式写入，不会覆盖之后的输出
+fn trim_log(log: &Path) {
+    let len = match fs::metadata(log) {
+        Ok(metadata) if metadata.len() > MAX_OUTPUT_LOG_BYTES => metadata.len(),
+        _ => return,
+    };
+    let Ok(mut file) = OpenOptions::new().read(true).append(true).open(log) else {
+        return;
+    };
+    let mut tail = Vec::new();
+    if file.seek(SeekFrom::Start(len - OUTPUT_TAIL_BYTES)).is_err() || file.read_to_end(&mut tail).is_err() {
+        return;
+    }
+    if file.set_len(0).is_ok() {
+        let _ = file.write_all(&tail);
+    }
+}
+
+// 删除之前运行时留下的输出临时文件，启动时调用
+// 启动器退出后仍在运行的子进程会留下文件，Windows 上仍被占用的文件删除失败时忽略
+pub fn remove_stale_logs() {
+    let Ok(entries) = fs::read_dir(std::env::temp_dir()) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with(LOG_PREFIX) && name.ends_with(".log") {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+fn remove_log(log: Option<&Path>) {
+    if let Some(log) = log {
+        let _ = fs::remove_file(log);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trim_log_keeps_only_the_tail() {
+        let temp = tempfile::tempdir().unwrap();
+        let log = temp.path().join("output.log");
+        let mut output: Vec<u8> = (0..MAX_OUTPUT_LOG_BYTES).map(|i| b'a' + (i % 26) as u8).collect();
+        output.extend_from_slice(b"\nlast line\n");
+        fs::write(&log, &output).unwrap();
+
+        trim_log(&log);
+        let trimmed = fs::read(&log).unwrap();
+        assert_eq!(trimmed.len() as u64, OUTPUT_TAIL_BYTES);
+        assert!(output.ends_with(&trimmed));
+        assert_eq!(read_tail(&log).lines().last(), Some("last line"));
+
+        // 未超过上限时不修改
+        trim_log(&log);
+        assert_eq!(fs::read(&log).unwrap(), trimmed);
+    }
+
+    #[test]
+    fn truncate_log_empties_the_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let log = temp.path().join("output.log");
+        fs::write(&log, "error: something\n").unwrap();
+        truncate_log(&log);
+        assert_eq!(fs::metadata(&log).unwrap().len(), 0);
+    }
+}
@@ -0,0 +1,159 @@
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use crate::models::LaunchOptions;
+use crate::utils::terminal_command;
+
+// 没有设置任何启动选项时，仍交给系统默认程序打开
+pub fn has_options(options: &LaunchOptions) -> bool {
+    options.launch_args.as_deref().is_some_and(|a| !a.trim().is_empty())
+        || options.working_dir.as_deref().is_some_and(|d| !d.trim().is_empty())
+        || !options.env.is_empty()
+        || options.run_in_terminal
+}
+
+// 按 POSIX shell 的规则拆分参数
+// 单引号内原样保留，双引号内只处理 \" \\ \$ \` 转义，引号外的反斜杠转义下一个字符
+pub fn split_args(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated quote in arguments".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err("Unterminated quote in arguments".to_string()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated quote in arguments".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                in_arg = true;
+                if let Some(c) = chars.next() {
+                    current.push(c);
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+// 追加用户填写的参数
+// Windows 的命令行由程序自己解析，原样传递；其他系统按 shell 的规则拆分
+fn append_args(command: &mut Command, args: &str) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        command.raw_arg(args);
+    }
+    #[cfg(not(target_os = "windows"))]
+    command.args(split_args(args)?);
+    Ok(())
+}
+
+// 根据启动选项构造命令，直接执行程序而不是交给系统默认程序打开
+pub fn build_command(path: &Path, options: &LaunchOptions) -> Result<Command, String> {
+    let args = options.launch_args.as_deref().filter(|a| !a.trim().is_empty());
+
+    let mut command = if options.run_in_terminal {
+        terminal_command(&[path.to_string_lossy().to_string()])?
+    } else {
+        Command::new(path)
+    };
+    if let Some(args) = args {
+        append_args(&mut command, args)?;
+    }
+
+    // 未指定工作目录时使用程序所在的目录
+    let working_dir = options
+        .working_dir
+        .as_deref()
+        .filter(|dir| !dir.trim().is_empty())
+        .map(PathBuf::from)
+        .or_else(|| path.parent().map(Path::to_path_buf));
+    if let Some(dir) = working_dir {
+        if !dir.is_dir() {
+            return Err(format!("Working directory does not exist: {}", dir.display()));
+        }
+        command.current_dir(dir);
+    }
+
+    command.envs(&options.env);
+    Ok(command)
+}
+
+// 按启动选项启动程序
+pub fn spawn(path: &Path, options: &LaunchOptions) -> Result<Child, String> {
+    build_command(path, options)?
+        .spawn()
+        .map_err(|e| format!("Failed to launch {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_args_handles_quotes() {
+        assert_eq!(split_args("  -a  --b=1 ").unwrap(), ["-a", "--b=1"]);
+        assert_eq!(split_args("'a b' \"c d\" e'f g'h").unwrap(), ["a b", "c d", "ef gh"]);
+        // 单引号内不处理转义
+        assert_eq!(split_args("'a\\b' '$HOME'").unwrap(), ["a\\b", "$HOME"]);
+        // 空引号也是一个参数
+        assert_eq!(split_args("'' \"\"").unwrap(), ["", ""]);
+        assert!(split_args("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn split_args_handles_escapes() {
+        assert_eq!(split_args("a\\ b c").unwrap(), ["a b", "c"]);
+        assert_eq!(split_args("\\'x").unwrap(), ["'x"]);
+        // 双引号内只有 \" \\ \$ \` 是转义，其他反斜杠保留
+        assert_eq!(split_args("\"a\\\"b\\\\c\\$d\\ne\"").unwrap(), ["a\"b\\c$d\\ne"]);
+        // 末尾的反斜杠忽略
+        assert_eq!(split_args("a\\").unwrap(), ["a"]);
+    }
+
+    #[test]
+    fn split_args_rejects_unterminated_quotes() {
+        assert!(split_args("'abc").is_err());
+        assert!(split_args("a \"bc").is_err());
+        assert!(split_args("\"abc\\").is_err());
+    }
+}
@@ -2,6 +2,26 @@ pub mod models;
 pub mod db;
 pub mod utils;
 pub mod icon;
+pub mod icon_cache;
+pub mod thumbnail;
+pub mod mime_type;
+pub mod text_preview;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub mod icon_theme;
+pub mod frecency;
+pub mod search;
+pub mod pinyin_index;
+pub mod desktop_entry;
+pub mod shell_link;
+pub mod url_item;
+pub mod shell_command;
+pub mod launcher;
+pub mod launch_tracker;
+pub mod health;
+pub mod file_watcher;
+pub mod folder_category;
+pub mod journal;
+pub mod protocol;
 pub mod commands;
 
 use tauri::{
@@ -28,16 +48,47 @@ pub fn run() {
                 let _ = window.set_focus();
             }
         }))
+        .register_asynchronous_uri_scheme_protocol(protocol::SCHEME, |ctx, request, responder| {
+            // 在后台线程读取数据库，避免阻塞主线程
+            let app = ctx.app_handle().clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                responder.respond(protocol::handle(&app, request));
+            });
+        })
         .invoke_handler(tauri::generate_handler![
             commands::file::save_files_to_db,
             commands::file::load_files_from_db,
+            commands::file::add_file,
+            commands::file::update_file,
+            commands::file::delete_files,
+            commands::file::move_files_to_category,
+            commands::file::reorder_files,
             commands::file::get_file_info,
+            commands::file::get_url_info,
+            commands::file::get_command_info,
             commands::file::open_path,
             commands::file::open_file_location,
+            commands::launch::launch_item,
+            commands::launch::launch_with_options,
+            commands::launch::list_running_launches,
+            commands::launch::kill_launch,
+            commands::launch::get_launch_history,
+            commands::health::check_item_health,
+            commands::health::relocate_item,
+            commands::trash::list_trash,
+            commands::trash::restore_items,
+            commands::trash::restore_categories,
+            commands::trash::empty_trash,
+            commands::trash::purge_trash,
+            commands::journal::undo,
+            commands::journal::redo,
+            commands::search::search_items,
             commands::category::save_categories_to_db,
             commands::category::load_categories_from_db,
             commands::category::rename_category_in_db,
             commands::category::delete_category_from_db,
+            commands::category::set_category_folder,
+            commands::category::sync_folder_category,
             commands::notification::send_notification_custom,
             commands::app::get_app_version,
             commands::app::set_skip_taskbar,
@@ -53,6 +104,39 @@ pub fn run() {
                 )?;
             }
 
+            // 打开数据库并注册为全局共享状态，所有命令复用同一个连接
+            let conn = db::open_database(app.handle())?;
+            app.manage(db::DbState::new(conn));
+
+            // 跟踪由启动器启动的子进程，清理上次运行留下的输出临时文件
+            launch_tracker::remove_stale_logs();
+            app.manage(launch_tracker::LaunchTracker::default());
+
+            // 监视启动项所在的目录，文件被重命名、修改或删除时更新数据库
+            let watcher = file_watcher::FileWatcher::start(app.handle());
+            watcher.sync(&*app.state::<db::DbState>().conn()?);
+            app.manage(watcher);
+
+            // 在后台同步文件夹分类
+            let handle = app.handle().clone();
+            std::thread::spawn(move || folder_category::sync_all(&handle));
+
+            // 在后台为升级前添加的项目补全拼音索引和 MIME 类型
+            let handle = app.handle().clone();
+            std::thread::spawn(move || {
+                let db = handle.state::<db::DbState>();
+                match db.conn().and_then(|conn| pinyin_index::backfill(&conn).map_err(|e| e.to_string())) {
+                    Ok(count) if count > 0 => println!("Rebuilt pinyin index for {} files", count),
+                    Ok(_) => {}
+                    Err(e) => println!("Failed to backfill pinyin index: {}", e),
+                }
+                match mime_type::backfill(&db) {
+                    Ok(count) if count > 0 => println!("Detected mime types for {} files", count),
+                    Ok(_) => {}
+                    Err(e) => println!("Failed to backfill mime types: {}", e),
+                }
+            });
+
             // 创建托盘菜单
             let show_i = MenuItem::with_id(app, "show", "显示主窗口", true, None::<&str>)?;
             let quit_i = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
@@ -0,0 +1,263 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use crate::db::DbState;
+
+// 根据文件内容判断 MIME 类型：先匹配魔数，再判断是否为文本，最后参考文件名
+
+// 用于判断类型的文件头长度
+const SNIFF_LEN: usize = 8 * 1024;
+
+pub const DIRECTORY: &str = "inode/directory";
+pub const OCTET_STREAM: &str = "application/octet-stream";
+pub const TEXT_PLAIN: &str = "text/plain";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detected {
+    pub mime: String,
+    // 内容为文本，可以作为预览读取
+    pub is_text: bool,
+}
+
+// 没有扩展名时常见的文件名
+fn mime_from_file_name(name: &str) -> Option<&'static str> {
+    let mime = match name {
+        "Dockerfile" | "Containerfile" => "text/x-dockerfile",
+        "Makefile" | "makefile" | "GNUmakefile" => "text/x-makefile",
+        "CMakeLists.txt" => "text/x-cmake",
+        "Jenkinsfile" => "text/x-groovy",
+        "Vagrantfile" | "Gemfile" | "Rakefile" => "application/x-ruby",
+        ".bashrc" | ".bash_profile" | ".profile" | ".zshrc" => "application/x-shellscript",
+        ".gitignore" | ".gitattributes" | ".editorconfig" | ".env" => TEXT_PLAIN,
+        _ => return None,
+    };
+    Some(mime)
+}
+
+// 按扩展名判断文本文件的具体类型
+fn mime_from_extension(ext: &str) -> Option<&'static str> {
+    let mime = match ext {
+        "txt" | "log" | "text" => TEXT_PLAIN,
+        "md" | "markdown" => "text/markdown",
+        "json" => "application/json",
+        "js" | "mjs" | "cjs" => "text/javascript",
+        "ts" => "text/x-typescript",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "yml" | "yaml" => "application/yaml",
+        "toml" => "application/toml",
+        "ini" | "conf" | "cfg" | "properties" => "text/x-ini",
+        "csv" => "text/csv",
+        "sql" => "application/sql",
+        "py" => "text/x-python",
+        "rs" => "text/x-rust",
+        "go" => "text/x-go",
+        "c" | "h" => "text/x-c",
+        "cpp" | "cc" | "hpp" => "text/x-c++",
+        "java" => "text/x-java",
+        "sh" | "bash" | "zsh" => "application/x-shellscript",
+        "bat" | "cmd" => "application/x-bat",
+        "ps1" => "text/x-powershell",
+        "vue" => "text/x-vue",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+// 根据 #! 行判断脚本类型，例如 #!/usr/bin/env python3
+fn mime_from_shebang(head: &[u8]) -> Option<&'static str> {
+    let line = head.strip_prefix(b"#!")?;
+    let end = line.iter().position(|&b| b == b'\n').unwrap_or(line.len());
+    let line = String::from_utf8_lossy(&line[..end]);
+    let mut parts = line.split_whitespace();
+    let mut interpreter = parts.next()?.rsplit('/').next()?.to_string();
+    if interpreter == "env" {
+        interpreter = parts.find(|p| !p.starts_with('-'))?.to_string();
+    }
+
+    let mime = match interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+        "sh" | "bash" | "zsh" | "dash" | "ksh" | "fish" => "application/x-shellscript",
+        "python" => "text/x-python",
+        "node" | "deno" | "bun" => "text/javascript",
+        "ruby" => "application/x-ruby",
+        "perl" => "application/x-perl",
+        "php" => "application/x-php",
+        "lua" => "text/x-lua",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+// 判断内容是否为文本：带 BOM、合法的 UTF-8，或没有控制字符的旧编码文本（如 GBK）
+pub fn looks_like_text(head: &[u8]) -> bool {
+    if head.starts_with(&[0xEF, 0xBB, 0xBF]) || head.starts_with(&[0xFF, 0xFE]) || head.starts_with(&[0xFE, 0xFF]) {
+        return true;
+    }
+    if head.contains(&0) {
+        return false;
+    }
+
+    match std::str::from_utf8(head) {
+        Ok(_) => return true,
+        // 截断在多字节字符中间时，前面的部分合法也算作 UTF-8
+        Err(e) if e.error_len().is_none() && e.valid_up_to() + 4 > head.len() => return true,
+        Err(_) => {}
+    }
+
+    // 非 UTF-8 时统计控制字符，少量的控制字符可以容忍
+    let control = head
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\n' | b'\r' | b'\t' | 0x0C | 0x1B))
+        .count();
+    control * 100 <= head.len()
+}
+
+// 根据文件头内容和文件名判断类型
+pub fn detect_bytes(head: &[u8], file_name: &str) -> Detected {
+    let ext = Path::new(file_name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    // 魔数匹配，infer 也会把 #! 脚本识别为 text/x-shellscript，这类文本类型交给后面细分
+    let sniffed = infer::get(head).map(|kind| kind.mime_type());
+    if let Some(mime) = sniffed.filter(|mime| !mime.starts_with("text/")) {
+        return Detected {
+            mime: mime.to_string(),
+            is_text: false,
+        };
+    }
+
+    if head.is_empty() || looks_like_text(head) {
+        let mime = mime_from_shebang(head)
+            .or_else(|| mime_from_file_name(file_name))
+            .or_else(|| mime_from_extension(&ext))
+            .or(sniffed)
+            .unwrap_or(TEXT_PLAIN);
+        return Detected {
+            mime: mime.to_string(),
+            is_text: true,
+        };
+    }
+
+    Detected {
+        mime: OCTET_STREAM.to_string(),
+        is_text: false,
+    }
+}
+
+// 读取文件头判断类型
+pub fn detect(path: &Path) -> Result<Detected, String> {
+    if path.is_dir() {
+        return Ok(Detected {
+            mime: DIRECTORY.to_string(),
+            is_text: false,
+        });
+    }
+
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    File::open(path)
+        .and_then(|file| file.take(SNIFF_LEN as u64).read_to_end(&mut head))
+        .map_err(|e| e.to_string())?;
+
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(detect_bytes(&head, &name))
+}
+
+// 为没有 MIME 类型的文件启动项补全类型，升级前添加的项目在启动后由后台线程调用
+// 读取文件头期间不持有数据库连接，目标不存在的项目保持为空，下次启动时再检查
+pub fn backfill(db: &DbState) -> Result<usize, String> {
+    let rows: Vec<(String, String)> = {
+        let conn = db.conn()?;
+        let mut stmt = conn
+            .prepare_cached("SELECT id, path FROM files WHERE kind = 'file' AND mime IS NULL AND missing = 0")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+
+    let mut updated = 0;
+    for (id, path) in rows {
+        let Ok(detected) = detect(Path::new(&path)) else {
+            continue;
+        };
+        let conn = db.conn()?;
+        updated += conn
+            .prepare_cached("UPDATE files SET mime = ? WHERE id = ? AND mime IS NULL")
+            .and_then(|mut stmt| stmt.execute([&detected.mime, &id]))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+    use crate::db::init_database;
+
+    #[test]
+    fn detects_well_known_file_names() {
+        let detected = detect_bytes(b"FROM rust:1.77\nRUN cargo build\n", "Dockerfile");
+        assert_eq!(detected.mime, "text/x-dockerfile");
+        assert!(detected.is_text);
+        assert_eq!(detect_bytes(b"all:\n\tcc main.c\n", "Makefile").mime, "text/x-makefile");
+    }
+
+    #[test]
+    fn detects_extensionless_scripts_by_shebang() {
+        assert_eq!(detect_bytes(b"#!/usr/bin/env python3\nprint(1)\n", "deploy").mime, "text/x-python");
+        assert_eq!(detect_bytes(b"#!/bin/bash\necho hi\n", "run").mime, "application/x-shellscript");
+        assert_eq!(detect_bytes(b"#!/usr/bin/env -S node --no-warnings\n", "serve").mime, "text/javascript");
+        // #! 行优先于扩展名
+        assert_eq!(detect_bytes(b"#!/usr/bin/perl\n", "tool.txt").mime, "application/x-perl");
+    }
+
+    #[test]
+    fn detects_config_files_by_extension() {
+        let detected = detect_bytes(b"server {\n    listen 80;\n}\n", "nginx.conf");
+        assert_eq!(detected.mime, "text/x-ini");
+        assert!(detected.is_text);
+        assert_eq!(detect_bytes(b"", "empty.conf").mime, "text/x-ini");
+        assert_eq!(detect_bytes(b"plain", "notes").mime, TEXT_PLAIN);
+    }
+
+    #[test]
+    fn detects_binary_content() {
+        let png = detect_bytes(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", "icon.conf");
+        assert_eq!(png.mime, "image/png");
+        assert!(!png.is_text);
+        assert_eq!(detect_bytes(&[0, 1, 2, 3, 0xFF, 0], "data").mime, OCTET_STREAM);
+    }
+
+    #[test]
+    fn backfill_fills_missing_mime_types() {
+        let temp = tempfile::tempdir().unwrap();
+        let script = temp.path().join("deploy");
+        std::fs::write(&script, "#!/bin/sh\necho deploy\n").unwrap();
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        init_database(&mut conn).unwrap();
+        conn.execute(
+            "INSERT INTO files (id, name, display_name, path) VALUES ('1', 'deploy', '', ?), ('2', 'gone', '', '/nonexistent/gone')",
+            [script.to_string_lossy()],
+        )
+        .unwrap();
+        let db = DbState::new(conn);
+
+        assert_eq!(backfill(&db).unwrap(), 1);
+        let mime = |id: &str| -> Option<String> {
+            db.conn().unwrap().query_row("SELECT mime FROM files WHERE id = ?", [id], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(mime("1").as_deref(), Some("application/x-shellscript"));
+        assert_eq!(mime("2"), None);
+        assert_eq!(backfill(&db).unwrap(), 0);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
 
 // 文件信息结构体
@@ -9,11 +10,91 @@ pub struct FileInfo {
     pub path: String,
     pub size: u64,
     pub r#type: String,
+    // 启动项类型，老数据没有该字段时为文件
+    #[serde(default)]
+    pub kind: ItemKind,
     pub icon: String,
     pub content: Option<String>,
     pub category: Option<String>,
     pub open_count: Option<u64>,
     pub created_at: Option<i64>,
+    pub sort_order: Option<i64>,
+    pub frecency: Option<f64>,
+    // 显示名称的拼音索引，格式见 pinyin_index
+    pub pinyin: Option<String>,
+    // 根据文件内容判断的 MIME 类型，例如 text/x-python、inode/directory
+    pub mime: Option<String>,
+    // 启动参数，按命令行的写法保存，例如 --profile "Work Dir"
+    pub launch_args: Option<String>,
+    // 启动时的工作目录，为空时使用程序所在目录
+    pub working_dir: Option<String>,
+    // 启动时追加或覆盖的环境变量
+    pub env: Option<BTreeMap<String, String>>,
+    // 是否在终端中运行
+    pub run_in_terminal: Option<bool>,
+    // 命令启动项使用的 shell，为空时使用系统默认的 shell
+    pub shell: Option<String>,
+    // 命令启动项在终端中运行结束后是否保持终端打开
+    pub keep_open: Option<bool>,
+    // 文件监视发现目标已被删除或移出监视范围
+    pub missing: Option<bool>,
+    // 移到回收站的时间，未删除时为空
+    pub deleted_at: Option<i64>,
+}
+
+// 启动项类型：本地文件、交给系统处理的 URL（https:、mailto:、ssh: 等），或由 shell 执行的命令
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    #[default]
+    File,
+    Url,
+    Command,
+}
+
+impl ItemKind {
+    // files.kind 列中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ItemKind::File => "file",
+            ItemKind::Url => "url",
+            ItemKind::Command => "command",
+        }
+    }
+
+    // 无法识别的值按文件处理
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "url" => ItemKind::Url,
+            "command" => ItemKind::Command,
+            _ => ItemKind::File,
+        }
+    }
+}
+
+// 直接启动程序时使用的选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LaunchOptions {
+    pub launch_args: Option<String>,
+    pub working_dir: Option<String>,
+    pub env: BTreeMap<String, String>,
+    pub run_in_terminal: bool,
+}
+
+// 文件列表的排序方式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileSort {
+    // 按使用频率和最近使用时间综合排序
+    #[default]
+    Frecency,
+    OpenCount,
+    Name,
+    CreatedAt,
+    Size,
+    // 按分类内的手动排序
+    Manual,
 }
 
 // 分类结构体
@@ -24,4 +105,251 @@ pub struct Category {
     pub name: String,
     pub icon: Option<String>,
     pub sort_order: i32,
+    // 绑定的文件夹，分类中的项目与文件夹内容保持同步
+    #[serde(default)]
+    pub folder: Option<WatchedFolder>,
+    // 移到回收站的时间，未删除时为空
+    #[serde(default)]
+    pub deleted_at: Option<i64>,
+}
+
+// 文件夹分类的设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedFolder {
+    pub dir: String,
+    // 相对于文件夹的路径匹配规则，例如 *.exe、tools/**，为空时包含全部
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    // 枚举的层数，1 为只包含文件夹中的直接内容
+    #[serde(default = "default_folder_depth")]
+    pub depth: u32,
+}
+
+fn default_folder_depth() -> u32 {
+    1
+}
+
+// 同步文件夹分类的结果
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FolderSyncSummary {
+    pub category: String,
+    pub added: usize,
+    pub removed: usize,
+    // 无法读取文件信息而跳过的数量
+    pub failed: usize,
+}
+
+// 删除分类时分类中项目和子分类的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CategoryDeleteMode {
+    // 项目移到上级分类，没有上级分类时移到 main，子分类改挂到上级分类
+    #[default]
+    MoveToParent,
+    // 项目移到 main，子分类改挂到上级分类
+    MoveToMain,
+    // 连同子分类一起删除，项目永久删除
+    Cascade,
+    // 连同子分类一起删除，项目移到回收站
+    Trash,
+}
+
+// 删除分类的结果
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CategoryDeleteSummary {
+    // 被删除的分类，包括级联删除的子分类
+    pub deleted_categories: Vec<String>,
+    // 项目移入的分类，只在移动模式下有值
+    pub target_category: Option<String>,
+    pub moved_items: usize,
+    // 改挂到上级分类的子分类数量
+    pub reparented_categories: usize,
+    pub deleted_items: usize,
+    pub trashed_items: usize,
+}
+
+// 启动记录结构体
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LaunchRecord {
+    pub id: i64,
+    pub file_id: String,
+    pub display_name: Option<String>,
+    pub path: Option<String>,
+    pub launched_at: i64,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+// 由启动器启动、仍在运行的进程
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunningLaunch {
+    pub launch_id: u64,
+    pub file_id: String,
+    pub display_name: Option<String>,
+    pub path: String,
+    pub pid: u32,
+    pub started_at: i64,
+    // 启动历史中对应记录的 ID
+    pub history_id: i64,
+}
+
+// 进程启动后很快以非零状态退出时发送给前端的事件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchFailure {
+    pub launch_id: u64,
+    pub file_id: String,
+    pub display_name: Option<String>,
+    pub path: String,
+    pub pid: u32,
+    // 被信号结束时没有退出码
+    pub exit_code: Option<i32>,
+    pub error: String,
+    // 标准错误输出的末尾部分
+    pub stderr_tail: String,
+    pub runtime_ms: u64,
+}
+
+// 搜索结果
+// highlights 为 matched_field（或 snippet）中命中部分的字符区间 [start, end)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+    pub path: String,
+    pub category: String,
+    pub score: i64,
+    pub matched_field: String,
+    pub highlights: Vec<(usize, usize)>,
+    pub snippet: Option<String>,
+}
+
+// 启动项目标的问题
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthIssue {
+    // 目标不存在
+    Missing,
+    // 目标存在但没有权限读取
+    Unreadable,
+    // 快捷方式或桌面启动器指向的程序不存在
+    BrokenShortcut,
+}
+
+// 失效的启动项，candidates 为查找到的可能的新位置，按可信程度排序
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ItemHealth {
+    pub id: String,
+    pub display_name: String,
+    pub path: String,
+    pub issue: HealthIssue,
+    pub detail: Option<String>,
+    pub candidates: Vec<String>,
+}
+
+// 文件监视发现的变化类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    // 目标被重命名或移动到监视的目录中，path 为新路径
+    Renamed,
+    // 目标内容被修改，或缺失的目标重新出现
+    Modified,
+    // 目标被删除或移出监视的目录，启动项被标记为缺失
+    Deleted,
+    // 文件夹分类中出现了新的匹配项目
+    Added,
+    // 文件夹分类中不再匹配的项目被移除
+    Removed,
+}
+
+// 文件监视更新的启动项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChange {
+    pub id: String,
+    pub path: String,
+    pub kind: FileChangeKind,
+}
+
+// 回收站中的项目和分类，按删除时间从新到旧排列
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrashContents {
+    pub items: Vec<FileInfo>,
+    pub categories: Vec<Category>,
+}
+
+// 恢复或清除回收站的结果
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TrashSummary {
+    pub items: usize,
+    pub categories: usize,
+}
+
+// 可以撤销的编辑操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EditAction {
+    AddItem,
+    UpdateItem,
+    ReplaceItems,
+    DeleteItems,
+    MoveItems,
+    ReorderItems,
+    RelocateItem,
+    RestoreItems,
+    SaveCategories,
+    RenameCategory,
+    DeleteCategory,
+    SetCategoryFolder,
+    RestoreCategories,
+}
+
+impl EditAction {
+    // journal.action 列中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EditAction::AddItem => "add_item",
+            EditAction::UpdateItem => "update_item",
+            EditAction::ReplaceItems => "replace_items",
+            EditAction::DeleteItems => "delete_items",
+            EditAction::MoveItems => "move_items",
+            EditAction::ReorderItems => "reorder_items",
+            EditAction::RelocateItem => "relocate_item",
+            EditAction::RestoreItems => "restore_items",
+            EditAction::SaveCategories => "save_categories",
+            EditAction::RenameCategory => "rename_category",
+            EditAction::DeleteCategory => "delete_category",
+            EditAction::SetCategoryFolder => "set_category_folder",
+            EditAction::RestoreCategories => "restore_categories",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        Some(match value {
+            "add_item" => EditAction::AddItem,
+            "update_item" => EditAction::UpdateItem,
+            "replace_items" => EditAction::ReplaceItems,
+            "delete_items" => EditAction::DeleteItems,
+            "move_items" => EditAction::MoveItems,
+            "reorder_items" => EditAction::ReorderItems,
+            "relocate_item" => EditAction::RelocateItem,
+            "restore_items" => EditAction::RestoreItems,
+            "save_categories" => EditAction::SaveCategories,
+            "rename_category" => EditAction::RenameCategory,
+            "delete_category" => EditAction::DeleteCategory,
+            "set_category_folder" => EditAction::SetCategoryFolder,
+            "restore_categories" => EditAction::RestoreCategories,
+            _ => return None,
+        })
+    }
+}
+
+// 撤销或重做的编辑记录
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: i64,
+    pub action: EditAction,
+    pub created_at: i64,
 }
@@ -0,0 +1,177 @@
+use pinyin::{ToPinyin, ToPinyinMulti};
+use rusqlite::Connection;
+
+// 拼音索引的存储格式：生成索引时的名称，换行，再是与名称逐字符对齐的词元，词元之间用空格分隔
+// 汉字的词元是它的所有读音（多音字用 | 分隔，常用读音在前），其余字符的词元是小写后的字符本身
+// 例如 "重庆 App" 存储为 "重庆 App\nzhong|chong qing  a p p"
+// 空白字符的词元为空，词元中不会出现换行，因此以最后一个换行分隔名称和词元
+const SOURCE_SEPARATOR: char = '\n';
+const TOKEN_SEPARATOR: char = ' ';
+const READING_SEPARATOR: char = '|';
+
+// 单个字符的所有候选读音
+fn char_readings(c: char) -> Vec<String> {
+    if let Some(multi) = c.to_pinyin_multi() {
+        let mut readings: Vec<String> = Vec::new();
+        for p in multi {
+            let plain = p.plain().to_string();
+            if !readings.contains(&plain) {
+                readings.push(plain);
+            }
+        }
+        if !readings.is_empty() {
+            return readings;
+        }
+    }
+
+    if let Some(p) = c.to_pinyin() {
+        return vec![p.plain().to_string()];
+    }
+
+    // 空白字符用空词元占位，保证与原字符串对齐
+    if c.is_whitespace() {
+        return vec![String::new()];
+    }
+    vec![c.to_lowercase().collect()]
+}
+
+// 是否包含汉字
+pub fn contains_han(text: &str) -> bool {
+    text.chars().any(|c| c.to_pinyin().is_some())
+}
+
+// 为名称生成拼音索引，不含汉字时返回 None
+pub fn build(text: &str) -> Option<String> {
+    if !contains_han(text) {
+        return None;
+    }
+
+    let tokens: Vec<String> = text
+        .chars()
+        .map(|c| {
+            char_readings(c)
+                .join(&READING_SEPARATOR.to_string())
+        })
+        .collect();
+    Some(format!("{}{}{}", text, SOURCE_SEPARATOR, tokens.join(&TOKEN_SEPARATOR.to_string())))
+}
+
+// 存储的索引是否由当前名称生成，不含汉字的名称没有索引
+pub fn is_current(text: &str, index: Option<&str>) -> bool {
+    match index.and_then(|index| index.rsplit_once(SOURCE_SEPARATOR)) {
+        Some((source, _)) => source == text,
+        None => index.is_none() && !contains_han(text),
+    }
+}
+
+// 重新生成缺少或过期的拼音索引，返回更新的数量
+// 升级前添加的项目和旧格式的索引在启动后由后台线程补全
+pub fn backfill(conn: &Connection) -> rusqlite::Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let rows: Vec<(String, String, String, Option<String>)> = {
+        let mut stmt = tx.prepare("SELECT id, display_name, name, pinyin FROM files")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    let mut updated = 0;
+    {
+        let mut stmt = tx.prepare("UPDATE files SET pinyin = ? WHERE id = ?")?;
+        for (id, display_name, name, index) in rows {
+            let source = if display_name.is_empty() { name } else { display_name };
+            if !is_current(&source, index.as_deref()) {
+                updated += stmt.execute(rusqlite::params![build(&source), id])?;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(updated)
+}
+
+// 解析存储的拼音索引，返回每个字符的候选读音
+// 索引不是由当前名称生成时重新生成
+pub fn readings(text: &str, index: Option<&str>) -> Option<Vec<Vec<String>>> {
+    if let Some((source, tokens)) = index.and_then(|index| index.rsplit_once(SOURCE_SEPARATOR)) {
+        if source == text {
+            let parsed: Vec<Vec<String>> = tokens
+                .split(TOKEN_SEPARATOR)
+                .map(|token| token.split(READING_SEPARATOR).map(str::to_string).collect())
+                .collect();
+            if parsed.len() == text.chars().count() {
+                return Some(parsed);
+            }
+        }
+    }
+
+    if !contains_han(text) {
+        return None;
+    }
+    Some(text.chars().map(char_readings).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_index_with_source_and_aligned_tokens() {
+        // 信 是多音字，常用读音在前
+        assert_eq!(build("微信").as_deref(), Some("微信\nwei xin|shen"));
+        assert_eq!(build("微信 PC").as_deref(), Some("微信 PC\nwei xin|shen  p c"));
+        assert_eq!(build("WeChat"), None);
+    }
+
+    #[test]
+    fn stored_index_is_used_for_its_own_source() {
+        let index = build("微信").unwrap();
+        assert_eq!(
+            readings("微信", Some(&index)),
+            Some(vec![vec!["wei".to_string()], vec!["xin".to_string(), "shen".to_string()]])
+        );
+    }
+
+    #[test]
+    fn stale_index_is_rebuilt_even_with_same_length() {
+        let index = build("微信").unwrap();
+        assert_eq!(readings("微博", Some(&index)), readings("微博", None));
+        assert_ne!(readings("微博", Some(&index)), readings("微信", None));
+
+        // 旧格式的索引没有记录名称，同样重新生成
+        assert_eq!(readings("微博", Some("wei xin")), readings("微博", None));
+        assert_eq!(readings("WeChat", Some(&index)), None);
+    }
+
+    #[test]
+    fn only_stale_indexes_need_rebuilding() {
+        assert!(is_current("微信", build("微信").as_deref()));
+        assert!(is_current("WeChat", None));
+        assert!(!is_current("微信", None));
+        assert!(!is_current("微信", Some("wei xin")));
+        assert!(!is_current("微博", build("微信").as_deref()));
+        assert!(!is_current("WeChat", build("微信").as_deref()));
+    }
+
+    #[test]
+    fn backfill_rebuilds_missing_and_stale_indexes() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::init_database(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO files (id, name, display_name, path, pinyin) VALUES
+                ('new', '微信.exe', '微信', '/a', NULL),
+                ('old', '微博.exe', '微博', '/b', 'wei bo'),
+                ('renamed', '钉钉.exe', '钉钉', '/c', '微信\nwei xin'),
+                ('ascii', 'code', '', '/d', NULL);",
+        )
+        .unwrap();
+
+        assert_eq!(backfill(&conn).unwrap(), 3);
+        let pinyin = |id: &str| -> Option<String> {
+            conn.query_row("SELECT pinyin FROM files WHERE id = ?", [id], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(pinyin("new"), build("微信"));
+        assert_eq!(pinyin("old"), build("微博"));
+        assert_eq!(pinyin("renamed"), build("钉钉"));
+        assert_eq!(pinyin("ascii"), None);
+        assert_eq!(backfill(&conn).unwrap(), 0);
+    }
+}
@@ -0,0 +1,285 @@
+use std::borrow::Cow;
+use std::path::Path;
+use rusqlite::OptionalExtension;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager, Runtime};
+use crate::db::DbState;
+use crate::icon_cache::{content_hash, load_icon};
+use crate::utils::percent_decode;
+use crate::thumbnail::{cached_thumbnail, file_stamp, is_image_file, snap_size, DEFAULT_THUMBNAIL_SIZE};
+
+// 自定义协议，前端通过 oops://localhost/<kind>/<id> 访问
+// Windows 上 WebView2 使用 http://oops.localhost/<kind>/<id>
+//   icon/<hash>     图标缓存中的图标，内容由哈希决定，可以永久缓存
+//   preview/<id>    文件的预览：图片返回 PNG 缩略图（?size= 指定边长，对齐到支持的档位），文本返回文本内容
+pub const SCHEME: &str = "oops";
+
+// 图标按内容哈希寻址，内容不会变化
+const IMMUTABLE_CACHE: &str = "public, max-age=31536000, immutable";
+// 预览随文件变化，每次使用前通过 ETag 重新验证
+const REVALIDATE_CACHE: &str = "no-cache";
+
+type ProtocolResponse = Response<Cow<'static, [u8]>>;
+
+// 取出请求中的 <kind>/<id>，兼容 oops://icon/<id> 这种把类型写在主机名位置的地址
+fn route(request: &Request<Vec<u8>>) -> Vec<String> {
+    let uri = request.uri();
+    let mut segments = Vec::new();
+    if let Some(host) = uri.host() {
+        if host != "localhost" && host != format!("{}.localhost", SCHEME) {
+            segments.push(host.to_string());
+        }
+    }
+    segments.extend(
+        uri.path()
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(percent_decode),
+    );
+    segments
+}
+
+fn query_param(request: &Request<Vec<u8>>, key: &str) -> Option<String> {
+    request.uri().query()?.split('&').find_map(|pair| {
+        let (k, v) = pair.split_once('=')?;
+        (k == key).then(|| v.to_string())
+    })
+}
+
+fn error_response(status: StatusCode, message: &str) -> ProtocolResponse {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Cow::Owned(message.as_bytes().to_vec()))
+        .unwrap_or_default()
+}
+
+// 带 ETag 的响应，请求的 If-None-Match 与 ETag 一致时返回 304，不再生成内容
+fn cached_response(
+    request: &Request<Vec<u8>>,
+    etag: &str,
+    cache_control: &str,
+    content_type: &str,
+    body: impl FnOnce() -> Result<Vec<u8>, String>,
+) -> Result<ProtocolResponse, String> {
+    let etag = format!("\"{}\"", etag);
+    let not_modified = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|tag| tag.trim() == etag));
+
+    let builder = Response::builder()
+        .header(header::ETAG, &etag)
+        .header(header::CACHE_CONTROL, cache_control);
+    if not_modified {
+        return builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Cow::Borrowed(&[][..]))
+            .map_err(|e| e.to_string());
+    }
+
+    builder
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Cow::Owned(body()?))
+        .map_err(|e| e.to_string())
+}
+
+fn serve_icon(db: &DbState, request: &Request<Vec<u8>>, hash: &str) -> Result<ProtocolResponse, String> {
+    let icon = {
+        let conn = db.conn()?;
+        load_icon(&conn, hash).map_err(|e| e.to_string())?
+    };
+
+    let Some((mime, data)) = icon else {
+        return Ok(error_response(StatusCode::NOT_FOUND, "icon not found"));
+    };
+    cached_response(request, hash, IMMUTABLE_CACHE, &mime, || Ok(data))
+}
+
+fn serve_preview(db: &DbState, request: &Request<Vec<u8>>, id: &str) -> Result<ProtocolResponse, String> {
+    let item: Option<(String, Option<String>)> = {
+        let conn = db.conn()?;
+        let item = conn
+            .prepare_cached("SELECT path, content FROM files WHERE id = ?")
+            .map_err(|e| e.to_string())?
+            .query_row([id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        item
+    };
+    let Some((path, content)) = item else {
+        return Ok(error_response(StatusCode::NOT_FOUND, "item not found"));
+    };
+
+    let path = Path::new(&path);
+    if is_image_file(path) {
+        let Ok((modified, len)) = file_stamp(path) else {
+            return Ok(error_response(StatusCode::NOT_FOUND, "file not found"));
+        };
+        let size = snap_size(
+            query_param(request, "size")
+                .and_then(|v| v.parse::<u32>().ok())
+                .unwrap_or(DEFAULT_THUMBNAIL_SIZE),
+        );
+        // 文件修改时间、大小和缩略图尺寸都不变时，缩略图内容不变
+        let etag = format!("{}-{}-{}", modified, len, size);
+        return cached_response(request, &etag, REVALIDATE_CACHE, "image/png", || {
+            cached_thumbnail(db, path, size)
+        });
+    }
+
+    match content {
+        Some(content) => {
+            let etag = content_hash(content.as_bytes());
+            cached_response(request, &etag, REVALIDATE_CACHE, "text/plain; charset=utf-8", || {
+                Ok(content.into_bytes())
+            })
+        }
+        None => Ok(error_response(StatusCode::NOT_FOUND, "no preview available")),
+    }
+}
+
+fn respond(db: &DbState, request: &Request<Vec<u8>>) -> ProtocolResponse {
+    let segments = route(request);
+    let result = match segments.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["icon", hash] => serve_icon(db, request, hash),
+        ["preview", id] => serve_preview(db, request, id),
+        _ => Ok(error_response(StatusCode::NOT_FOUND, "not found")),
+    };
+
+    result.unwrap_or_else(|e| {
+        println!("Failed to serve {}: {}", request.uri(), e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, &e)
+    })
+}
+
+// 处理自定义协议请求
+pub fn handle<R: Runtime>(app: &AppHandle<R>, request: Request<Vec<u8>>) -> ProtocolResponse {
+    respond(&app.state::<DbState>(), &request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+    use crate::icon_cache::store_icon_bytes;
+
+    fn request(uri: &str, if_none_match: Option<&str>) -> Request<Vec<u8>> {
+        let mut builder = Request::builder().uri(uri);
+        if let Some(etag) = if_none_match {
+            builder = builder.header(header::IF_NONE_MATCH, etag);
+        }
+        builder.body(Vec::new()).unwrap()
+    }
+
+    fn test_db() -> DbState {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::init_database(&mut conn).unwrap();
+        DbState::new(conn)
+    }
+
+    fn header_value(response: &ProtocolResponse, name: header::HeaderName) -> Option<&str> {
+        response.headers().get(name).and_then(|value| value.to_str().ok())
+    }
+
+    #[test]
+    fn route_handles_both_url_forms() {
+        assert_eq!(route(&request("oops://localhost/icon/abc", None)), ["icon", "abc"]);
+        assert_eq!(route(&request("http://oops.localhost/icon/abc", None)), ["icon", "abc"]);
+        assert_eq!(route(&request("oops://icon/abc", None)), ["icon", "abc"]);
+        // 路径片段按百分号编码解码，查询参数不属于路径
+        assert_eq!(
+            route(&request("http://oops.localhost/preview/a%20b%2Fc?size=64", None)),
+            ["preview", "a b/c"]
+        );
+        assert!(route(&request("oops://localhost/", None)).is_empty());
+    }
+
+    #[test]
+    fn query_param_reads_values() {
+        let request = request("oops://localhost/preview/a?x=1&size=64", None);
+        assert_eq!(query_param(&request, "size").as_deref(), Some("64"));
+        assert_eq!(query_param(&request, "missing"), None);
+    }
+
+    #[test]
+    fn cached_response_honors_if_none_match() {
+        let response = cached_response(&request("oops://localhost/", None), "tag", REVALIDATE_CACHE, "text/plain", || {
+            Ok(b"body".to_vec())
+        })
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header_value(&response, header::ETAG), Some("\"tag\""));
+        assert_eq!(header_value(&response, header::CACHE_CONTROL), Some(REVALIDATE_CACHE));
+        assert_eq!(header_value(&response, header::CONTENT_TYPE), Some("text/plain"));
+        assert_eq!(response.body().as_ref(), b"body");
+
+        // 命中时不生成内容
+        let matching = request("oops://localhost/", Some("\"other\", \"tag\""));
+        let response = cached_response(&matching, "tag", REVALIDATE_CACHE, "text/plain", || {
+            panic!("body should not be generated")
+        })
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(header_value(&response, header::ETAG), Some("\"tag\""));
+        assert!(response.body().is_empty());
+
+        let stale = request("oops://localhost/", Some("\"other\""));
+        let response = cached_response(&stale, "tag", REVALIDATE_CACHE, "text/plain", || Ok(Vec::new())).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn respond_serves_icons_and_previews() {
+        let db = test_db();
+        let hash = {
+            let conn = db.conn().unwrap();
+            conn.execute(
+                "INSERT INTO files (id, name, display_name, path, content) VALUES ('a', 'a.txt', 'a', '/nonexistent/a.txt', 'hello')",
+                [],
+            )
+            .unwrap();
+            store_icon_bytes(&conn, "image/png", b"icon").unwrap();
+            content_hash(b"icon")
+        };
+
+        let response = respond(&db, &request(&format!("oops://localhost/icon/{}", hash), None));
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header_value(&response, header::CONTENT_TYPE), Some("image/png"));
+        assert_eq!(header_value(&response, header::CACHE_CONTROL), Some(IMMUTABLE_CACHE));
+        assert_eq!(response.body().as_ref(), b"icon");
+
+        let response = respond(&db, &request("http://oops.localhost/preview/a", None));
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body().as_ref(), b"hello");
+        let etag = header_value(&response, header::ETAG).unwrap().to_string();
+        let response = respond(&db, &request("http://oops.localhost/preview/a", Some(&etag)));
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[test]
+    fn respond_returns_not_found() {
+        let db = test_db();
+        // 图片文件已不存在，或项目没有预览内容
+        db.conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO files (id, name, display_name, path) VALUES
+                    ('img', 'a.png', 'a', '/nonexistent/a.png'),
+                    ('bin', 'a.bin', 'a', '/nonexistent/a.bin');",
+            )
+            .unwrap();
+        for uri in [
+            "oops://localhost/unknown/a",
+            "oops://localhost/icon",
+            "oops://localhost/icon/a/b",
+            "oops://localhost/icon/0000",
+            "oops://localhost/preview/missing",
+            "oops://localhost/preview/img",
+            "oops://localhost/preview/bin",
+        ] {
+            assert_eq!(respond(&db, &request(uri, None)).status(), StatusCode::NOT_FOUND, "{}", uri);
+        }
+    }
+}
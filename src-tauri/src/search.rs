@@ -0,0 +1,390 @@
+// 模糊匹配结果，positions 为命中字符在原字符串中的下标（按字符计）
+#[derive(Debug, Clone)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    pub positions: Vec<usize>,
+}
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_SUBSTRING: i64 = 64;
+const BONUS_CONSECUTIVE: i64 = 12;
+const BONUS_BOUNDARY: i64 = 10;
+const PENALTY_GAP: i64 = 1;
+const MAX_GAP_PENALTY: i64 = 20;
+
+// 统一为小写后逐字符比较，保证下标与原字符串一一对应
+fn normalize_char(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+// 是否为单词起始位置：开头、分隔符之后或驼峰大写处
+fn is_boundary(chars: &[char], index: usize) -> bool {
+    if index == 0 {
+        return true;
+    }
+    let prev = chars[index - 1];
+    let cur = chars[index];
+    matches!(prev, ' ' | '-' | '_' | '.' | '/' | '\\' | '(' | '[')
+        || (prev.is_lowercase() && cur.is_uppercase())
+}
+
+// 在已归一化的字符序列中查找连续子串的起始下标
+fn find_chars(hay: &[char], needle: &[char]) -> Option<usize> {
+    if needle.is_empty() || needle.len() > hay.len() {
+        return None;
+    }
+    hay.windows(needle.len()).position(|w| w == needle)
+}
+
+// 大小写不敏感的连续子串匹配
+pub fn substring_match(query: &str, haystack: &str) -> Option<FuzzyMatch> {
+    let needle: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(normalize_char)
+        .collect();
+    let original: Vec<char> = haystack.chars().collect();
+    let hay: Vec<char> = original.iter().map(|&c| normalize_char(c)).collect();
+    let start = find_chars(&hay, &needle)?;
+
+    let mut score = BONUS_SUBSTRING + needle.len() as i64 * (SCORE_MATCH + BONUS_CONSECUTIVE);
+    if is_boundary(&original, start) {
+        score += BONUS_BOUNDARY * 2;
+    }
+    // 越靠前越好，完全相等额外加分
+    score -= (start as i64).min(MAX_GAP_PENALTY);
+    if needle.len() == hay.len() {
+        score += BONUS_SUBSTRING;
+    }
+
+    Some(FuzzyMatch {
+        score,
+        positions: (start..start + needle.len()).collect(),
+    })
+}
+
+// 对 haystack 做大小写不敏感的模糊子序列匹配
+// 连续子串命中得分最高，其次是在单词起始处命中的子序列
+pub fn fuzzy_match(query: &str, haystack: &str) -> Option<FuzzyMatch> {
+    if let Some(m) = substring_match(query, haystack) {
+        return Some(m);
+    }
+
+    let needle: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(normalize_char)
+        .collect();
+    if needle.is_empty() {
+        return None;
+    }
+
+    let original: Vec<char> = haystack.chars().collect();
+    let hay: Vec<char> = original.iter().map(|&c| normalize_char(c)).collect();
+    if needle.len() > hay.len() {
+        return None;
+    }
+
+    // 子序列匹配：每个查询字符优先选择单词起始处，否则取最近的一处
+    let mut positions = Vec::with_capacity(needle.len());
+    let mut from = 0;
+    for (n, &qc) in needle.iter().enumerate() {
+        let remaining = needle.len() - n - 1;
+        let last_allowed = hay.len() - remaining;
+        let candidates = (from..last_allowed).filter(|&i| hay[i] == qc);
+
+        let mut first = None;
+        let mut chosen = None;
+        for i in candidates {
+            if first.is_none() {
+                first = Some(i);
+                // 紧跟上一个命中字符时直接选择
+                if positions.last().map(|&p| p + 1 == i).unwrap_or(false) {
+                    chosen = Some(i);
+                    break;
+                }
+            }
+            if is_boundary(&original, i) {
+                chosen = Some(i);
+                break;
+            }
+        }
+
+        let index = chosen.or(first)?;
+        positions.push(index);
+        from = index + 1;
+    }
+
+    let mut score = 0;
+    for (k, &pos) in positions.iter().enumerate() {
+        score += SCORE_MATCH;
+        if is_boundary(&original, pos) {
+            score += BONUS_BOUNDARY;
+        }
+        if k > 0 {
+            let gap = (pos - positions[k - 1] - 1) as i64;
+            if gap == 0 {
+                score += BONUS_CONSECUTIVE;
+            } else {
+                score -= (gap * PENALTY_GAP).min(MAX_GAP_PENALTY);
+            }
+        }
+    }
+    score -= (positions[0] as i64).min(MAX_GAP_PENALTY);
+
+    Some(FuzzyMatch { score, positions })
+}
+
+// 将命中下标合并为 [start, end) 区间，便于前端高亮
+pub fn to_ranges(positions: &[usize]) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &pos in positions {
+        match ranges.last_mut() {
+            Some((_, end)) if *end == pos => *end = pos + 1,
+            _ => ranges.push((pos, pos + 1)),
+        }
+    }
+    ranges
+}
+
+// 按拼音匹配名称中连续的一段字符
+// 每个汉字可以用任一读音的全拼或前缀（至少首字母）匹配，也可以直接输入汉字本身，
+// 因此 "wx"、"weixin"、"wxin"、"微x" 都能命中 "微信"
+pub fn pinyin_match(query: &str, text: &str, readings: &[Vec<String>]) -> Option<FuzzyMatch> {
+    let needle: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(normalize_char)
+        .collect();
+    let original: Vec<char> = text.chars().collect();
+    if needle.is_empty() || readings.len() != original.len() {
+        return None;
+    }
+
+    // 从第 ci 个字符、查询的第 qi 个字符开始回溯匹配，返回匹配结束时的字符下标
+    // 同一个 (ci, qi) 无论从哪里到达，能否匹配成功都相同，记录失败过的状态避免重复回溯
+    fn consume(
+        needle: &[char],
+        original: &[char],
+        readings: &[Vec<String>],
+        ci: usize,
+        qi: usize,
+        full_syllables: &mut usize,
+        failed: &mut [bool],
+    ) -> Option<usize> {
+        if qi == needle.len() {
+            return Some(ci);
+        }
+        let state = ci * needle.len() + qi;
+        if ci == original.len() || failed[state] {
+            return None;
+        }
+
+        // 名称中的空白不需要输入
+        if original[ci].is_whitespace() {
+            let end = consume(needle, original, readings, ci + 1, qi, full_syllables, failed);
+            failed[state] = end.is_none();
+            return end;
+        }
+
+        // 直接输入原字符
+        let direct = normalize_char(original[ci]);
+        if direct == needle[qi] {
+            if let Some(end) = consume(needle, original, readings, ci + 1, qi + 1, full_syllables, failed) {
+                return Some(end);
+            }
+        }
+
+        // 使用读音的全拼或前缀，较长的前缀优先
+        // 非汉字的读音就是字符本身，已经在上面尝试过
+        for reading in &readings[ci] {
+            let reading: Vec<char> = reading.chars().collect();
+            if reading == [direct] {
+                continue;
+            }
+            let max_len = reading.len().min(needle.len() - qi);
+            for len in (1..=max_len).rev() {
+                if reading[..len] != needle[qi..qi + len] {
+                    continue;
+                }
+                if let Some(end) = consume(needle, original, readings, ci + 1, qi + len, full_syllables, failed) {
+                    if len == reading.len() {
+                        *full_syllables += 1;
+                    }
+                    return Some(end);
+                }
+            }
+        }
+
+        failed[state] = true;
+        None
+    }
+
+    let mut failed = vec![false; original.len() * needle.len()];
+    for start in 0..original.len() {
+        // 跳过空白处开始的匹配
+        if original[start].is_whitespace() {
+            continue;
+        }
+        let mut full_syllables = 0;
+        if let Some(end) = consume(&needle, &original, readings, start, 0, &mut full_syllables, &mut failed) {
+            let matched = end - start;
+            let mut score = needle.len() as i64 * SCORE_MATCH
+                + matched as i64 * BONUS_CONSECUTIVE
+                + full_syllables as i64 * BONUS_BOUNDARY;
+            if is_boundary(&original, start) {
+                score += BONUS_BOUNDARY * 2;
+            }
+            score -= (start as i64).min(MAX_GAP_PENALTY);
+            if matched == original.len() {
+                score += BONUS_SUBSTRING;
+            }
+            return Some(FuzzyMatch {
+                score,
+                positions: (start..end).collect(),
+            });
+        }
+    }
+
+    None
+}
+
+// 在长文本中截取命中位置附近的片段，返回片段及片段内的高亮区间
+pub fn snippet_around(text: &str, query: &str, context: usize) -> Option<(String, Vec<(usize, usize)>)> {
+    let hay: Vec<char> = text.chars().map(normalize_char).collect();
+    let needle: Vec<char> = query.trim().chars().map(normalize_char).collect();
+    let start = find_chars(&hay, &needle)?;
+    let from = start.saturating_sub(context);
+    let to = (start + needle.len() + context).min(hay.len());
+
+    let snippet: String = text
+        .chars()
+        .skip(from)
+        .take(to - from)
+        .map(|c| if c == '\n' || c == '\r' { ' ' } else { c })
+        .collect();
+    let highlight = (start - from, start - from + needle.len());
+    Some((snippet, vec![highlight]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pinyin_index;
+
+    fn pinyin(query: &str, text: &str) -> Option<Vec<usize>> {
+        let readings = pinyin_index::readings(text, None)?;
+        pinyin_match(query, text, &readings).map(|m| m.positions)
+    }
+
+    fn score(query: &str, haystack: &str) -> i64 {
+        fuzzy_match(query, haystack).unwrap().score
+    }
+
+    #[test]
+    fn substring_match_scores_position_and_boundary() {
+        // 完全相等：64 + 4 × (16 + 12) + 边界 20 + 完全相等 64
+        let exact = substring_match("code", "Code").unwrap();
+        assert_eq!((exact.score, exact.positions), (260, vec![0, 1, 2, 3]));
+        // 单词起始处：64 + 112 + 20 - 位置 3
+        let word = substring_match("CODE", "VS Code").unwrap();
+        assert_eq!((word.score, word.positions), (193, vec![3, 4, 5, 6]));
+        // 单词中间：64 + 112 - 位置 3
+        assert_eq!(substring_match("code", "Barcode").unwrap().score, 173);
+        // 位置扣分有上限
+        let far = format!("{}code", "x".repeat(100));
+        assert_eq!(substring_match("code", &far).unwrap().score, 176 - MAX_GAP_PENALTY);
+
+        // 查询中的空白忽略，下标按字符计
+        assert_eq!(substring_match("c o", "VS Code").unwrap().positions, vec![3, 4]);
+        assert_eq!(substring_match("信", "微信").unwrap().positions, vec![1]);
+        assert!(substring_match("", "Code").is_none());
+        assert!(substring_match("cdoe", "Code").is_none());
+    }
+
+    #[test]
+    fn fuzzy_match_prefers_word_starts() {
+        let m = fuzzy_match("vsc", "Visual Studio Code").unwrap();
+        // 3 × 16 + 3 个边界 × 10 - 两段间隔 6
+        assert_eq!((m.score, m.positions), (66, vec![0, 7, 14]));
+        // 驼峰大写处也是单词起始
+        assert_eq!(fuzzy_match("gc", "GoogleChrome").unwrap().positions, vec![0, 6]);
+        // 紧跟上一个命中字符时不再跳到后面的单词起始处
+        assert_eq!(fuzzy_match("cod", "Cool Desktop").unwrap().positions, vec![0, 1, 5]);
+
+        assert!(fuzzy_match("", "Code").is_none());
+        assert!(fuzzy_match("   ", "Code").is_none());
+        assert!(fuzzy_match("codes", "Code").is_none());
+        assert!(fuzzy_match("ec", "Code").is_none());
+    }
+
+    #[test]
+    fn fuzzy_match_orders_results() {
+        let ranked = ["Code", "Code Runner", "VS Code", "Barcode", "Cool Desktop", "cxoxdxe"];
+        let scores: Vec<i64> = ranked.iter().map(|name| score("code", name)).collect();
+        assert_eq!(scores, [260, 196, 193, 173, 105, 71]);
+    }
+
+    #[test]
+    fn to_ranges_merges_adjacent_positions() {
+        assert_eq!(to_ranges(&[0, 1, 2, 5, 7, 8]), vec![(0, 3), (5, 6), (7, 9)]);
+        assert!(to_ranges(&[]).is_empty());
+    }
+
+    #[test]
+    fn snippet_around_keeps_context() {
+        let (snippet, ranges) = snippet_around("line one\nhello world\nthree", "WORLD", 3).unwrap();
+        assert_eq!(snippet, "lo world th");
+        assert_eq!(ranges, vec![(3, 8)]);
+        assert!(snippet_around("hello", "world", 3).is_none());
+    }
+
+    #[test]
+    fn pinyin_matches_full_initials_and_mixed_input() {
+        for query in ["wx", "weixin", "wxin", "weix", "微x", "微信", "WX", "wei xin"] {
+            assert_eq!(pinyin(query, "微信"), Some(vec![0, 1]), "query {}", query);
+        }
+        assert_eq!(pinyin("xin", "微信"), Some(vec![1]));
+        assert_eq!(pinyin("wxy", "微信"), None);
+        assert_eq!(pinyin("xw", "微信"), None);
+    }
+
+    #[test]
+    fn pinyin_matches_any_reading_of_polyphonic_characters() {
+        for query in ["cq", "zq", "chongqing", "zhongqing"] {
+            assert_eq!(pinyin(query, "重庆"), Some(vec![0, 1]), "query {}", query);
+        }
+        // 乐 读作 yue 或 le
+        assert_eq!(pinyin("qqyy", "QQ音乐"), Some(vec![0, 1, 2, 3]));
+        assert_eq!(pinyin("qqyl", "QQ音乐"), Some(vec![0, 1, 2, 3]));
+        assert_eq!(pinyin("qq音乐", "QQ音乐"), Some(vec![0, 1, 2, 3]));
+        assert_eq!(pinyin("yinyue", "QQ音乐"), Some(vec![2, 3]));
+    }
+
+    #[test]
+    fn pinyin_skips_whitespace_in_names() {
+        assert_eq!(pinyin("wxpc", "微信 PC"), Some(vec![0, 1, 2, 3, 4]));
+        assert_eq!(pinyin("pc", "微信 PC"), Some(vec![3, 4]));
+    }
+
+    #[test]
+    fn pinyin_prefers_full_syllables_and_boundaries() {
+        let readings = pinyin_index::readings("微信", None).unwrap();
+        let full = pinyin_match("weixin", "微信", &readings).unwrap();
+        let initials = pinyin_match("wx", "微信", &readings).unwrap();
+        let tail = pinyin_match("xin", "微信", &readings).unwrap();
+        assert!(full.score > initials.score);
+        assert!(initials.score > tail.score);
+    }
+
+    #[test]
+    fn pinyin_near_miss_on_long_ascii_name_finishes() {
+        // 每个 ASCII 字符的读音就是字符本身，回溯没有去重时需要约 2^n 步
+        let text = format!("中{}", "a".repeat(60));
+        let query = format!("{}b", "a".repeat(59));
+        assert_eq!(pinyin(&query, &text), None);
+
+        let query = "a".repeat(60);
+        assert_eq!(pinyin(&query, &text), Some((1..61).collect()));
+    }
+}
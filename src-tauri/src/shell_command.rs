@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::utils::terminal_command;
+
+// 命令启动项：路径列保存命令文本，由指定的 shell 执行
+
+// 未指定 shell 时使用的 shell：Windows 为 cmd，其他系统为 $SHELL，没有时为 /bin/sh
+pub fn default_shell() -> String {
+    if cfg!(target_os = "windows") {
+        return "cmd".to_string();
+    }
+    env::var("SHELL")
+        .ok()
+        .filter(|shell| !shell.is_empty())
+        .unwrap_or_else(|| "/bin/sh".to_string())
+}
+
+// shell 的名称，例如 /usr/bin/bash 为 bash，pwsh.exe 为 pwsh
+pub fn shell_name(shell: &str) -> String {
+    Path::new(shell)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+// 用单引号包裹，作为 POSIX shell 的一个参数
+fn posix_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+// 执行命令文本的参数，keep_open 时命令结束后留在交互式 shell 中
+// cmd 的参数需要原样传递，由 build_command 单独处理
+pub fn shell_args(shell: &str, script: &str, keep_open: bool) -> Vec<String> {
+    match shell_name(shell).as_str() {
+        "cmd" => vec![if keep_open { "/K" } else { "/C" }.to_string(), script.to_string()],
+        "powershell" | "pwsh" => {
+            let mut args = vec!["-NoProfile".to_string()];
+            if keep_open {
+                args.push("-NoExit".to_string());
+            }
+            args.extend(["-Command".to_string(), script.to_string()]);
+            args
+        }
+        _ => {
+            let script = if keep_open {
+                format!("{}\nexec {}", script, posix_quote(shell))
+            } else {
+                script.to_string()
+            };
+            vec!["-c".to_string(), script]
+        }
+    }
+}
+
+// 展开工作目录开头的 ~
+pub fn expand_home(dir: &str) -> PathBuf {
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"));
+    match (dir.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            PathBuf::from(home).join(rest.trim_start_matches(['/', '\\']))
+        }
+        _ => PathBuf::from(dir),
+    }
+}
+
+// 构造执行命令文本的命令
+// 未指定工作目录时使用用户主目录，in_terminal 时在终端窗口中执行
+pub fn build_command(
+    script: &str,
+    shell: Option<&str>,
+    working_dir: Option<&str>,
+    env: &BTreeMap<String, String>,
+    in_terminal: bool,
+    keep_open: bool,
+) -> Result<Command, String> {
+    let script = script.trim();
+    if script.is_empty() {
+        return Err("command is empty".to_string());
+    }
+
+    let shell = shell
+        .map(str::trim)
+        .filter(|shell| !shell.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(default_shell);
+    let args = shell_args(&shell, script, in_terminal && keep_open);
+
+    let mut command = if in_terminal {
+        let mut terminal_args = vec![shell.clone()];
+        terminal_args.extend(args);
+        terminal_command(&terminal_args)?
+    } else {
+        let mut command = Command::new(&shell);
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            // 不弹出控制台窗口
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+            command.creation_flags(CREATE_NO_WINDOW);
+            // cmd 自己解析命令行，命令文本原样传递
+            if shell_name(&shell) == "cmd" {
+                command.arg(&args[0]).raw_arg(&args[1]);
+            } else {
+                command.args(&args);
+            }
+        }
+        #[cfg(not(target_os = "windows"))]
+        command.args(&args);
+        command
+    };
+
+    let working_dir = working_dir
+        .map(str::trim)
+        .filter(|dir| !dir.is_empty())
+        .map(expand_home)
+        .or_else(|| env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")).map(PathBuf::from));
+    if let Some(dir) = working_dir {
+        if !dir.is_dir() {
+            return Err(format!("Working directory does not exist: {}", dir.display()));
+        }
+        command.current_dir(dir);
+    }
+
+    command.envs(env);
+    Ok(command)
+}
+
+// 命令的默认名称：第一行非空内容，过长时截断
+pub fn command_name(script: &str) -> String {
+    const MAX_CHARS: usize = 40;
+    let line = script.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default();
+    if line.chars().count() > MAX_CHARS {
+        format!("{}…", line.chars().take(MAX_CHARS).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::launcher::split_args;
+
+    #[test]
+    fn shell_args_for_each_shell() {
+        assert_eq!(shell_args("cmd", "dir /b", false), ["/C", "dir /b"]);
+        assert_eq!(shell_args("cmd.exe", "dir", true), ["/K", "dir"]);
+        assert_eq!(shell_args("pwsh.exe", "Get-Date", false), ["-NoProfile", "-Command", "Get-Date"]);
+        assert_eq!(
+            shell_args("powershell", "Get-Date", true),
+            ["-NoProfile", "-NoExit", "-Command", "Get-Date"]
+        );
+        assert_eq!(shell_args("/bin/bash", "ls -la", false), ["-c", "ls -la"]);
+        // 命令结束后由同一个 shell 替换为交互式 shell
+        assert_eq!(
+            shell_args("/opt/my shell/zsh", "make", true),
+            ["-c", "make\nexec '/opt/my shell/zsh'"]
+        );
+    }
+
+    #[test]
+    fn posix_quote_round_trips() {
+        assert_eq!(posix_quote("plain"), "'plain'");
+        assert_eq!(posix_quote("it's"), r"'it'\''s'");
+        for arg in ["", "a b", "it's", "$HOME `id` \"x\"", "back\\slash"] {
+            assert_eq!(split_args(&posix_quote(arg)).unwrap(), [arg]);
+        }
+    }
+
+    #[test]
+    fn expand_home_only_expands_leading_tilde() {
+        let Some(home) = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")) else {
+            return;
+        };
+        let home = PathBuf::from(home);
+        assert_eq!(expand_home("~"), home);
+        assert_eq!(expand_home("~/projects"), home.join("projects"));
+        assert_eq!(expand_home("~\\projects"), home.join("projects"));
+        // ~user 和中间的 ~ 不展开
+        assert_eq!(expand_home("~root/x"), PathBuf::from("~root/x"));
+        assert_eq!(expand_home("/tmp/~"), PathBuf::from("/tmp/~"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn terminal_cmd_line_is_passed_raw() {
+        let args = |command: &Command| {
+            command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect::<Vec<_>>()
+        };
+
+        // 命令文本中的引号和 & 由 cmd 解析，不能被转义
+        let command = build_command("echo \"a b\" & dir", Some("cmd"), None, &BTreeMap::new(), true, true).unwrap();
+        assert_eq!(command.get_program(), "cmd.exe");
+        assert_eq!(args(&command), [r#"/C start "" cmd.exe /K cmd /K echo "a b" & dir"#]);
+
+        // 其他程序的参数含空格时加引号
+        let command = terminal_command(&[r"C:\Program Files\app.exe".to_string(), "--flag".to_string()]).unwrap();
+        assert_eq!(args(&command), [r#"/C start "" cmd.exe /K "C:\Program Files\app.exe" --flag"#]);
+    }
+
+    #[test]
+    fn command_name_uses_first_non_empty_line() {
+        assert_eq!(command_name("\n\n  docker compose up -d  \nsecond"), "docker compose up -d");
+        assert_eq!(command_name("   "), "");
+        let long = "x".repeat(50);
+        assert_eq!(command_name(&long), format!("{}…", "x".repeat(40)));
+        // 按字符而不是字节截断
+        let chinese = "备".repeat(41);
+        assert_eq!(command_name(&chinese), format!("{}…", "备".repeat(40)));
+    }
+}
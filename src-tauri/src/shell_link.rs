@@ -0,0 +1,393 @@
+use std::fs;
+use std::path::Path;
+
+// Windows 快捷方式（.lnk，MS-SHLLINK 格式）中启动器关心的字段
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShellLink {
+    pub local_path: Option<String>,
+    pub env_target: Option<String>,
+    pub description: Option<String>,
+    pub relative_path: Option<String>,
+    pub working_dir: Option<String>,
+    pub arguments: Option<String>,
+    pub icon_location: Option<String>,
+    pub icon_index: i32,
+}
+
+const HEADER_SIZE: u32 = 0x4C;
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+// LinkFlags
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x0000_0001;
+const HAS_LINK_INFO: u32 = 0x0000_0002;
+const HAS_NAME: u32 = 0x0000_0004;
+const HAS_RELATIVE_PATH: u32 = 0x0000_0008;
+const HAS_WORKING_DIR: u32 = 0x0000_0010;
+const HAS_ARGUMENTS: u32 = 0x0000_0020;
+const HAS_ICON_LOCATION: u32 = 0x0000_0040;
+const IS_UNICODE: u32 = 0x0000_0080;
+const FORCE_NO_LINK_INFO: u32 = 0x0000_0100;
+
+// LinkInfoFlags
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x1;
+const COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX: u32 = 0x2;
+
+// ExtraData 块签名
+const ENVIRONMENT_VARIABLE_DATA_BLOCK: u32 = 0xA000_0001;
+const ICON_ENVIRONMENT_DATA_BLOCK: u32 = 0xA000_0007;
+
+// 字符串数据的最大长度，防止损坏的文件导致过量分配
+const MAX_STRING_CHARS: usize = 32 * 1024;
+
+// 小端字节读取器，越界时返回错误而不是 panic
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).ok_or("Shell link is truncated")?;
+        let slice = self.data.get(self.pos..end).ok_or("Shell link is truncated")?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        self.bytes(len).map(|_| ())
+    }
+}
+
+fn read_u32_at(data: &[u8], offset: usize) -> Result<u32, String> {
+    let b = data.get(offset..offset + 4).ok_or("Shell link is truncated")?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+// ANSI 字符串使用系统代码页，这里按 UTF-8 宽松解码，有 Unicode 版本时优先使用 Unicode 版本
+fn decode_ansi(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).to_string()
+}
+
+fn decode_utf16(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+// 读取以 NUL 结尾的 ANSI 字符串
+fn ansi_at(data: &[u8], offset: usize) -> Result<String, String> {
+    let tail = data.get(offset..).ok_or("Shell link is truncated")?;
+    let end = tail.iter().position(|&b| b == 0).unwrap_or(tail.len());
+    Ok(decode_ansi(&tail[..end]))
+}
+
+// 读取以 NUL 结尾的 UTF-16LE 字符串
+fn utf16_at(data: &[u8], offset: usize) -> Result<String, String> {
+    let tail = data.get(offset..).ok_or("Shell link is truncated")?;
+    let end = tail
+        .chunks_exact(2)
+        .position(|c| c == [0, 0])
+        .map(|i| i * 2)
+        .unwrap_or(tail.len() - tail.len() % 2);
+    Ok(decode_utf16(&tail[..end]))
+}
+
+// 定长缓冲区中的字符串（ExtraData 块使用）
+fn fixed_ansi(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    decode_ansi(&bytes[..end])
+}
+
+fn fixed_utf16(bytes: &[u8]) -> String {
+    let end = bytes
+        .chunks_exact(2)
+        .position(|c| c == [0, 0])
+        .map(|i| i * 2)
+        .unwrap_or(bytes.len() - bytes.len() % 2);
+    decode_utf16(&bytes[..end])
+}
+
+fn non_empty(s: String) -> Option<String> {
+    if s.is_empty() { None } else { Some(s) }
+}
+
+// 解析 LinkInfo 结构，返回本地或网络路径
+fn parse_link_info(info: &[u8]) -> Result<Option<String>, String> {
+    let header_size = read_u32_at(info, 4)? as usize;
+    let flags = read_u32_at(info, 8)?;
+    let local_base_path_offset = read_u32_at(info, 16)? as usize;
+    let network_link_offset = read_u32_at(info, 20)? as usize;
+    let common_path_suffix_offset = read_u32_at(info, 24)? as usize;
+
+    // 头部不小于 0x24 时带有 Unicode 版本的偏移
+    let (local_base_path, common_path_suffix) = if header_size >= 0x24 {
+        let base_unicode = read_u32_at(info, 28)? as usize;
+        let suffix_unicode = read_u32_at(info, 32)? as usize;
+        (
+            if flags & VOLUME_ID_AND_LOCAL_BASE_PATH != 0 { utf16_at(info, base_unicode)? } else { String::new() },
+            utf16_at(info, suffix_unicode)?,
+        )
+    } else {
+        (
+            if flags & VOLUME_ID_AND_LOCAL_BASE_PATH != 0 { ansi_at(info, local_base_path_offset)? } else { String::new() },
+            ansi_at(info, common_path_suffix_offset)?,
+        )
+    };
+
+    if flags & VOLUME_ID_AND_LOCAL_BASE_PATH != 0 && !local_base_path.is_empty() {
+        return Ok(Some(format!("{}{}", local_base_path, common_path_suffix)));
+    }
+
+    if flags & COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX != 0 {
+        let link = info.get(network_link_offset..).ok_or("Shell link is truncated")?;
+        let net_name_offset = read_u32_at(link, 8)? as usize;
+        let net_name = if net_name_offset > 0x14 {
+            // 带有 Unicode 版本的网络名称
+            let unicode_offset = read_u32_at(link, 20)? as usize;
+            utf16_at(link, unicode_offset)?
+        } else {
+            ansi_at(link, net_name_offset)?
+        };
+        if net_name.is_empty() {
+            return Ok(None);
+        }
+        if common_path_suffix.is_empty() {
+            return Ok(Some(net_name));
+        }
+        return Ok(Some(format!("{}\\{}", net_name.trim_end_matches('\\'), common_path_suffix)));
+    }
+
+    Ok(None)
+}
+
+// 解析 .lnk 文件内容
+pub fn parse(data: &[u8]) -> Result<ShellLink, String> {
+    let mut reader = Reader::new(data);
+
+    if reader.u32()? != HEADER_SIZE {
+        return Err("Not a shell link file".to_string());
+    }
+    if reader.bytes(16)? != LINK_CLSID {
+        return Err("Not a shell link file".to_string());
+    }
+    let flags = reader.u32()?;
+    // FileAttributes、三个时间戳、FileSize
+    reader.skip(4 + 8 * 3 + 4)?;
+    let icon_index = reader.u32()? as i32;
+    // ShowCommand、HotKey 和保留字段
+    reader.skip(4 + 2 + 2 + 4 + 4)?;
+
+    let mut link = ShellLink {
+        icon_index,
+        ..Default::default()
+    };
+
+    if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+        let size = reader.u16()? as usize;
+        reader.skip(size)?;
+    }
+
+    if flags & HAS_LINK_INFO != 0 {
+        let start = reader.pos;
+        let size = reader.u32()? as usize;
+        if size < 0x1C {
+            return Err("Invalid LinkInfo size".to_string());
+        }
+        reader.pos = start;
+        let info = reader.bytes(size)?;
+        if flags & FORCE_NO_LINK_INFO == 0 {
+            link.local_path = parse_link_info(info)?;
+        }
+    }
+
+    let unicode = flags & IS_UNICODE != 0;
+    let read_string = |reader: &mut Reader| -> Result<Option<String>, String> {
+        let count = reader.u16()? as usize;
+        if count > MAX_STRING_CHARS {
+            return Err("Shell link string is too long".to_string());
+        }
+        let value = if unicode {
+            decode_utf16(reader.bytes(count * 2)?)
+        } else {
+            decode_ansi(reader.bytes(count)?)
+        };
+        Ok(non_empty(value))
+    };
+
+    if flags & HAS_NAME != 0 {
+        link.description = read_string(&mut reader)?;
+    }
+    if flags & HAS_RELATIVE_PATH != 0 {
+        link.relative_path = read_string(&mut reader)?;
+    }
+    if flags & HAS_WORKING_DIR != 0 {
+        link.working_dir = read_string(&mut reader)?;
+    }
+    if flags & HAS_ARGUMENTS != 0 {
+        link.arguments = read_string(&mut reader)?;
+    }
+    if flags & HAS_ICON_LOCATION != 0 {
+        link.icon_location = read_string(&mut reader)?;
+    }
+
+    // ExtraData 块，以大小小于 4 的终止块结束；损坏的尾部不影响前面已解析的字段
+    while let Ok(block_size) = reader.u32() {
+        if block_size < 8 {
+            break;
+        }
+        let Ok(block) = reader.bytes(block_size as usize - 4) else {
+            break;
+        };
+        let signature = u32::from_le_bytes([block[0], block[1], block[2], block[3]]);
+        let body = &block[4..];
+        if body.len() < 260 + 520 {
+            continue;
+        }
+        let value = non_empty(fixed_utf16(&body[260..260 + 520]))
+            .or_else(|| non_empty(fixed_ansi(&body[..260])));
+        match signature {
+            ENVIRONMENT_VARIABLE_DATA_BLOCK => link.env_target = value,
+            ICON_ENVIRONMENT_DATA_BLOCK if value.is_some() => link.icon_location = value,
+            _ => {}
+        }
+    }
+
+    Ok(link)
+}
+
+// 读取并解析 .lnk 文件
+pub fn load(path: &Path) -> Result<ShellLink, String> {
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    parse(&data)
+}
+
+// 展开 %VAR% 形式的环境变量，未定义的变量保持原样
+pub fn expand_env_vars(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('%') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('%') {
+            Some(end) => {
+                let name = &after[..end];
+                match std::env::var(name) {
+                    Ok(v) if !name.is_empty() => result.push_str(&v),
+                    _ => {
+                        result.push('%');
+                        result.push_str(name);
+                        result.push('%');
+                    }
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                result.push('%');
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+impl ShellLink {
+    // 快捷方式指定的图标文件，展开环境变量
+    pub fn icon_path(&self) -> Option<String> {
+        self.icon_location
+            .as_deref()
+            .map(expand_env_vars)
+            .filter(|path| !path.trim().is_empty())
+    }
+
+    // 按优先级得到快捷方式的目标：LinkInfo 中的路径、环境变量路径、相对于 .lnk 所在目录的相对路径
+    pub fn target_path(&self, lnk_path: &Path) -> Option<String> {
+        if let Some(path) = &self.local_path {
+            return Some(path.clone());
+        }
+        if let Some(path) = &self.env_target {
+            return Some(expand_env_vars(path));
+        }
+        let relative = self.relative_path.as_ref()?;
+        let dir = lnk_path.parent()?;
+        Some(dir.join(relative.replace('\\', std::path::MAIN_SEPARATOR_STR)).to_string_lossy().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_unicode_local_link() {
+        let link = parse(include_bytes!("../tests/fixtures/lnk/unicode_local.lnk")).unwrap();
+        assert_eq!(
+            link.local_path.as_deref(),
+            Some("C:\\Program Files\\Microsoft VS Code\\Code.exe")
+        );
+        assert_eq!(link.description.as_deref(), Some("Code editing. Redefined."));
+        assert_eq!(link.working_dir.as_deref(), Some("C:\\Program Files\\Microsoft VS Code"));
+        // 参数中的引号和撇号原样保留
+        assert_eq!(
+            link.arguments.as_deref(),
+            Some("--new-window \"C:\\Users\\O'Brien\\notes\"")
+        );
+        assert_eq!(
+            link.icon_location.as_deref(),
+            Some("C:\\Program Files\\Microsoft VS Code\\Code.exe")
+        );
+        assert_eq!(link.icon_index, 2);
+        assert_eq!(link.icon_path(), link.icon_location);
+    }
+
+    #[test]
+    fn parses_ansi_network_link() {
+        let link = parse(include_bytes!("../tests/fixtures/lnk/ansi_network.lnk")).unwrap();
+        assert_eq!(link.local_path.as_deref(), Some("\\\\server\\share\\tools\\app.exe"));
+        assert_eq!(link.working_dir.as_deref(), Some("\\\\server\\share\\tools"));
+        assert_eq!(link.arguments, None);
+    }
+
+    #[test]
+    fn parses_environment_block() {
+        let link = parse(include_bytes!("../tests/fixtures/lnk/env_only.lnk")).unwrap();
+        assert_eq!(link.local_path, None);
+        assert_eq!(link.env_target.as_deref(), Some("%windir%\\system32\\notepad.exe"));
+        assert_eq!(link.arguments.as_deref(), Some("/A"));
+    }
+
+    #[test]
+    fn rejects_truncated_and_foreign_files() {
+        let data = include_bytes!("../tests/fixtures/lnk/unicode_local.lnk");
+        assert!(parse(&data[..40]).is_err());
+        assert!(parse(&data[..120]).is_err());
+        assert!(parse(b"[Desktop Entry]\nName=x\n").is_err());
+    }
+
+    #[test]
+    fn expands_environment_variables() {
+        std::env::set_var("OOPS_LNK_TEST", "C:\\Windows");
+        assert_eq!(expand_env_vars("%OOPS_LNK_TEST%\\notepad.exe"), "C:\\Windows\\notepad.exe");
+        assert_eq!(expand_env_vars("%OOPS_UNDEFINED%\\x"), "%OOPS_UNDEFINED%\\x");
+        assert_eq!(expand_env_vars("100%"), "100%");
+    }
+}
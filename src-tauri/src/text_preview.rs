@@ -0,0 +1,129 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+
+// 预览最多保留的行数
+pub const PREVIEW_MAX_LINES: usize = 200;
+
+// 最多读取的文件头长度，大文件只读取开头部分
+const READ_LIMIT: u64 = 256 * 1024;
+
+// 判断文件头的编码：BOM 优先，其次是合法的 UTF-8，最后交给 chardetng 猜测
+fn detect_encoding(bytes: &[u8], complete: bool) -> (&'static Encoding, usize) {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        return (encoding, bom_len);
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(_) => return (UTF_8, 0),
+        // 读取被截断在多字节字符中间
+        Err(e) if !complete && e.error_len().is_none() => return (UTF_8, 0),
+        Err(_) => {}
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, complete);
+    (detector.guess(None, true), 0)
+}
+
+// 解码文本并截取前 max_lines 行
+pub fn decode_preview(bytes: &[u8], complete: bool, max_lines: usize) -> String {
+    let (encoding, bom_len) = detect_encoding(bytes, complete);
+    let (decoded, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+
+    let mut text = decoded.into_owned();
+    if !complete {
+        // 截断处的半个字符会被解码为替换字符
+        while text.ends_with('\u{FFFD}') {
+            text.pop();
+        }
+    }
+
+    if let Some((index, _)) = text.match_indices('\n').nth(max_lines.saturating_sub(1)) {
+        text.truncate(index + 1);
+    }
+    text
+}
+
+// 读取文本文件开头的预览内容
+pub fn read_preview(path: &Path) -> Result<String, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut bytes = Vec::new();
+    // 多读一个字节，用来判断文件是否超过读取上限
+    file.take(READ_LIMIT + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+
+    let complete = bytes.len() as u64 <= READ_LIMIT;
+    bytes.truncate(READ_LIMIT as usize);
+    Ok(decode_preview(&bytes, complete, PREVIEW_MAX_LINES))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        let mut bytes = if big_endian { vec![0xFE, 0xFF] } else { vec![0xFF, 0xFE] };
+        for unit in text.encode_utf16() {
+            let pair = if big_endian { unit.to_be_bytes() } else { unit.to_le_bytes() };
+            bytes.extend_from_slice(&pair);
+        }
+        bytes
+    }
+
+    #[test]
+    fn detects_bom_encodings() {
+        let text = "你好，world\n";
+        let (encoding, bom_len) = detect_encoding(&utf16(text, false), true);
+        assert_eq!((encoding.name(), bom_len), ("UTF-16LE", 2));
+        assert_eq!(decode_preview(&utf16(text, false), true, 10), text);
+
+        let (encoding, bom_len) = detect_encoding(&utf16(text, true), true);
+        assert_eq!((encoding.name(), bom_len), ("UTF-16BE", 2));
+        assert_eq!(decode_preview(&utf16(text, true), true, 10), text);
+
+        // UTF-8 的 BOM 不出现在预览中
+        assert_eq!(decode_preview(b"\xEF\xBB\xBFabc", true, 10), "abc");
+    }
+
+    #[test]
+    fn detects_gbk_with_chardetng() {
+        // 不是合法 UTF-8 的 GBK 文本交给 chardetng 猜测
+        let (bytes, _, _) = encoding_rs::GBK.encode("中文文本预览，编码检测。这是一段用来测试的简体中文内容。");
+        assert!(std::str::from_utf8(&bytes).is_err());
+        let (encoding, bom_len) = detect_encoding(&bytes, true);
+        assert_eq!((encoding.name(), bom_len), ("GBK", 0));
+        assert_eq!(
+            decode_preview(&bytes, true, 10),
+            "中文文本预览，编码检测。这是一段用来测试的简体中文内容。"
+        );
+    }
+
+    #[test]
+    fn keeps_utf8_cut_in_the_middle_of_a_character() {
+        // 读取上限截断在“界”的三个字节中间
+        let bytes = "hello 世界".as_bytes();
+        let cut = &bytes[..bytes.len() - 1];
+        assert_eq!(detect_encoding(cut, false).0, UTF_8);
+        assert_eq!(decode_preview(cut, false, 10), "hello 世");
+    }
+
+    #[test]
+    fn keeps_first_lines() {
+        assert_eq!(decode_preview(b"a\nb\nc\n", true, 2), "a\nb\n");
+        assert_eq!(decode_preview(b"a\nb\n", true, 2), "a\nb\n");
+        assert_eq!(decode_preview(b"a\nb", true, 5), "a\nb");
+    }
+
+    #[test]
+    fn reads_preview_from_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("a.txt");
+        std::fs::write(&path, utf16("line 1\nline 2\n", false)).unwrap();
+        assert_eq!(read_preview(&path).unwrap(), "line 1\nline 2\n");
+        assert!(read_preview(&temp.path().join("missing.txt")).is_err());
+    }
+}
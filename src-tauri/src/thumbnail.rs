@@ -0,0 +1,209 @@
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use image::ImageFormat;
+use rusqlite::{params, Connection, OptionalExtension};
+use crate::db::DbState;
+use crate::utils::now_millis;
+
+// 可以直接生成缩略图的图片格式
+pub const IMAGE_EXTENSIONS: [&str; 8] = ["png", "jpg", "jpeg", "webp", "gif", "bmp", "ico", "tiff"];
+
+// 缩略图默认边长
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+
+// 支持的缩略图边长，请求的尺寸向上取整到其中一档，避免同一张图缓存过多尺寸
+pub const THUMBNAIL_SIZES: [u32; 4] = [64, 128, 256, 512];
+
+pub fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.as_str()))
+}
+
+// 将请求的尺寸对齐到支持的档位
+pub fn snap_size(size: u32) -> u32 {
+    THUMBNAIL_SIZES
+        .iter()
+        .copied()
+        .find(|&s| s >= size)
+        .unwrap_or(THUMBNAIL_SIZES[THUMBNAIL_SIZES.len() - 1])
+}
+
+// 文件的修改时间（毫秒）和大小，用于判断缓存是否过期
+pub fn file_stamp(path: &Path) -> Result<(i64, u64), String> {
+    let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    Ok((modified, metadata.len()))
+}
+
+// 生成较长边不超过 size 的 PNG 缩略图，小图保持原尺寸
+pub fn render_thumbnail(path: &Path, size: u32) -> Result<Vec<u8>, String> {
+    let img = image::open(path).map_err(|e| e.to_string())?;
+    let img = if img.width() > size || img.height() > size {
+        img.thumbnail(size, size)
+    } else {
+        img
+    };
+
+    let mut cursor = Cursor::new(Vec::new());
+    img.write_to(&mut cursor, ImageFormat::Png).map_err(|e| e.to_string())?;
+    Ok(cursor.into_inner())
+}
+
+fn load_cached(conn: &Connection, path: &str, size: u32, stamp: (i64, u64)) -> rusqlite::Result<Option<Vec<u8>>> {
+    conn.prepare_cached(
+        "SELECT data FROM thumbnail_cache WHERE path = ? AND size = ? AND mtime = ? AND file_size = ?",
+    )?
+    .query_row(params![path, size, stamp.0, stamp.1 as i64], |row| row.get(0))
+    .optional()
+}
+
+fn store_cached(conn: &Connection, path: &str, size: u32, stamp: (i64, u64), data: &[u8]) -> rusqlite::Result<()> {
+    conn.prepare_cached(
+        "INSERT OR REPLACE INTO thumbnail_cache (path, size, mtime, file_size, data, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+    )?
+    .execute(params![path, size, stamp.0, stamp.1 as i64, data, now_millis()])?;
+    Ok(())
+}
+
+// 读取缩略图，缓存不存在或文件修改时间、大小变化时重新生成
+// 生成缩略图较慢，期间不持有数据库连接
+pub fn cached_thumbnail(db: &DbState, path: &Path, size: u32) -> Result<Vec<u8>, String> {
+    let size = snap_size(size);
+    let stamp = file_stamp(path)?;
+    let key = path.to_string_lossy();
+
+    {
+        let conn = db.conn()?;
+        if let Some(data) = load_cached(&conn, &key, size, stamp).map_err(|e| e.to_string())? {
+            return Ok(data);
+        }
+    }
+
+    let data = render_thumbnail(path, size)?;
+
+    let conn = db.conn()?;
+    store_cached(&conn, &key, size, stamp, &data).map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+// 删除已不在文件列表中的缩略图
+pub fn prune_stale_thumbnails(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.prepare_cached("DELETE FROM thumbnail_cache WHERE path NOT IN (SELECT path FROM files)")?
+        .execute([])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+    use std::fs::File;
+    use std::time::{Duration, SystemTime};
+
+    fn test_db() -> DbState {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::init_database(&mut conn).unwrap();
+        DbState::new(conn)
+    }
+
+    fn write_png(path: &Path, width: u32, height: u32) {
+        RgbaImage::from_pixel(width, height, Rgba([255, 0, 0, 255]))
+            .save_with_format(path, ImageFormat::Png)
+            .unwrap();
+    }
+
+    fn set_mtime(path: &Path, secs: u64) {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    fn dimensions(png: &[u8]) -> (u32, u32) {
+        let img = image::load_from_memory_with_format(png, ImageFormat::Png).unwrap();
+        (img.width(), img.height())
+    }
+
+    #[test]
+    fn snap_size_rounds_up_to_supported_sizes() {
+        assert_eq!(snap_size(0), 64);
+        assert_eq!(snap_size(64), 64);
+        assert_eq!(snap_size(65), 128);
+        assert_eq!(snap_size(128), 128);
+        assert_eq!(snap_size(200), 256);
+        assert_eq!(snap_size(256), 256);
+        assert_eq!(snap_size(257), 512);
+        // 超过最大档位时使用最大档位
+        assert_eq!(snap_size(4096), 512);
+    }
+
+    #[test]
+    fn cached_thumbnail_rerenders_when_file_changes() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("a.png");
+        write_png(&path, 200, 100);
+        set_mtime(&path, 1_000);
+        let db = test_db();
+
+        // 按档位缩小，保持宽高比
+        let first = cached_thumbnail(&db, &path, 100).unwrap();
+        assert_eq!(dimensions(&first), (128, 64));
+        let cached: i64 = db
+            .conn()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM thumbnail_cache WHERE size = 128", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(cached, 1);
+
+        // 缓存命中时不读取文件内容
+        db.conn()
+            .unwrap()
+            .execute("UPDATE thumbnail_cache SET data = x'01'", [])
+            .unwrap();
+        assert_eq!(cached_thumbnail(&db, &path, 128).unwrap(), [1]);
+
+        // 修改时间变化
+        set_mtime(&path, 2_000);
+        assert_eq!(dimensions(&cached_thumbnail(&db, &path, 128).unwrap()), (128, 64));
+
+        // 大小变化，修改时间相同
+        write_png(&path, 100, 100);
+        set_mtime(&path, 2_000);
+        assert_eq!(dimensions(&cached_thumbnail(&db, &path, 128).unwrap()), (100, 100));
+
+        assert!(cached_thumbnail(&db, &temp.path().join("missing.png"), 128).is_err());
+    }
+
+    #[test]
+    fn prune_removes_thumbnails_of_unused_paths() {
+        let db = test_db();
+        let conn = db.conn().unwrap();
+        conn.execute(
+            "INSERT INTO files (id, name, display_name, path) VALUES ('a', 'a.png', 'a', '/pics/a.png')",
+            [],
+        )
+        .unwrap();
+        for (path, size) in [("/pics/a.png", 64), ("/pics/a.png", 256), ("/pics/b.png", 64)] {
+            store_cached(&conn, path, size, (0, 0), &[0]).unwrap();
+        }
+
+        assert_eq!(prune_stale_thumbnails(&conn).unwrap(), 1);
+        let remaining: Vec<(String, u32)> = conn
+            .prepare("SELECT path, size FROM thumbnail_cache ORDER BY size")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(remaining, [("/pics/a.png".to_string(), 64), ("/pics/a.png".to_string(), 256)]);
+    }
+}
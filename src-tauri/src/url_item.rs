@@ -0,0 +1,350 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use crate::text_preview::decode_preview;
+use crate::utils::percent_decode;
+
+// URL 启动项：路径列保存 URL 本身，打开时交给系统的协议处理程序
+
+// 读取 HTML 文件开头的长度，<title> 和 <link rel="icon"> 都在 <head> 中
+const HTML_READ_LIMIT: u64 = 64 * 1024;
+
+// URL 的协议名，协议名至少两个字符，避免把 Windows 盘符 C:\ 当作协议
+pub fn url_scheme(s: &str) -> Option<&str> {
+    let (scheme, rest) = s.trim().split_once(':')?;
+    let valid = scheme.len() >= 2
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    (valid && !rest.is_empty()).then_some(scheme)
+}
+
+pub fn is_url(s: &str) -> bool {
+    url_scheme(s).is_some()
+}
+
+// file:// URL 对应的本地路径
+pub fn file_url_to_path(url: &str) -> Option<PathBuf> {
+    let scheme = url_scheme(url)?;
+    if !scheme.eq_ignore_ascii_case("file") {
+        return None;
+    }
+    let rest = url.trim()[scheme.len() + 1..].strip_prefix("//")?;
+    let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+    let path = percent_decode(rest.split(['?', '#']).next().unwrap_or(rest));
+
+    // Windows 路径的形式为 /C:/Users/...
+    #[cfg(target_os = "windows")]
+    let path = if path.starts_with('/') && path.as_bytes().get(2) == Some(&b':') {
+        path[1..].to_string()
+    } else {
+        path
+    };
+
+    Some(PathBuf::from(path))
+}
+
+// 本地路径转换为 file:// URL
+pub fn path_to_file_url(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut url = String::from("file://");
+    if !path.starts_with('/') {
+        url.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b':' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
+
+// URL 的默认名称：本地文件使用文件名，有主机名时使用主机名，否则使用去掉协议后的部分（如 mailto: 的地址）
+pub fn url_name(url: &str) -> String {
+    let url = url.trim();
+    if let Some(name) = file_url_to_path(url).as_deref().and_then(Path::file_name) {
+        return name.to_string_lossy().to_string();
+    }
+
+    let rest = url.split_once(':').map(|(_, rest)| rest).unwrap_or(url);
+    let name = match rest.strip_prefix("//") {
+        Some(authority) => {
+            let authority = authority.split(['/', '?', '#']).next().unwrap_or(authority);
+            let host = authority.rsplit('@').next().unwrap_or(authority);
+            // 去掉端口号
+            match host.rsplit_once(':') {
+                Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+                _ => host,
+            }
+        }
+        None => rest.split(['?', '#']).next().unwrap_or(rest),
+    };
+
+    if name.is_empty() {
+        url.to_string()
+    } else {
+        percent_decode(name)
+    }
+}
+
+// HTML 文件中启动器关心的内容
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HtmlPage {
+    pub title: Option<String>,
+    // <link rel="icon"> 的 href，未解析
+    pub icon_href: Option<String>,
+}
+
+// 图标链接的来源
+#[derive(Debug, Clone, PartialEq)]
+pub enum IconSource {
+    DataUrl(String),
+    File(PathBuf),
+}
+
+// 解码常见的字符实体
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest[1..].find(';').map(|end| &rest[1..end + 1]);
+        let ch = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => {
+                let number = entity.strip_prefix('#')?;
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => number.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+
+        match (ch, entity) {
+            (Some(ch), Some(entity)) => {
+                decoded.push(ch);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+// 读取标签中的属性值，支持带引号和不带引号的写法
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut search = 0;
+    loop {
+        let index = search + lower[search..].find(name)?;
+        search = index + name.len();
+
+        // 属性名前必须是空白，避免匹配到 data-rel 之类的属性
+        let rest = lower[search..].trim_start();
+        if !lower[..index].ends_with(|c: char| c.is_ascii_whitespace()) || !rest.starts_with('=') {
+            continue;
+        }
+
+        let value = tag[tag.len() - rest.len() + 1..].trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+            _ => value.split(|c: char| c.is_ascii_whitespace()).next().unwrap_or_default(),
+        };
+        return Some(decode_entities(value));
+    }
+}
+
+// 从 HTML 中取出 <title> 和图标链接
+pub fn parse_html_head(html: &str) -> HtmlPage {
+    // 只转换 ASCII 字母，转换前后的字节位置一致
+    let lower = html.to_ascii_lowercase();
+
+    let title = lower
+        .find("<title")
+        .and_then(|start| {
+            let content_start = start + lower[start..].find('>')? + 1;
+            let content_end = content_start + lower[content_start..].find("</title")?;
+            Some(decode_entities(&html[content_start..content_end]))
+        })
+        .map(|title| title.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|title| !title.is_empty());
+
+    let mut icon_href = None;
+    let mut pos = 0;
+    while let Some(offset) = lower[pos..].find("<link") {
+        let start = pos + offset;
+        let end = lower[start..].find('>').map_or(lower.len(), |end| start + end);
+        let tag = &html[start..end];
+        let is_icon = attribute(tag, "rel")
+            .is_some_and(|rel| rel.to_ascii_lowercase().split_whitespace().any(|rel| rel == "icon"));
+        if is_icon {
+            if let Some(href) = attribute(tag, "href").filter(|href| !href.is_empty()) {
+                icon_href = Some(href);
+                break;
+            }
+        }
+        pos = end;
+    }
+
+    HtmlPage { title, icon_href }
+}
+
+// 读取本地 HTML 文件的标题和图标链接
+pub fn read_html_page(path: &Path) -> Result<HtmlPage, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut bytes = Vec::new();
+    file.take(HTML_READ_LIMIT + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+
+    let complete = bytes.len() as u64 <= HTML_READ_LIMIT;
+    bytes.truncate(HTML_READ_LIMIT as usize);
+    Ok(parse_html_head(&decode_preview(&bytes, complete, usize::MAX)))
+}
+
+// 解析图标链接：data: URL 直接使用，相对路径和 file:// 地址按 HTML 文件所在目录解析
+// 网络地址不下载，返回 None
+pub fn resolve_icon_href(href: &str, html_path: &Path) -> Option<IconSource> {
+    let href = href.trim();
+    if href.starts_with("data:") {
+        return Some(IconSource::DataUrl(href.to_string()));
+    }
+    if is_url(href) {
+        return file_url_to_path(href).map(IconSource::File);
+    }
+    // 协议相对地址 //example.com/favicon.ico
+    if href.starts_with("//") {
+        return None;
+    }
+
+    let relative = percent_decode(href.split(['?', '#']).next().unwrap_or(href));
+    let path = match relative.strip_prefix('/') {
+        // 根路径没有对应的站点目录，只能按文件系统的绝对路径处理
+        Some(_) => PathBuf::from(&relative),
+        None => html_path.parent()?.join(&relative),
+    };
+    Some(IconSource::File(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_scheme_rejects_drive_letters() {
+        assert_eq!(url_scheme("https://example.com"), Some("https"));
+        assert_eq!(url_scheme("  mailto:a@example.com "), Some("mailto"));
+        assert_eq!(url_scheme("git+ssh://host/repo"), Some("git+ssh"));
+        assert_eq!(url_scheme(r"C:\Windows\notepad.exe"), None);
+        assert_eq!(url_scheme("C:/Windows"), None);
+        assert_eq!(url_scheme("/usr/bin/app"), None);
+        assert_eq!(url_scheme("1abc:x"), None);
+        assert_eq!(url_scheme("a b:x"), None);
+        // 只有协议名没有内容
+        assert_eq!(url_scheme("https:"), None);
+    }
+
+    #[test]
+    fn file_urls_round_trip() {
+        #[cfg(not(target_os = "windows"))]
+        let path = PathBuf::from("/tmp/a b/中文#1?.txt");
+        #[cfg(target_os = "windows")]
+        let path = PathBuf::from(r"C:\Users\a b\中文#1.txt");
+
+        let url = path_to_file_url(&path);
+        assert!(!url.contains([' ', '#', '?']));
+        assert_eq!(file_url_to_path(&url), Some(path));
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            assert_eq!(path_to_file_url(Path::new("/a b/c.html")), "file:///a%20b/c.html");
+            assert_eq!(file_url_to_path("FILE://localhost/a/b.html"), Some(PathBuf::from("/a/b.html")));
+            // 查询和片段不属于路径
+            assert_eq!(file_url_to_path("file:///a/b.html?x=1#top"), Some(PathBuf::from("/a/b.html")));
+        }
+        #[cfg(target_os = "windows")]
+        assert_eq!(path_to_file_url(Path::new(r"C:\a b\c.html")), "file:///C:/a%20b/c.html");
+
+        assert_eq!(file_url_to_path("https://example.com/a"), None);
+        assert_eq!(file_url_to_path("file:a.html"), None);
+    }
+
+    #[test]
+    fn url_name_uses_file_name_or_host() {
+        assert_eq!(url_name("https://user@example.com:8080/path?q#f"), "example.com");
+        assert_eq!(url_name("mailto:a%40b@example.com"), "a@b@example.com");
+        assert_eq!(url_name("file:///tmp/a%20b.html"), "a b.html");
+        assert_eq!(url_name("about:"), "about:");
+    }
+
+    #[test]
+    fn decode_entities_handles_named_and_numeric() {
+        assert_eq!(decode_entities("a &amp; b &lt;c&gt; &quot;d&quot; &apos;e&apos;"), "a & b <c> \"d\" 'e'");
+        assert_eq!(decode_entities("&#20013;&#x6587;&#X41;"), "中文A");
+        // 无法识别的实体原样保留
+        assert_eq!(decode_entities("AT&T &unknown; &#xZZ; & ;"), "AT&T &unknown; &#xZZ; & ;");
+        assert_eq!(decode_entities("&#1114112;&"), "&#1114112;&");
+    }
+
+    #[test]
+    fn parse_html_head_reads_title_and_icon() {
+        let html = r#"<!DOCTYPE html>
+<HTML><Head>
+<meta charset="utf-8">
+<TITLE lang="en">
+  Tom &amp; Jerry
+  首页 </TITLE>
+<link data-rel="icon" href="wrong.png">
+<link rel="stylesheet" href="style.css">
+<link rel='shortcut icon' href='img/fav&amp;icon.ico'>
+<link rel="icon" href="second.png">
+</head><body></body></HTML>"#;
+        let page = parse_html_head(html);
+        assert_eq!(page.title.as_deref(), Some("Tom & Jerry 首页"));
+        assert_eq!(page.icon_href.as_deref(), Some("img/fav&icon.ico"));
+
+        // 不带引号的属性，空标题
+        let page = parse_html_head("<title> </title><link href=a.ico rel=icon>");
+        assert_eq!(page, HtmlPage { title: None, icon_href: Some("a.ico".to_string()) });
+        assert_eq!(parse_html_head("<title>unterminated"), HtmlPage::default());
+    }
+
+    #[test]
+    fn resolve_icon_href_handles_local_links() {
+        let html = Path::new("/site/pages/index.html");
+        assert_eq!(
+            resolve_icon_href("data:image/png;base64,AAAA", html),
+            Some(IconSource::DataUrl("data:image/png;base64,AAAA".to_string()))
+        );
+        assert_eq!(
+            resolve_icon_href("img/a%20b.png?v=2", html),
+            Some(IconSource::File(Path::new("/site/pages").join("img/a b.png")))
+        );
+        assert_eq!(resolve_icon_href("https://example.com/favicon.ico", html), None);
+        assert_eq!(resolve_icon_href("//example.com/favicon.ico", html), None);
+    }
+
+    #[test]
+    fn read_html_page_decodes_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("index.html");
+        std::fs::write(&path, "<title>页面</title><link rel=icon href=favicon.ico>").unwrap();
+        let page = read_html_page(&path).unwrap();
+        assert_eq!(page.title.as_deref(), Some("页面"));
+        assert_eq!(page.icon_href.as_deref(), Some("favicon.ico"));
+    }
+}
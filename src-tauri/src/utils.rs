@@ -1,28 +1,26 @@
 use std::env;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use crate::shell_link::{self, ShellLink};
 
-pub fn resolve_shortcut(path: &str) -> String {
-    #[cfg(target_os = "windows")]
-    {
-        if path.to_lowercase().ends_with(".lnk") {
-            let output = Command::new("powershell")
-                .args([
-                    "-NoProfile",
-                    "-Command",
-                    &format!("$shell = New-Object -ComObject WScript.Shell; $shortcut = $shell.CreateShortcut('{}'); $shortcut.TargetPath", path)
-                ])
-                .output();
-
-            if let Ok(out) = output {
-                let target = String::from_utf8_lossy(&out.stdout).trim().to_string();
-                if !target.is_empty() && Path::new(&target).exists() {
-                    return target;
+// 解析快捷方式的目标路径，解析成功时同时返回快捷方式的内容
+// .lnk 直接解析二进制内容，目标不存在时保持原路径
+// Linux 的 .desktop 文件本身就是启动目标，保持原路径，由 desktop_entry 负责解析
+pub fn resolve_shortcut(path: &str) -> (String, Option<ShellLink>) {
+    if path.to_lowercase().ends_with(".lnk") {
+        let lnk_path = Path::new(path);
+        match shell_link::load(lnk_path) {
+            Ok(link) => {
+                if let Some(target) = link.target_path(lnk_path) {
+                    if Path::new(&target).exists() {
+                        return (target, Some(link));
+                    }
                 }
             }
+            Err(e) => println!("Failed to parse shortcut {}: {}", path, e),
         }
     }
-    path.to_string()
+    (path.to_string(), None)
 }
 
 pub fn to_abs_path(path: &str) -> Result<String, String> {
@@ -53,3 +51,123 @@ pub fn to_abs_path(path: &str) -> Result<String, String> {
 
     Ok(abs_str)
 }
+
+// 当前时间的毫秒时间戳
+pub fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+// 解码 URL 中 %XX 形式的字符，不合法的转义原样保留
+pub fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+// 在 PATH 中查找可执行文件
+pub fn find_in_path(program: &str) -> Option<PathBuf> {
+    let paths = env::var_os("PATH")?;
+
+    #[cfg(target_os = "windows")]
+    let extensions: Vec<String> = env::var("PATHEXT")
+        .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string())
+        .split(';')
+        .map(|ext| ext.to_lowercase())
+        .collect();
+
+    for dir in env::split_paths(&paths) {
+        let candidate = dir.join(program);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+
+        #[cfg(target_os = "windows")]
+        for ext in &extensions {
+            let candidate = dir.join(format!("{}{}", program, ext));
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+    }
+    None
+}
+
+// 构造在终端窗口中运行 args 的命令
+pub fn terminal_command(args: &[String]) -> Result<Command, String> {
+    if args.is_empty() {
+        return Err("command is empty".to_string());
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // cmd 自己解析命令行，不能按 Command::arg 的规则转义，拼接后原样传递
+        // 在 cmd 中执行的命令文本保持原样，其他参数含空格时加引号
+        let runs_cmd = crate::shell_command::shell_name(&args[0]) == "cmd";
+        let line = args
+            .iter()
+            .enumerate()
+            .map(|(index, arg)| {
+                if runs_cmd && index > 0 && index == args.len() - 1 {
+                    arg.clone()
+                } else if arg.is_empty() || arg.contains(char::is_whitespace) {
+                    format!("\"{}\"", arg)
+                } else {
+                    arg.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut command = Command::new("cmd.exe");
+        command.raw_arg(format!("/C start \"\" cmd.exe /K {}", line));
+        return Ok(command);
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        return Err("Running in a terminal is not supported on macOS".to_string());
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        // 优先使用用户指定的终端，其次是常见的终端模拟器
+        let preferred = env::var("TERMINAL").ok().filter(|t| !t.is_empty());
+        let candidates = preferred
+            .iter()
+            .map(String::as_str)
+            .chain(["x-terminal-emulator", "gnome-terminal", "konsole", "xfce4-terminal", "kitty", "alacritty", "xterm"]);
+
+        for terminal in candidates {
+            if find_in_path(terminal).is_none() && !Path::new(terminal).is_file() {
+                continue;
+            }
+            let mut command = Command::new(terminal);
+            // gnome-terminal 已弃用 -e，改用 -- 分隔要执行的命令
+            if terminal.ends_with("gnome-terminal") {
+                command.arg("--");
+            } else {
+                command.arg("-e");
+            }
+            command.args(args);
+            return Ok(command);
+        }
+
+        Err("No terminal emulator found".to_string())
+    }
+}
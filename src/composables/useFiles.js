@@ -1,6 +1,7 @@
 import { ref, computed } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
+import { ElMessage } from 'element-plus'
 
 // 检测是否在 Tauri 环境中运行
 const isTauri = () => !!window.__TAURI_INTERNALS__;
@@ -20,6 +21,33 @@ const generateDisplayName = (fileName) => {
   return fileName
 }
 
+// 辅助函数：转换为后端 FileInfo 结构（openCount -> open_count, displayName -> display_name）
+const toDbFile = (file, categoryId) => {
+  const dbFile = {
+    ...file,
+    open_count: file.openCount || 0,
+    display_name: file.displayName || file.display_name || generateDisplayName(file.name),
+    category: categoryId,
+    created_at: file.created_at || Date.now()
+  }
+  delete dbFile.openCount
+  delete dbFile.displayName
+  return dbFile
+}
+
+// 辅助函数：转换为前端使用的字段格式
+const fromDbFile = (file, categoryId) => {
+  const { open_count, display_name, created_at, ...otherFields } = file
+  return {
+    ...otherFields,
+    openCount: open_count || 0,
+    displayName: display_name || generateDisplayName(file.name),
+    category: categoryId,
+    // 如果数据库里没时间（老数据），加载时补全，避免每次保存都变
+    created_at: created_at || Date.now()
+  }
+}
+
 // Global state
 const currentCategory = ref('main') // 这里存储分类的 ID
 const customCategories = ref([])
@@ -38,6 +66,8 @@ const currentFiles = computed(() => {
     let result = 0
     if (sortMethod.value === 'openCount') {
       result = (a.openCount || 0) - (b.openCount || 0)
+    } else if (sortMethod.value === 'frecency') {
+      result = (a.frecency || 0) - (b.frecency || 0)
     } else if (sortMethod.value === 'created_at') {
       result = (a.created_at || 0) - (b.created_at || 0)
     } else {
@@ -101,7 +131,8 @@ export function useFiles() {
     }
   }
 
-  const deleteCategory = async (id) => {
+  // mode 为 move_to_parent、move_to_main、cascade 或 trash，返回删除结果
+  const deleteCategory = async (id, mode = 'move_to_parent') => {
     if (!id) return
     
     // 检查是否是最后一个分类，不允许删除最后一个分类以保证系统运行
@@ -109,6 +140,16 @@ export function useFiles() {
       console.warn('Cannot delete the last category.')
       return
     }
+
+    // 项目会被移动到其他分类，子分类也可能被一起删除，删除后重新加载
+    if (isTauri()) {
+      const summary = await invoke('delete_category_from_db', { id, mode })
+      await loadFiles()
+      if (!customCategories.value.some(c => c.id === currentCategory.value)) {
+        currentCategory.value = customCategories.value[0]?.id || 'main'
+      }
+      return summary
+    }
     
     const index = customCategories.value.findIndex(c => c.id === id)
     if (index !== -1) {
@@ -124,20 +165,23 @@ export function useFiles() {
         currentCategory.value = customCategories.value[0].id
       }
 
-      try {
-        if (isTauri()) {
-          await invoke('delete_category_from_db', { id })
-        } else {
-          localStorage.setItem('oopslauncher_categories', JSON.stringify(customCategories.value))
-          localStorage.setItem('oopslauncher_files', JSON.stringify(filesByCategory.value))
-        }
-        console.log(`Category ${id} deleted successfully`)
-      } catch (error) {
-        console.error('Failed to delete category:', error)
-      }
+      localStorage.setItem('oopslauncher_categories', JSON.stringify(customCategories.value))
+      localStorage.setItem('oopslauncher_files', JSON.stringify(filesByCategory.value))
+      console.log(`Category ${id} deleted successfully`)
     }
   }
 
+  // 绑定或解除分类的文件夹，folder 为空时解除绑定，返回同步结果
+  const setCategoryFolder = async (id, folder) => {
+    const summary = await invoke('set_category_folder', { id, folder })
+    const category = customCategories.value.find(c => c.id === id)
+    if (category) {
+      category.folder = folder
+    }
+    await loadFiles()
+    return summary
+  }
+
   const saveCategories = async () => {
     try {
       if (isTauri()) {
@@ -190,17 +234,7 @@ export function useFiles() {
         const allFiles = []
         for (const [categoryId, categoryFiles] of Object.entries(filesByCategory.value)) {
           for (const file of categoryFiles) {
-            // 转换openCount为open_count, displayName为display_name
-            const fileToSave = {
-              ...file,
-              open_count: file.openCount || 0,
-              display_name: file.displayName || generateDisplayName(file.name),
-              category: categoryId,
-              created_at: file.created_at || Date.now()
-            }
-            delete fileToSave.openCount
-            delete fileToSave.displayName
-            allFiles.push(fileToSave)
+            allFiles.push(toDbFile(file, categoryId))
           }
         }
         await invoke('save_files_to_db', { files: allFiles })
@@ -214,6 +248,53 @@ export function useFiles() {
     }
   }
 
+  // 新增单个文件，Tauri 环境下只写入这一条记录
+  const addFile = async (fileInfo) => {
+    const categoryId = fileInfo.category || currentCategory.value
+    if (isTauri()) {
+      const saved = await invoke('add_file', { file: toDbFile(fileInfo, categoryId) })
+      filesByCategory.value[categoryId].push(fromDbFile(saved, categoryId))
+    } else {
+      filesByCategory.value[categoryId].push(fileInfo)
+    }
+  }
+
+  // 添加 URL 启动项，标题为空时由后端从本地 HTML 文件读取或使用主机名
+  // 返回 false 表示当前分类中已存在该 URL
+  const addUrl = async (url, title) => {
+    if (!filesByCategory.value[currentCategory.value]) {
+      filesByCategory.value[currentCategory.value] = []
+    }
+
+    const fileInfo = await invoke('get_url_info', { url, title: title || null, favicon: null })
+    if (filesByCategory.value[currentCategory.value].some(f => f.path === fileInfo.path)) {
+      return false
+    }
+
+    fileInfo.id = Date.now() + Math.random().toString(36).substr(2, 9)
+    if (!fileInfo.icon || fileInfo.icon === '') {
+      fileInfo.icon = await getFileIcon({ name: fileInfo.name })
+    }
+    fileInfo.category = currentCategory.value
+    fileInfo.displayName = fileInfo.display_name
+    await addFile(fileInfo)
+    return true
+  }
+
+  // 添加命令启动项，标题为空时使用命令的第一行
+  const addCommand = async (command, title) => {
+    if (!filesByCategory.value[currentCategory.value]) {
+      filesByCategory.value[currentCategory.value] = []
+    }
+
+    const fileInfo = await invoke('get_command_info', { command, title: title || null })
+    fileInfo.id = Date.now() + Math.random().toString(36).substr(2, 9)
+    fileInfo.category = currentCategory.value
+    fileInfo.displayName = fileInfo.display_name
+    await addFile(fileInfo)
+    return fileInfo
+  }
+
   const loadFiles = async () => {
     try {
       if (!isTauri()) {
@@ -265,7 +346,7 @@ export function useFiles() {
         currentCategory.value = customCategories.value[0].id
       }
 
-      const loaded = await invoke('load_files_from_db')
+      const loaded = await invoke('load_files_from_db', { sort: 'frecency' })
       const organizedFiles = {}
       
       // Ensure all custom categories are present in filesByCategory
@@ -284,16 +365,7 @@ export function useFiles() {
           const targetId = organizedFiles[categoryId] ? categoryId : customCategories.value[0].id
           
           // 转换字段名并保留 category 属性 (存储的是 ID)
-          const { open_count, display_name, created_at, ...otherFields } = file
-          const fileWithFormattedFields = {
-            ...otherFields,
-            openCount: open_count || 0,
-            displayName: display_name || generateDisplayName(file.name),
-            category: targetId,
-            // 如果数据库里没时间（老数据），加载时补全，避免每次保存都变
-            created_at: created_at || Date.now() 
-          }
-          organizedFiles[targetId].push(fileWithFormattedFields)
+          organizedFiles[targetId].push(fromDbFile(file, targetId))
         }
       }
       
@@ -350,15 +422,19 @@ export function useFiles() {
       }
       
       if (!filesByCategory.value[currentCategory.value].some(f => f.path === fileInfo.path)) {
-        filesByCategory.value[currentCategory.value].push(fileInfo);
-        addedCount++;
+        try {
+          await addFile(fileInfo);
+          addedCount++;
+        } catch (error) {
+          console.error(`Failed to add file ${fileInfo.path}:`, error);
+        }
       } else {
         existingCount++;
         console.warn(`File already exists: ${fileInfo.path}`);
       }
     }
     
-    if (addedCount > 0) {
+    if (addedCount > 0 && !isTauri()) {
       await saveFiles();
     }
     
@@ -369,35 +445,119 @@ export function useFiles() {
     };
   }
 
-  const deleteFile = (id) => {
+  // 删除的文件移到回收站，返回是否可以撤销
+  const deleteFile = async (id) => {
     filesByCategory.value[currentCategory.value] = filesByCategory.value[currentCategory.value].filter(file => file.id !== id)
-    saveFiles()
+    try {
+      if (isTauri()) {
+        await invoke('delete_files', { ids: [id] })
+        return true
+      } else {
+        await saveFiles()
+      }
+    } catch (error) {
+      console.error('Failed to delete file:', error)
+    }
+    return false
   }
 
-  const openFile = async (file) => {
+  // 从回收站恢复项目或分类，原分类可能一起恢复，恢复后重新加载
+  const restoreItems = async (ids) => {
+    const summary = await invoke('restore_items', { ids })
+    await loadFiles()
+    return summary
+  }
+
+  const restoreCategories = async (ids) => {
+    const summary = await invoke('restore_categories', { ids })
+    await loadFiles()
+    return summary
+  }
+
+  // 撤销或重做最近一次编辑，返回对应的编辑记录，没有可以撤销或重做的编辑时返回 null
+  // 分类可能被一起撤销，当前分类不存在时切换到列表中的第一个分类
+  const applyJournal = async (command) => {
+    const entry = await invoke(command)
+    if (entry) {
+      await loadFiles()
+      if (!customCategories.value.some(c => c.id === currentCategory.value)) {
+        currentCategory.value = customCategories.value[0]?.id || 'main'
+      }
+    }
+    return entry
+  }
+
+  const undo = () => applyJournal('undo')
+
+  const redo = () => applyJournal('redo')
+
+  const moveFilesToCategory = async (ids, categoryId) => {
+    if (!ids || ids.length === 0 || !categoryId) return
+    const source = filesByCategory.value[currentCategory.value] || []
+    const moving = source.filter(f => ids.includes(f.id))
+    filesByCategory.value[currentCategory.value] = source.filter(f => !ids.includes(f.id))
+    if (!filesByCategory.value[categoryId]) {
+      filesByCategory.value[categoryId] = []
+    }
+    filesByCategory.value[categoryId].push(...moving.map(f => ({ ...f, category: categoryId })))
     try {
-      console.log(`Opening file: ${file.path}`)
-      if (window.__TAURI_INTERNALS__?.invoke) {
-        await invoke('open_path', { path: file.path })
+      if (isTauri()) {
+        await invoke('move_files_to_category', { ids, category: categoryId })
       } else {
-        window.open(file.path, '_blank')
+        await saveFiles()
       }
-      
-      // 增加打开次数
+    } catch (error) {
+      console.error('Failed to move files:', error)
+    }
+  }
+
+  const reorderFiles = async (categoryId, ids) => {
+    const files = filesByCategory.value[categoryId] || []
+    filesByCategory.value[categoryId] = ids
+      .map((id, index) => {
+        const file = files.find(f => f.id === id)
+        return file ? { ...file, sort_order: index } : null
+      })
+      .filter(Boolean)
+    try {
+      if (isTauri()) {
+        await invoke('reorder_files', { category: categoryId, ids })
+      } else {
+        await saveFiles()
+      }
+    } catch (error) {
+      console.error('Failed to reorder files:', error)
+    }
+  }
+
+  const openFile = async (file) => {
+    try {
+      console.log(`Opening file: ${file.path}`)
       const category = file.category || 'main'
       const filesInCategory = filesByCategory.value[category]
-      if (filesInCategory) {
-        const fileIndex = filesInCategory.findIndex(f => f.id === file.id)
+      const fileIndex = filesInCategory ? filesInCategory.findIndex(f => f.id === file.id) : -1
+
+      if (isTauri()) {
+        // 后端负责启动、累加打开次数并记录启动历史
+        const openCount = await invoke('launch_item', { id: file.id })
         if (fileIndex !== -1) {
-          const updatedFile = filesInCategory[fileIndex]
-          updatedFile.openCount = (updatedFile.openCount || 0) + 1
-          filesInCategory[fileIndex] = updatedFile
-          await saveFiles()
+          filesInCategory[fileIndex].openCount = openCount
         }
+        return
+      }
+
+      window.open(file.path, '_blank')
+
+      // 增加打开次数
+      if (fileIndex !== -1) {
+        const updatedFile = filesInCategory[fileIndex]
+        updatedFile.openCount = (updatedFile.openCount || 0) + 1
+        filesInCategory[fileIndex] = updatedFile
+        await saveFiles()
       }
     } catch (error) {
       console.error('Failed to open file:', error)
-      alert(`打开文件失败: ${error.message}`)
+      alert(`打开文件失败: ${error.message || error}`)
     }
   }
 
@@ -421,15 +581,31 @@ export function useFiles() {
                 fileInfo.category = currentCategory.value
                 fileInfo.displayName = fileInfo.display_name || generateDisplayName(fileInfo.name)
                 fileInfo.created_at = fileInfo.created_at || Date.now()
-                filesByCategory.value[currentCategory.value].push(fileInfo)
+                await addFile(fileInfo)
               } catch (error) {
                 console.error(`Failed to process path ${path}:`, error)
               }
             }
           }
-          await saveFiles()
         }
       })
+
+      // 启动项的目标被重命名、修改或删除后重新加载
+      await listen('files-changed', async () => {
+        await loadFiles()
+      })
+
+      // 启动的程序很快以非零状态退出时提示错误输出
+      await listen('launch-failed', (event) => {
+        const { display_name, path, error, stderr_tail } = event.payload
+        const lines = (stderr_tail || '').split('\n').filter(Boolean)
+        const detail = lines.length > 0 ? `\n${lines[lines.length - 1]}` : ''
+        console.error(`Launch of ${path} failed: ${error}`, stderr_tail)
+        ElMessage.error({
+          message: `${display_name || path} 启动失败：${error}${detail}`,
+          duration: 5000,
+        })
+      })
     }
   }
 
@@ -446,8 +622,17 @@ export function useFiles() {
     loadFiles,
     processFiles,
     deleteFile,
+    restoreItems,
+    restoreCategories,
+    undo,
+    redo,
+    moveFilesToCategory,
+    reorderFiles,
     openFile,
     setupTauriListeners,
+    setCategoryFolder,
+    addUrl,
+    addCommand,
     saveFiles,
     sortMethod,
     sortOrder
@@ -7,6 +7,7 @@ const defaultSettings = {
     minimizeToTray: true,
     hideTaskbar: false,
     language: 'zh-CN',
+    trashRetentionDays: 30, // 回收站中的项目保留的天数
   },
   appearance: {
     theme: 'light',
@@ -0,0 +1,25 @@
+// 后端自定义协议 oops:// 的地址转换
+// 数据库中的图标引用为 oops://icon/<hash>，WebView2 只能通过 http://oops.localhost 访问自定义协议
+const ASSET_PREFIX = 'oops://'
+
+const isWindows = navigator.userAgent.includes('Windows')
+
+// 是否为后端图标缓存的引用
+export const isAssetRef = (value) => typeof value === 'string' && value.startsWith(ASSET_PREFIX)
+
+// 是否可以作为图片显示（base64 图标或图标引用），否则按 emoji 显示
+export const isImageIcon = (icon) =>
+  typeof icon === 'string' && (icon.startsWith('data:image/') || isAssetRef(icon))
+
+// 将引用转换为 webview 可以加载的地址
+export const assetUrl = (value) => {
+  if (!isAssetRef(value)) return value
+  const path = value.slice(ASSET_PREFIX.length)
+  return isWindows ? `http://oops.localhost/${path}` : `oops://localhost/${path}`
+}
+
+// 文件预览地址：图片为 PNG 缩略图，文本文件为文本内容
+export const previewUrl = (fileId, size) => {
+  const query = size ? `?size=${size}` : ''
+  return assetUrl(`${ASSET_PREFIX}preview/${encodeURIComponent(fileId)}${query}`)
+}